@@ -0,0 +1,103 @@
+//! Reference connector plugin
+//!
+//! Speaks the Loupe plugin protocol (newline-delimited JSON-RPC 2.0 over
+//! stdio, see `docs/PLUGINS.md`). Every query returns a single row echoing
+//! the query text and its bound parameters. Queries containing `fail` return
+//! a query error, and `sleep` makes the plugin stall past any timeout.
+//! Requests after `initialize` are handled on their own threads, so a
+//! sleeping query doesn't hold up the others.
+//!
+//! Register it with:
+//!
+//! ```json
+//! {"echo": {"command": "target/debug/examples/echo_plugin"}}
+//! ```
+
+use serde_json::{Value, json};
+use std::io::{BufRead, Write};
+use std::sync::Arc;
+
+fn main() {
+    let stdin = std::io::stdin();
+    let mut connection_string = Arc::new(String::new());
+
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else { break };
+        let Ok(request) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+
+        // The host waits for `initialize` before sending anything else
+        if request["method"] == "initialize" {
+            let params = &request["params"];
+            let outcome = if params["protocol_version"] != json!(1) {
+                Err((-32004, "unsupported protocol version".to_string()))
+            } else {
+                let value = params["connection_string"].as_str().unwrap_or_default();
+                connection_string = Arc::new(value.to_string());
+                Ok(json!({"name": "echo"}))
+            };
+            respond(&request["id"], outcome);
+            continue;
+        }
+
+        let connection_string = connection_string.clone();
+        std::thread::spawn(move || respond(&request["id"], handle(&connection_string, &request)));
+    }
+}
+
+fn handle(connection_string: &str, request: &Value) -> Result<Value, (i64, String)> {
+    let params = &request["params"];
+    match request["method"].as_str().unwrap_or_default() {
+        "test_connection" => Ok(json!({})),
+        "execute" => execute(connection_string, params),
+        "get_schema" => Ok(json!([{
+            "schema": "echo",
+            "name": "echo",
+            "columns": [
+                {"name": "query", "data_type": "text", "is_nullable": false},
+                {"name": "params", "data_type": "json", "is_nullable": false}
+            ]
+        }])),
+        _ => Err((-32601, "method not found".to_string())),
+    }
+}
+
+/// Write one response line; the stdout lock keeps concurrent replies whole
+fn respond(id: &Value, outcome: Result<Value, (i64, String)>) {
+    let response = match outcome {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err((code, message)) => {
+            json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+        }
+    };
+    let mut stdout = std::io::stdout().lock();
+    if writeln!(stdout, "{}", response).and_then(|_| stdout.flush()).is_err() {
+        std::process::exit(0);
+    }
+}
+
+fn execute(connection_string: &str, params: &Value) -> Result<Value, (i64, String)> {
+    let sql = params["sql"].as_str().unwrap_or_default();
+    if sql.contains("fail") {
+        return Err((-32003, format!("echo refused query: {}", sql)));
+    }
+    if sql.contains("sleep") {
+        std::thread::sleep(std::time::Duration::from_secs(30));
+    }
+
+    // Always three rows, regardless of max_rows, so callers must truncate
+    let rows: Vec<Value> = (0..3)
+        .map(|i| json!([sql, params["params"], connection_string, i]))
+        .collect();
+
+    Ok(json!({
+        "columns": [
+            {"name": "query", "data_type": "text"},
+            {"name": "params", "data_type": "json"},
+            {"name": "connection", "data_type": "text"},
+            {"name": "n", "data_type": "int8"}
+        ],
+        "rows": rows
+    }))
+}
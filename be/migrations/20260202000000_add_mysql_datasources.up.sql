@@ -0,0 +1,9 @@
+-- Allow MySQL/MariaDB datasources alongside Postgres
+
+ALTER TABLE datasources
+DROP CONSTRAINT IF EXISTS datasources_ds_type_check;
+
+ALTER TABLE datasources
+ADD CONSTRAINT datasources_ds_type_check CHECK (ds_type IN ('postgres', 'mysql'));
+
+COMMENT ON COLUMN datasources.ds_type IS 'Connector type: postgres, mysql';
@@ -0,0 +1,12 @@
+-- Revert to Postgres/MySQL datasources
+-- SQLite datasources (and their queries/runs via cascade) must be removed first
+
+DELETE FROM datasources WHERE ds_type = 'sqlite';
+
+ALTER TABLE datasources
+DROP CONSTRAINT IF EXISTS datasources_ds_type_check;
+
+ALTER TABLE datasources
+ADD CONSTRAINT datasources_ds_type_check CHECK (ds_type IN ('postgres', 'mysql'));
+
+COMMENT ON COLUMN datasources.ds_type IS 'Connector type: postgres, mysql';
@@ -0,0 +1,9 @@
+-- Allow file-backed SQLite datasources
+
+ALTER TABLE datasources
+DROP CONSTRAINT IF EXISTS datasources_ds_type_check;
+
+ALTER TABLE datasources
+ADD CONSTRAINT datasources_ds_type_check CHECK (ds_type IN ('postgres', 'mysql', 'sqlite'));
+
+COMMENT ON COLUMN datasources.ds_type IS 'Connector type: postgres, mysql, sqlite';
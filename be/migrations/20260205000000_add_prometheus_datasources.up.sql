@@ -0,0 +1,9 @@
+-- Allow Prometheus datasources
+
+ALTER TABLE datasources
+DROP CONSTRAINT IF EXISTS datasources_ds_type_check;
+
+ALTER TABLE datasources
+ADD CONSTRAINT datasources_ds_type_check CHECK (ds_type IN ('postgres', 'mysql', 'sqlite', 'http', 'prometheus'));
+
+COMMENT ON COLUMN datasources.ds_type IS 'Connector type: postgres, mysql, sqlite, http, prometheus';
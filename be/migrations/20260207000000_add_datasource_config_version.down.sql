@@ -0,0 +1,4 @@
+-- Remove datasource config version
+
+ALTER TABLE datasources
+DROP COLUMN IF EXISTS config_version;
@@ -0,0 +1,6 @@
+-- Track connection config changes so runners can drop stale pooled connectors
+
+ALTER TABLE datasources
+ADD COLUMN config_version INTEGER NOT NULL DEFAULT 1;
+
+COMMENT ON COLUMN datasources.config_version IS 'Incremented whenever the connection string changes; part of the connector cache key';
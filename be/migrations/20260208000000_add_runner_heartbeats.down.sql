@@ -0,0 +1,8 @@
+-- Remove runner heartbeats and run leases
+
+DROP INDEX IF EXISTS idx_runs_lease_expires_at;
+
+ALTER TABLE runs
+DROP COLUMN IF EXISTS lease_expires_at;
+
+DROP TABLE IF EXISTS runners;
@@ -0,0 +1,21 @@
+-- Track live runners and lease claimed runs so crashed runners' work is reclaimed
+
+CREATE TABLE runners (
+    id TEXT PRIMARY KEY,
+    hostname TEXT,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_heartbeat_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE runs
+ADD COLUMN lease_expires_at TIMESTAMPTZ NULL;
+
+-- Runs already executing get one lease period for their runner to start heartbeating
+UPDATE runs SET lease_expires_at = NOW() + INTERVAL '60 seconds' WHERE status = 'running';
+
+-- Index for the reaper's scan of expired leases
+CREATE INDEX idx_runs_lease_expires_at ON runs (lease_expires_at)
+WHERE status = 'running';
+
+COMMENT ON TABLE runners IS 'Runner instances and their last heartbeat';
+COMMENT ON COLUMN runs.lease_expires_at IS 'When the claiming runner''s lease lapses unless renewed by a heartbeat (NULL if not running)';
@@ -0,0 +1,9 @@
+-- Remove per-datasource concurrency caps
+
+DROP INDEX IF EXISTS idx_runs_datasource_running;
+
+ALTER TABLE organizations
+DROP COLUMN IF EXISTS last_run_claimed_at;
+
+ALTER TABLE datasources
+DROP COLUMN IF EXISTS max_concurrent_runs;
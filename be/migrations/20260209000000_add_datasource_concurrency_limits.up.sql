@@ -0,0 +1,14 @@
+-- Per-datasource concurrency caps, and the bookkeeping for round-robin claiming between orgs
+
+ALTER TABLE datasources
+ADD COLUMN max_concurrent_runs INTEGER NULL CHECK (max_concurrent_runs IS NULL OR max_concurrent_runs > 0);
+
+ALTER TABLE organizations
+ADD COLUMN last_run_claimed_at TIMESTAMPTZ NULL;
+
+-- Index for counting a datasource's running runs at claim time
+CREATE INDEX idx_runs_datasource_running ON runs (datasource_id)
+WHERE status = 'running';
+
+COMMENT ON COLUMN datasources.max_concurrent_runs IS 'Maximum runs executing against this datasource across all runners (NULL for no limit)';
+COMMENT ON COLUMN organizations.last_run_claimed_at IS 'When a runner last claimed one of this org''s runs; orgs served longest ago are claimed from first';
@@ -0,0 +1,3 @@
+-- Remove shared query slots
+
+DROP TABLE IF EXISTS query_slots;
@@ -0,0 +1,15 @@
+-- Query execution slots shared by all runners, so concurrency limits hold across processes
+
+CREATE TABLE query_slots (
+    id UUID PRIMARY KEY,
+    org_id UUID NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    holder TEXT NOT NULL,
+    acquired_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_query_slots_org_id ON query_slots (org_id);
+CREATE INDEX idx_query_slots_holder ON query_slots (holder);
+
+COMMENT ON TABLE query_slots IS 'Held query slots; a slot whose holder stops renewing it expires and is freed';
+COMMENT ON COLUMN query_slots.holder IS 'Runner holding the slot; its heartbeats extend expires_at';
@@ -0,0 +1,5 @@
+-- Remove run throttling
+
+ALTER TABLE runs
+DROP COLUMN IF EXISTS first_throttled_at,
+DROP COLUMN IF EXISTS throttled_until;
@@ -0,0 +1,8 @@
+-- Runs turned away by the query limiter wait in the queue instead of failing
+
+ALTER TABLE runs
+ADD COLUMN throttled_until TIMESTAMPTZ NULL,
+ADD COLUMN first_throttled_at TIMESTAMPTZ NULL;
+
+COMMENT ON COLUMN runs.throttled_until IS 'Queued run is not claimed before this time (set when the query limiter was full)';
+COMMENT ON COLUMN runs.first_throttled_at IS 'When the query limiter first turned this run away; it fails once it has waited too long';
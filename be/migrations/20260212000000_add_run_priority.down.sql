@@ -0,0 +1,6 @@
+-- Remove run priorities
+
+DROP INDEX IF EXISTS idx_runs_queued_priority;
+
+ALTER TABLE runs
+DROP COLUMN IF EXISTS priority;
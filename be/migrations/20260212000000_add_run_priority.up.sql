@@ -0,0 +1,9 @@
+-- Scheduling lanes so interactive runs aren't stuck behind scheduled refreshes
+
+ALTER TABLE runs
+ADD COLUMN priority TEXT NOT NULL DEFAULT 'interactive'
+    CHECK (priority IN ('interactive', 'dashboard', 'scheduled', 'backfill'));
+
+CREATE INDEX idx_runs_queued_priority ON runs (priority, created_at) WHERE status = 'queued';
+
+COMMENT ON COLUMN runs.priority IS 'Scheduling lane: interactive, dashboard, scheduled or backfill; waiting runs are promoted over time';
@@ -0,0 +1,10 @@
+-- Remove retry policies
+
+ALTER TABLE runs
+DROP COLUMN IF EXISTS retry_policy;
+
+ALTER TABLE schedules
+DROP COLUMN IF EXISTS retry_policy;
+
+ALTER TABLE queries
+DROP COLUMN IF EXISTS retry_policy;
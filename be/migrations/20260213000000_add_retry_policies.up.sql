@@ -0,0 +1,14 @@
+-- Configurable retry policies on queries and schedules, copied onto each run
+
+ALTER TABLE queries
+ADD COLUMN retry_policy JSONB NULL;
+
+ALTER TABLE schedules
+ADD COLUMN retry_policy JSONB NULL;
+
+ALTER TABLE runs
+ADD COLUMN retry_policy JSONB NOT NULL DEFAULT '{}'::jsonb;
+
+COMMENT ON COLUMN queries.retry_policy IS 'Retry policy for runs of this query (max_retries, base_delay_seconds, max_delay_seconds, jitter, retry_on); NULL uses the default';
+COMMENT ON COLUMN schedules.retry_policy IS 'Retry policy for runs of this schedule; NULL uses the query''s policy';
+COMMENT ON COLUMN runs.retry_policy IS 'Retry policy in effect for this run; missing fields take their defaults';
@@ -0,0 +1,9 @@
+-- Remove run failure replay columns
+
+DROP INDEX IF EXISTS idx_run_failures_datasource_id;
+
+ALTER TABLE run_failures
+DROP COLUMN IF EXISTS replayed_at,
+DROP COLUMN IF EXISTS replayed_run_id,
+DROP COLUMN IF EXISTS max_rows,
+DROP COLUMN IF EXISTS timeout_seconds;
@@ -0,0 +1,14 @@
+-- Keep what's needed to replay a dead-lettered run, and record replays
+
+ALTER TABLE run_failures
+ADD COLUMN timeout_seconds INTEGER NULL,
+ADD COLUMN max_rows INTEGER NULL,
+ADD COLUMN replayed_run_id UUID NULL,
+ADD COLUMN replayed_at TIMESTAMPTZ NULL;
+
+CREATE INDEX idx_run_failures_datasource_id ON run_failures(datasource_id);
+
+COMMENT ON COLUMN run_failures.timeout_seconds IS 'Timeout of the failed run; NULL for failures recorded before replay support (the query default is used)';
+COMMENT ON COLUMN run_failures.max_rows IS 'Row limit of the failed run; NULL for failures recorded before replay support';
+COMMENT ON COLUMN run_failures.replayed_run_id IS 'Run created by the most recent replay of this failure';
+COMMENT ON COLUMN run_failures.replayed_at IS 'When this failure was last replayed';
@@ -0,0 +1,14 @@
+-- Remove result size limits
+
+ALTER TABLE run_results
+DROP COLUMN IF EXISTS truncation_reason,
+DROP COLUMN IF EXISTS truncated;
+
+ALTER TABLE runs
+DROP COLUMN IF EXISTS max_bytes;
+
+ALTER TABLE queries
+DROP COLUMN IF EXISTS max_bytes;
+
+ALTER TABLE organizations
+DROP COLUMN IF EXISTS max_result_bytes;
@@ -0,0 +1,20 @@
+-- Result size limits (per query and run, capped per org) and why a stored result was cut short
+
+ALTER TABLE organizations
+ADD COLUMN max_result_bytes BIGINT NULL CHECK (max_result_bytes IS NULL OR max_result_bytes > 0);
+
+ALTER TABLE queries
+ADD COLUMN max_bytes BIGINT NULL CHECK (max_bytes IS NULL OR max_bytes > 0);
+
+ALTER TABLE runs
+ADD COLUMN max_bytes BIGINT NULL CHECK (max_bytes IS NULL OR max_bytes > 0);
+
+ALTER TABLE run_results
+ADD COLUMN truncated BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN truncation_reason TEXT NULL CHECK (truncation_reason IN ('max_rows', 'max_bytes'));
+
+COMMENT ON COLUMN organizations.max_result_bytes IS 'Ceiling on the serialized size of any run result in the org (NULL for only the runner-wide MAX_RESULT_BYTES)';
+COMMENT ON COLUMN queries.max_bytes IS 'Default serialized result size limit for runs of this query (NULL for the org ceiling)';
+COMMENT ON COLUMN runs.max_bytes IS 'Serialized result size limit, already capped by the org ceiling (NULL for the runner-wide MAX_RESULT_BYTES)';
+COMMENT ON COLUMN run_results.truncated IS 'Whether rows were left out because a limit was reached';
+COMMENT ON COLUMN run_results.truncation_reason IS 'Limit that cut the result short: max_rows or max_bytes';
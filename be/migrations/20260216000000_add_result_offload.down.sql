@@ -0,0 +1,10 @@
+-- Remove result offload (offloaded results can't be kept without their rows)
+
+DELETE FROM run_results WHERE rows IS NULL;
+
+ALTER TABLE run_results
+DROP CONSTRAINT IF EXISTS check_run_results_rows_stored;
+
+ALTER TABLE run_results
+DROP COLUMN IF EXISTS storage_key,
+ALTER COLUMN rows SET NOT NULL;
@@ -0,0 +1,11 @@
+-- Large result rows can live in object storage, with a pointer kept here
+
+ALTER TABLE run_results
+ALTER COLUMN rows DROP NOT NULL,
+ADD COLUMN storage_key TEXT NULL;
+
+ALTER TABLE run_results
+ADD CONSTRAINT check_run_results_rows_stored CHECK ((rows IS NULL) <> (storage_key IS NULL));
+
+COMMENT ON COLUMN run_results.rows IS 'Rows stored inline; NULL when they were offloaded to object storage';
+COMMENT ON COLUMN run_results.storage_key IS 'Object storage key of the serialized rows; NULL when they are stored inline';
@@ -0,0 +1,12 @@
+-- Remove result encodings (encoded results can't be read back as JSONB rows)
+
+DELETE FROM run_results WHERE encoding <> 'json';
+
+ALTER TABLE run_results DROP CONSTRAINT IF EXISTS check_run_results_encoding;
+ALTER TABLE run_results DROP CONSTRAINT IF EXISTS check_run_results_rows_stored;
+ALTER TABLE run_results
+ADD CONSTRAINT check_run_results_rows_stored CHECK ((rows IS NULL) <> (storage_key IS NULL));
+
+ALTER TABLE run_results
+DROP COLUMN IF EXISTS data,
+DROP COLUMN IF EXISTS encoding;
@@ -0,0 +1,13 @@
+-- Results can be stored as zstd-compressed columnar JSON instead of JSONB rows
+
+ALTER TABLE run_results
+ADD COLUMN encoding TEXT NOT NULL DEFAULT 'json' CHECK (encoding IN ('json', 'columnar_zstd')),
+ADD COLUMN data BYTEA NULL;
+
+ALTER TABLE run_results DROP CONSTRAINT check_run_results_rows_stored;
+ALTER TABLE run_results
+ADD CONSTRAINT check_run_results_rows_stored CHECK (num_nonnulls(rows, data, storage_key) = 1),
+ADD CONSTRAINT check_run_results_encoding CHECK ((rows IS NULL OR encoding = 'json') AND (data IS NULL OR encoding <> 'json'));
+
+COMMENT ON COLUMN run_results.encoding IS 'How the rows are serialized: json (array of row arrays) or columnar_zstd (zstd-compressed array of column arrays)';
+COMMENT ON COLUMN run_results.data IS 'Encoded rows stored inline for non-json encodings; NULL otherwise';
@@ -0,0 +1,7 @@
+-- Remove retention policies
+
+ALTER TABLE queries
+DROP COLUMN IF EXISTS retention_policy;
+
+ALTER TABLE organizations
+DROP COLUMN IF EXISTS retention_policy;
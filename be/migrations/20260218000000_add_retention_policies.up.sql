@@ -0,0 +1,10 @@
+-- Retention policies for run history, set per org and overridable per query
+
+ALTER TABLE organizations
+ADD COLUMN retention_policy JSONB NULL;
+
+ALTER TABLE queries
+ADD COLUMN retention_policy JSONB NULL;
+
+COMMENT ON COLUMN organizations.retention_policy IS 'Retention of results, runs and run failures (keep_days, keep_last, keep_dashboard_results); NULL uses the default';
+COMMENT ON COLUMN queries.retention_policy IS 'Retention policy for this query''s runs; NULL uses the org''s policy';
@@ -11,13 +11,15 @@ use argon2::{
     password_hash::{PasswordHasher, SaltString, rand_core::OsRng},
 };
 use loupe::models::OrgRole;
-use loupe::{load_env, init_tracing, CacheManager, Config, Database, JwtManager, Metrics};
+use loupe::{load_env, init_tracing, CacheManager, Config, ConnectorCache, ConnectorCacheConfig, Database, JwtManager, Metrics, ResultStore};
 use std::sync::Arc;
 
 pub struct AppState {
     pub db: Database,
     pub jwt: JwtManager,
     pub cache: CacheManager,
+    pub connectors: Arc<ConnectorCache>,
+    pub results: ResultStore,
 }
 
 #[actix_web::main]
@@ -83,11 +85,19 @@ async fn main() -> std::io::Result<()> {
         });
 
     let jwt = JwtManager::new(config.jwt.secret.clone(), config.jwt.expiration_hours as i64);
-    let state = Arc::new(AppState { db, jwt, cache });
 
     // Initialize metrics
     let metrics = Arc::new(Metrics::new().expect("Failed to create metrics registry"));
 
+    // Datasource connectors for connection tests and schema browsing
+    let connectors = Arc::new(ConnectorCache::new(ConnectorCacheConfig::from_env(), metrics.clone()));
+    connectors.spawn_sweeper();
+
+    // Reads results from Postgres or object storage, wherever runners put them
+    let results = ResultStore::from_env(db.clone()).expect("Failed to configure result storage");
+
+    let state = Arc::new(AppState { db, jwt, cache, connectors, results });
+
     tracing::info!("Starting Loupe API server at http://{}:{}", config.api.host, config.api.port);
     tracing::info!("Rate limiting: global 100 req/min/IP + endpoint-specific limits on auth and runs/execute");
     tracing::info!("Metrics endpoint: http://{}:{}/metrics", config.api.host, config.api.port);
@@ -1,13 +1,14 @@
 use crate::AppState;
 use crate::permissions::{get_user_context, require_permission, Permission};
-use actix_web::{HttpRequest, HttpResponse, web};
+use actix_web::{HttpRequest, HttpResponse, http::header, web};
 use loupe::Error;
-use loupe::connectors::{Connector, PostgresConnector};
+use loupe::connectors::{PluginRegistry, SqliteConnector};
 use loupe::filtering::{ListParams, SortableColumns};
 use loupe::models::{
     ConnectionTestResult, CreateDatasourceRequest, DatasourceResponse, DatasourceType,
-    UpdateDatasourceRequest,
+    UpdateDatasourceRequest, UploadDatasourceParams, UploadDatasourceResponse,
 };
+use loupe::uploads::{self, UploadFormat};
 use loupe::validation::validate_request;
 use loupe::PaginatedResponse;
 use std::sync::Arc;
@@ -18,6 +19,11 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         web::scope("/datasources")
             .route("", web::get().to(list_datasources))
             .route("", web::post().to(create_datasource))
+            .service(
+                web::resource("/upload")
+                    .app_data(web::PayloadConfig::new(uploads::max_upload_bytes()))
+                    .route(web::post().to(upload_datasource)),
+            )
             .route("/{id}", web::get().to(get_datasource))
             .route("/{id}", web::put().to(update_datasource))
             .route("/{id}", web::delete().to(delete_datasource))
@@ -91,17 +97,89 @@ async fn create_datasource(
     // Validate request
     validate_request(&*body)?;
 
+    // File-backed datasources must point inside the configured allow-list
+    if body.ds_type == DatasourceType::Sqlite {
+        SqliteConnector::validate_path(&body.connection_string)?;
+    }
+    if body.ds_type == DatasourceType::Plugin {
+        PluginRegistry::global().validate_connection_string(&body.connection_string)?;
+    }
+
     // In production, encrypt the connection string
     let encrypted = &body.connection_string; // TODO: actual encryption
 
     let datasource = state
         .db
-        .create_datasource(org_id, &body.name, body.ds_type, encrypted, user_id)
+        .create_datasource(
+            org_id,
+            &body.name,
+            body.ds_type,
+            encrypted,
+            user_id,
+            body.max_concurrent_runs,
+        )
         .await?;
 
     Ok(HttpResponse::Created().json(DatasourceResponse::from(datasource)))
 }
 
+async fn upload_datasource(
+    state: web::Data<Arc<AppState>>,
+    req: HttpRequest,
+    query: web::Query<UploadDatasourceParams>,
+    body: web::Bytes,
+) -> Result<HttpResponse, Error> {
+    let (user_id, org_id, role) = get_user_context(&state, &req).await?;
+    // Uploads carry no credentials, so editors may create them
+    require_permission(role, Permission::Editor)?;
+
+    validate_request(&*query)?;
+
+    let format = query
+        .format
+        .or_else(|| {
+            req.headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(UploadFormat::from_content_type)
+        })
+        .ok_or_else(|| Error::BadRequest("Specify format=csv|parquet or a matching Content-Type".to_string()))?;
+
+    if body.is_empty() {
+        return Err(Error::BadRequest("Uploaded file is empty".to_string()));
+    }
+
+    let table = uploads::table_name(query.table.as_deref().unwrap_or(&query.name));
+
+    // Parsing is CPU-bound, keep it off the async workers
+    let data = body.clone();
+    let parsed = tokio::task::spawn_blocking(move || uploads::parse_upload(format, data))
+        .await
+        .map_err(|e| Error::Internal(format!("Upload parsing failed: {}", e)))??;
+
+    let db_path = uploads::store_upload(org_id, format, &body, &table, &parsed).await?;
+    let conn_str = format!("sqlite://{}", db_path.display());
+
+    let datasource = match state
+        .db
+        .create_datasource(org_id, &query.name, DatasourceType::Sqlite, &conn_str, user_id, None)
+        .await
+    {
+        Ok(ds) => ds,
+        Err(e) => {
+            uploads::remove_upload_files(&db_path).await;
+            return Err(e);
+        }
+    };
+
+    Ok(HttpResponse::Created().json(UploadDatasourceResponse {
+        datasource: DatasourceResponse::from(datasource),
+        table,
+        row_count: parsed.rows.len(),
+        columns: parsed.columns,
+    }))
+}
+
 async fn get_datasource(
     state: web::Data<Arc<AppState>>,
     req: HttpRequest,
@@ -129,12 +207,29 @@ async fn update_datasource(
 
     let id = path.into_inner();
 
+    if let Some(conn_str) = body.connection_string.as_deref() {
+        let existing = state.db.get_datasource(id, org_id).await?;
+        if existing.ds_type == DatasourceType::Sqlite {
+            SqliteConnector::validate_path(conn_str)?;
+        }
+        if existing.ds_type == DatasourceType::Plugin {
+            PluginRegistry::global().validate_connection_string(conn_str)?;
+        }
+    }
+
     let encrypted = body.connection_string.as_deref(); // TODO: encryption
 
     let datasource = state
         .db
-        .update_datasource(id, org_id, body.name.as_deref(), encrypted)
+        .update_datasource(
+            id,
+            org_id,
+            body.name.as_deref(),
+            encrypted,
+            body.max_concurrent_runs,
+        )
         .await?;
+    state.connectors.invalidate(id);
 
     Ok(HttpResponse::Ok().json(DatasourceResponse::from(datasource)))
 }
@@ -148,7 +243,18 @@ async fn delete_datasource(
     require_permission(role, Permission::Admin)?;
 
     let id = path.into_inner();
-    state.db.delete_datasource(id, org_id).await?;
+    let datasource = state.db.get_datasource(id, org_id).await?;
+    let storage_keys = state.db.delete_datasource(id, org_id).await?;
+    state.connectors.invalidate(id);
+    state.results.delete_orphaned_objects(&storage_keys).await;
+
+    // Uploaded datasources own their files
+    if datasource.ds_type == DatasourceType::Sqlite
+        && let Some(path) = datasource.connection_string_encrypted.strip_prefix("sqlite://")
+    {
+        uploads::remove_upload_files(std::path::Path::new(path)).await;
+    }
+
     Ok(HttpResponse::NoContent().finish())
 }
 
@@ -163,29 +269,28 @@ async fn test_connection(
     let id = path.into_inner();
     let datasource = state.db.get_datasource(id, org_id).await?;
 
-    // Decrypt connection string (TODO: actual decryption)
-    let conn_str = &datasource.connection_string_encrypted;
-
-    match datasource.ds_type {
-        DatasourceType::Postgres => match PostgresConnector::new(conn_str).await {
-            Ok(connector) => match connector.test_connection().await {
-                Ok(latency) => Ok(HttpResponse::Ok().json(ConnectionTestResult {
-                    success: true,
-                    message: "Connection successful".to_string(),
-                    latency_ms: Some(latency.as_millis() as u64),
-                })),
-                Err(e) => Ok(HttpResponse::Ok().json(ConnectionTestResult {
+    match state.connectors.get(&datasource).await {
+        Ok(connector) => match connector.test_connection().await {
+            Ok(latency) => Ok(HttpResponse::Ok().json(ConnectionTestResult {
+                success: true,
+                message: "Connection successful".to_string(),
+                latency_ms: Some(latency.as_millis() as u64),
+            })),
+            Err(e) => {
+                // Reconnect from scratch on the next attempt
+                state.connectors.invalidate(id);
+                Ok(HttpResponse::Ok().json(ConnectionTestResult {
                     success: false,
                     message: e.to_string(),
                     latency_ms: None,
-                })),
-            },
-            Err(e) => Ok(HttpResponse::Ok().json(ConnectionTestResult {
-                success: false,
-                message: e.to_string(),
-                latency_ms: None,
-            })),
+                }))
+            }
         },
+        Err(e) => Ok(HttpResponse::Ok().json(ConnectionTestResult {
+            success: false,
+            message: e.to_string(),
+            latency_ms: None,
+        })),
     }
 }
 
@@ -200,13 +305,7 @@ async fn get_schema(
     let id = path.into_inner();
     let datasource = state.db.get_datasource(id, org_id).await?;
 
-    let conn_str = &datasource.connection_string_encrypted;
-
-    match datasource.ds_type {
-        DatasourceType::Postgres => {
-            let connector = PostgresConnector::new(conn_str).await?;
-            let schema = connector.get_schema().await?;
-            Ok(HttpResponse::Ok().json(schema))
-        }
-    }
+    let connector = state.connectors.get(&datasource).await?;
+    let schema = connector.get_schema().await?;
+    Ok(HttpResponse::Ok().json(schema))
 }
@@ -1,4 +1,5 @@
 use actix_web::{HttpResponse, web};
+use loupe::models::RunPriority;
 use loupe::{Error, Metrics};
 use std::sync::Arc;
 
@@ -19,12 +20,25 @@ async fn get_metrics(
     // Update database connection pool metrics before rendering
     let pool_stats = state.db.pool_stats();
     metrics.update_pool_metrics(&pool_stats);
+    state.connectors.record_pool_metrics();
 
     // Update job queue metrics
     let (pending_jobs, retry_jobs, dead_letter_jobs) = state.db.get_queue_stats().await?;
     metrics.job_queue_depth.set(pending_jobs);
+    let depths = state.db.get_queue_depth_by_priority().await?;
+    for priority in RunPriority::ALL {
+        let depth = depths
+            .iter()
+            .find(|(p, _)| *p == priority)
+            .map_or(0, |(_, depth)| *depth);
+        metrics
+            .job_queue_depth_by_priority
+            .with_label_values(&[priority.as_str()])
+            .set(depth);
+    }
     metrics.job_retry_queue_depth.set(retry_jobs);
     metrics.job_dead_letter_queue_size.set(dead_letter_jobs);
+    metrics.runners_active.set(state.db.count_active_runners().await?);
 
     // Update cache metrics
     if let Ok(cache_stats) = state.cache.stats().await {
@@ -6,6 +6,7 @@ mod health;
 mod metrics;
 mod organizations;
 mod queries;
+mod run_failures;
 mod runs;
 mod schedules;
 mod visualizations;
@@ -24,6 +25,7 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             .configure(datasources::configure)
             .configure(queries::configure)
             .configure(runs::configure)
+            .configure(run_failures::configure)
             .configure(dashboards::configure)
             .configure(visualizations::configure)
             .configure(schedules::configure)
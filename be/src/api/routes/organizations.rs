@@ -2,14 +2,19 @@ use crate::AppState;
 use crate::permissions::{get_user_context, require_permission, Permission};
 use actix_web::{HttpRequest, HttpResponse, web};
 use loupe::Error;
-use loupe::models::{OrgRole, UserResponse};
+use loupe::models::{
+    OrgRole, OrganizationSettingsResponse, UpdateOrganizationSettingsRequest, UserResponse,
+};
 use loupe::{PaginatedResponse, PaginationParams};
+use loupe::validation::validate_request;
 use std::sync::Arc;
 use uuid::Uuid;
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/organizations")
+            .route("/settings", web::get().to(get_organization_settings))
+            .route("/settings", web::put().to(update_organization_settings))
             .route("/users", web::get().to(list_organization_users))
             .route("/users/{user_id}/role", web::put().to(update_user_role))
             .route("/users/{user_id}", web::delete().to(remove_user_from_organization)),
@@ -101,3 +106,47 @@ async fn remove_user_from_organization(
 
     Ok(HttpResponse::NoContent().finish())
 }
+
+async fn get_organization_settings(
+    state: web::Data<Arc<AppState>>,
+    req: HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let (_, org_id, role) = get_user_context(&state, &req).await?;
+    require_permission(role, Permission::Viewer)?;
+
+    let org = state.db.get_organization(org_id).await?;
+    Ok(HttpResponse::Ok().json(OrganizationSettingsResponse::from(org)))
+}
+
+async fn update_organization_settings(
+    state: web::Data<Arc<AppState>>,
+    req: HttpRequest,
+    body: web::Json<UpdateOrganizationSettingsRequest>,
+) -> Result<HttpResponse, Error> {
+    let (_, org_id, role) = get_user_context(&state, &req).await?;
+    require_permission(role, Permission::Admin)?;
+
+    validate_request(&*body)?;
+
+    let org = match body.max_result_bytes {
+        Some(max_result_bytes) => {
+            state
+                .db
+                .set_organization_max_result_bytes(org_id, max_result_bytes)
+                .await?
+        }
+        None => state.db.get_organization(org_id).await?,
+    };
+
+    let org = match &body.retention_policy {
+        Some(policy) => {
+            state
+                .db
+                .set_organization_retention_policy(org_id, policy.as_ref())
+                .await?
+        }
+        None => org,
+    };
+
+    Ok(HttpResponse::Ok().json(OrganizationSettingsResponse::from(org)))
+}
@@ -1,7 +1,8 @@
 use crate::AppState;
 use crate::permissions::{get_user_context, require_permission, Permission};
 use actix_web::{HttpRequest, HttpResponse, web};
-use loupe::{Error, SqlValidator};
+use loupe::Error;
+use loupe::connectors::validate_query;
 use loupe::filtering::{ListParams, SortableColumns};
 use loupe::models::{
     CreateQueryRequest, ImportQueriesRequest, ImportQueriesResponse, QueryExport, QueryResponse,
@@ -95,11 +96,10 @@ async fn create_query(
     validate_request(&*body)?;
 
     // Verify datasource exists and belongs to org
-    state.db.get_datasource(body.datasource_id, org_id).await?;
+    let datasource = state.db.get_datasource(body.datasource_id, org_id).await?;
 
     // SECURITY: Validate SQL to prevent injection attacks
-    let validator = SqlValidator::new();
-    validator.validate(&body.sql)?;
+    validate_query(datasource.ds_type, &body.sql)?;
 
     let parameters = serde_json::to_value(&body.parameters).unwrap_or_default();
     let tags = serde_json::to_value(&body.tags).unwrap_or_default();
@@ -116,10 +116,22 @@ async fn create_query(
             &tags,
             body.timeout_seconds,
             body.max_rows,
+            body.max_bytes,
             user_id,
+            body.retry_policy.as_ref(),
         )
         .await?;
 
+    let query = match &body.retention_policy {
+        Some(policy) => {
+            state
+                .db
+                .set_query_retention_policy(query.id, org_id, Some(policy))
+                .await?
+        }
+        None => query,
+    };
+
     tracing::info!(
         query_id = %query.id,
         user_id = %user_id,
@@ -157,8 +169,9 @@ async fn update_query(
 
     // SECURITY: Validate SQL if it's being updated
     if let Some(ref sql) = body.sql {
-        let validator = SqlValidator::new();
-        validator.validate(sql)?;
+        let existing = state.db.get_query(id, org_id).await?;
+        let datasource = state.db.get_datasource(existing.datasource_id, org_id).await?;
+        validate_query(datasource.ds_type, sql)?;
     }
 
     let parameters = body
@@ -180,9 +193,21 @@ async fn update_query(
             tags.as_ref(),
             body.timeout_seconds,
             body.max_rows,
+            body.max_bytes,
+            body.retry_policy.as_ref().map(Option::as_ref),
         )
         .await?;
 
+    let query = match &body.retention_policy {
+        Some(policy) => {
+            state
+                .db
+                .set_query_retention_policy(id, org_id, policy.as_ref())
+                .await?
+        }
+        None => query,
+    };
+
     Ok(HttpResponse::Ok().json(QueryResponse::from(query)))
 }
 
@@ -195,7 +220,8 @@ async fn delete_query(
     require_permission(role, Permission::Editor)?;
 
     let id = path.into_inner();
-    state.db.delete_query(id, org_id).await?;
+    let storage_keys = state.db.delete_query(id, org_id).await?;
+    state.results.delete_orphaned_objects(&storage_keys).await;
     Ok(HttpResponse::NoContent().finish())
 }
 
@@ -244,7 +270,7 @@ async fn import_queries(
     require_permission(role, Permission::Editor)?;
 
     // Verify datasource exists and belongs to org
-    state.db.get_datasource(body.datasource_id, org_id).await?;
+    let datasource = state.db.get_datasource(body.datasource_id, org_id).await?;
 
     // Get existing query names for deduplication
     let existing_queries = state.db.list_queries(org_id).await?;
@@ -254,7 +280,6 @@ async fn import_queries(
     let mut imported = 0;
     let mut skipped = 0;
     let mut skipped_names = Vec::new();
-    let validator = SqlValidator::new();
 
     for query in &body.queries {
         // Check for duplicate
@@ -267,7 +292,7 @@ async fn import_queries(
         }
 
         // SECURITY: Validate SQL for each imported query
-        validator.validate(&query.sql)?;
+        validate_query(datasource.ds_type, &query.sql)?;
 
         let parameters = serde_json::to_value(&query.parameters).unwrap_or_default();
         let tags = serde_json::to_value(&query.tags).unwrap_or_default();
@@ -284,7 +309,9 @@ async fn import_queries(
                 &tags,
                 query.timeout_seconds,
                 query.max_rows,
+                None,
                 user_id,
+                None,
             )
             .await?;
 
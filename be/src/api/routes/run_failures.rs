@@ -0,0 +1,201 @@
+use crate::AppState;
+use crate::permissions::{get_user_context, require_permission, Permission};
+use actix_web::{HttpRequest, HttpResponse, web};
+use loupe::Error;
+use loupe::filtering::{ListParams, SortableColumns};
+use loupe::models::{
+    PurgeRunFailuresRequest, PurgeRunFailuresResponse, RetryPolicy, RunFailureResponse,
+    RunPriority, RunResponse,
+};
+use loupe::PaginatedResponse;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Runs that exhausted their retries (the dead-letter queue); admin only
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/run-failures")
+            .route("", web::get().to(list_run_failures))
+            .route("/purge", web::post().to(purge_run_failures))
+            .route("/{id}", web::get().to(get_run_failure))
+            .route("/{id}", web::delete().to(delete_run_failure))
+            .route("/{id}/replay", web::post().to(replay_run_failure)),
+    );
+}
+
+#[derive(serde::Deserialize)]
+pub struct ListRunFailuresQuery {
+    // Filter parameters
+    query_id: Option<Uuid>,
+    datasource_id: Option<Uuid>,
+
+    // Date range parameters (when the run was dead-lettered)
+    start_date: Option<chrono::DateTime<chrono::Utc>>,
+    end_date: Option<chrono::DateTime<chrono::Utc>>,
+
+    // Sort parameters
+    sort_by: Option<String>,
+    sort_direction: Option<String>,
+
+    // Pagination parameters
+    #[serde(default = "default_limit")]
+    limit: i64,
+    #[serde(default)]
+    offset: i64,
+}
+
+fn default_limit() -> i64 {
+    20
+}
+
+async fn list_run_failures(
+    state: web::Data<Arc<AppState>>,
+    req: HttpRequest,
+    query: web::Query<ListRunFailuresQuery>,
+) -> Result<HttpResponse, Error> {
+    let (_, org_id, role) = get_user_context(&state, &req).await?;
+    require_permission(role, Permission::Admin)?;
+
+    let lp = ListParams::parse(
+        query.limit, query.offset,
+        query.sort_by.clone(), query.sort_direction.clone(),
+        None, None,
+        SortableColumns::RUN_FAILURES, "created_at",
+    );
+
+    loupe::validation::validate_date_range(query.start_date, query.end_date)
+        .map_err(|e| Error::BadRequest(
+            e.message.map(|m| m.to_string()).unwrap_or_else(|| "Invalid date range".to_string())
+        ))?;
+
+    let (failures, total) = state
+        .db
+        .list_run_failures_paginated(
+            org_id,
+            query.query_id,
+            query.datasource_id,
+            query.start_date,
+            query.end_date,
+            &lp.sort_column,
+            &lp.sort_direction,
+            lp.pagination.limit,
+            lp.pagination.offset,
+        )
+        .await?;
+
+    let items: Vec<RunFailureResponse> = failures.into_iter().map(Into::into).collect();
+
+    let paginated = PaginatedResponse::new(items, total, &lp.pagination);
+    Ok(HttpResponse::Ok().json(paginated))
+}
+
+async fn get_run_failure(
+    state: web::Data<Arc<AppState>>,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, Error> {
+    let (_, org_id, role) = get_user_context(&state, &req).await?;
+    require_permission(role, Permission::Admin)?;
+
+    let failure = state.db.get_run_failure(path.into_inner(), org_id).await?;
+    Ok(HttpResponse::Ok().json(RunFailureResponse::from(failure)))
+}
+
+/// Queue the failed SQL again as a fresh run
+///
+/// The new run uses the already-bound SQL and parameters from the failure,
+/// so later edits to the query don't change what is replayed. The failure
+/// is kept and records the new run.
+async fn replay_run_failure(
+    state: web::Data<Arc<AppState>>,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, Error> {
+    let (user_id, org_id, role) = get_user_context(&state, &req).await?;
+    require_permission(role, Permission::Admin)?;
+
+    let failure = state.db.get_run_failure(path.into_inner(), org_id).await?;
+    let query = state.db.get_query(failure.query_id, org_id).await?;
+    let org = state.db.get_organization(org_id).await?;
+
+    let run = state
+        .db
+        .create_run(
+            org_id,
+            query.id,
+            failure.datasource_id,
+            &failure.executed_sql,
+            &failure.parameters,
+            failure.timeout_seconds.unwrap_or(query.timeout_seconds),
+            failure.max_rows.unwrap_or(query.max_rows),
+            org.cap_result_bytes(query.max_bytes),
+            user_id,
+            RunPriority::Interactive,
+            &RetryPolicy::resolve(&query, None),
+        )
+        .await?;
+
+    state
+        .db
+        .mark_run_failure_replayed(failure.id, org_id, run.id)
+        .await?;
+
+    tracing::info!(
+        run_failure_id = %failure.id,
+        run_id = %run.id,
+        user_id = %user_id,
+        "Dead-lettered run replayed"
+    );
+
+    Ok(HttpResponse::Created().json(RunResponse::from(run)))
+}
+
+async fn delete_run_failure(
+    state: web::Data<Arc<AppState>>,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, Error> {
+    let (_, org_id, role) = get_user_context(&state, &req).await?;
+    require_permission(role, Permission::Admin)?;
+
+    let id = path.into_inner();
+    let purged = state
+        .db
+        .purge_run_failures(org_id, Some(&[id]), None, None, None)
+        .await?;
+    if purged == 0 {
+        return Err(Error::NotFound("Run failure not found".into()));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+async fn purge_run_failures(
+    state: web::Data<Arc<AppState>>,
+    req: HttpRequest,
+    body: web::Json<PurgeRunFailuresRequest>,
+) -> Result<HttpResponse, Error> {
+    let (user_id, org_id, role) = get_user_context(&state, &req).await?;
+    require_permission(role, Permission::Admin)?;
+
+    // Refuse to empty the whole queue by accident
+    if body.is_empty() {
+        return Err(Error::BadRequest(
+            "At least one of ids, query_id, datasource_id or before is required".into(),
+        ));
+    }
+
+    let purged = state
+        .db
+        .purge_run_failures(
+            org_id,
+            body.ids.as_deref(),
+            body.query_id,
+            body.datasource_id,
+            body.before,
+        )
+        .await?;
+
+    tracing::info!(org_id = %org_id, user_id = %user_id, purged, "Dead-lettered runs purged");
+
+    Ok(HttpResponse::Ok().json(PurgeRunFailuresResponse { purged }))
+}
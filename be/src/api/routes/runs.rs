@@ -2,13 +2,16 @@ use crate::AppState;
 use crate::permissions::{get_user_context, require_permission, Permission};
 use actix_governor::{Governor, GovernorConfigBuilder};
 use actix_web::{HttpRequest, HttpResponse, web};
-use loupe::{Error, SqlValidator};
+use loupe::Error;
+use loupe::connectors::validate_query;
+use loupe::export::{self, ExportFormat};
 use loupe::filtering::{ListParams, SortableColumns};
 use loupe::models::{
-    CreateRunRequest, ExecuteAdHocRequest, ParamDef, RunResponse, RunResultResponse, RunStatus,
+    ColumnarRunResultResponse, CreateRunRequest, ExecuteAdHocRequest, ParamDef, ResultLayout,
+    RetryPolicy, RunPriority, RunResponse, RunResultParams, RunResultResponse, RunStatus,
 };
 use loupe::params::{ParamSchema, bind_params};
-use loupe::PaginatedResponse;
+use loupe::{PaginatedResponse, ResultView};
 use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -35,6 +38,7 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             )
             .route("/{id}", web::get().to(get_run))
             .route("/{id}/result", web::get().to(get_run_result))
+            .route("/{id}/result/export", web::get().to(export_run_result))
             .route("/{id}/cancel", web::post().to(cancel_run)),
     );
 }
@@ -132,6 +136,10 @@ async fn create_run(
     let timeout = body.timeout_seconds.unwrap_or(query.timeout_seconds);
     let max_rows = body.max_rows.unwrap_or(query.max_rows);
 
+    // Result size limit, never above the org ceiling
+    let max_bytes = positive_max_bytes(body.max_bytes)?.or(query.max_bytes);
+    let max_bytes = state.db.get_organization(org_id).await?.cap_result_bytes(max_bytes);
+
     // Parse query's parameter schema
     let param_defs: Vec<ParamDef> =
         serde_json::from_value(query.parameters.clone()).unwrap_or_default();
@@ -161,23 +169,8 @@ async fn create_run(
     } else {
         let bound = bind_params(&query.sql, &schema, &param_values)?;
         // Store the typed values in order for the runner
-        let values_json: Vec<serde_json::Value> = bound
-            .values
-            .iter()
-            .map(|tv| match tv {
-                loupe::TypedValue::String(s) => serde_json::json!({"type": "string", "value": s}),
-                loupe::TypedValue::Number(n) => serde_json::json!({"type": "number", "value": n}),
-                loupe::TypedValue::Integer(i) => serde_json::json!({"type": "integer", "value": i}),
-                loupe::TypedValue::Boolean(b) => serde_json::json!({"type": "boolean", "value": b}),
-                loupe::TypedValue::Date(d) => {
-                    serde_json::json!({"type": "date", "value": d.to_string()})
-                }
-                loupe::TypedValue::DateTime(dt) => {
-                    serde_json::json!({"type": "datetime", "value": dt.to_rfc3339()})
-                }
-                loupe::TypedValue::Null => serde_json::json!({"type": "null", "value": null}),
-            })
-            .collect();
+        let values_json: Vec<serde_json::Value> =
+            bound.values.iter().map(|tv| tv.to_tagged_json()).collect();
         (bound.sql, serde_json::json!(values_json))
     };
 
@@ -192,7 +185,10 @@ async fn create_run(
             &bound_values,
             timeout,
             max_rows,
+            max_bytes,
             user_id,
+            body.priority.unwrap_or(RunPriority::Interactive),
+            &RetryPolicy::resolve(&query, None),
         )
         .await?;
 
@@ -208,13 +204,15 @@ async fn execute_adhoc(
     // SECURITY: Only editors and admins can execute ad-hoc SQL
     require_permission(role, Permission::Editor)?;
 
+    // Verify datasource exists
+    let datasource = state.db.get_datasource(body.datasource_id, org_id).await?;
+
     // SECURITY: Validate SQL to prevent injection attacks
     // This is CRITICAL - validate BEFORE storing or executing
-    let validator = SqlValidator::new();
-    validator.validate(&body.sql)?;
+    validate_query(datasource.ds_type, &body.sql)?;
 
-    // Verify datasource exists
-    let datasource = state.db.get_datasource(body.datasource_id, org_id).await?;
+    let max_bytes = positive_max_bytes(body.max_bytes)?;
+    let max_bytes = state.db.get_organization(org_id).await?.cap_result_bytes(max_bytes);
 
     // For ad-hoc queries, no parameter schema is defined (raw SQL only)
     // Create an ephemeral query
@@ -230,7 +228,9 @@ async fn execute_adhoc(
             &serde_json::json!([]), // empty tags for adhoc
             body.timeout_seconds,
             body.max_rows,
+            None,
             user_id,
+            None,
         )
         .await?;
 
@@ -245,7 +245,10 @@ async fn execute_adhoc(
             &serde_json::json!([]), // Empty params array
             body.timeout_seconds,
             body.max_rows,
+            max_bytes,
             user_id,
+            RunPriority::Interactive,
+            &RetryPolicy::default(),
         )
         .await?;
 
@@ -258,6 +261,14 @@ async fn execute_adhoc(
     Ok(HttpResponse::Created().json(RunResponse::from(run)))
 }
 
+/// Reject a non-positive requested result size limit
+fn positive_max_bytes(max_bytes: Option<i64>) -> Result<Option<i64>, Error> {
+    match max_bytes {
+        Some(bytes) if bytes < 1 => Err(Error::BadRequest("max_bytes must be positive".to_string())),
+        max_bytes => Ok(max_bytes),
+    }
+}
+
 async fn get_run(
     state: web::Data<Arc<AppState>>,
     req: HttpRequest,
@@ -271,17 +282,87 @@ async fn get_run(
     Ok(HttpResponse::Ok().json(RunResponse::from(run)))
 }
 
+/// GET /api/v1/runs/{id}/result - Get a run's result
+///
+/// Rows come back as one array per row; `?format=columnar` returns one array
+/// per column instead. Any of `limit`, `offset`, `sort_by`, `sort_direction`,
+/// `columns` or `filters` returns a page of the stored rows instead, without
+/// re-running the query.
 async fn get_run_result(
     state: web::Data<Arc<AppState>>,
     req: HttpRequest,
     path: web::Path<Uuid>,
+    query: web::Query<RunResultParams>,
 ) -> Result<HttpResponse, Error> {
     let (_, _, role) = get_user_context(&state, &req).await?;
     require_permission(role, Permission::Viewer)?;
 
     let run_id = path.into_inner();
-    let result = state.db.get_run_result(run_id).await?;
-    Ok(HttpResponse::Ok().json(RunResultResponse::from(result)))
+    if query.is_paged() {
+        if query.format == ResultLayout::Columnar {
+            return Err(Error::BadRequest(
+                "format=columnar can't be combined with paging, sorting, filtering or columns"
+                    .to_string(),
+            ));
+        }
+        let result = RunResultResponse::from(state.results.get_run_result(run_id).await?);
+        let view = ResultView::parse(&query, &result.columns)?;
+        return Ok(HttpResponse::Ok().json(view.page(result)));
+    }
+
+    match query.format {
+        ResultLayout::Rows => {
+            let result = state.results.get_run_result(run_id).await?;
+            Ok(HttpResponse::Ok().json(RunResultResponse::from(result)))
+        }
+        ResultLayout::Columnar => {
+            let (result, data) = state.results.get_columnar_result(run_id).await?;
+            Ok(HttpResponse::Ok().json(ColumnarRunResultResponse::new(result, data)))
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct ExportRunResultQuery {
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+/// GET /api/v1/runs/{id}/result/export - Download a run's result as a file
+///
+/// `?format=` is one of csv (default), tsv, xlsx, parquet or jsonl; the file
+/// is named after the run's query. CSV, TSV and JSON Lines are streamed.
+async fn export_run_result(
+    state: web::Data<Arc<AppState>>,
+    req: HttpRequest,
+    path: web::Path<Uuid>,
+    query: web::Query<ExportRunResultQuery>,
+) -> Result<HttpResponse, Error> {
+    let (_, org_id, role) = get_user_context(&state, &req).await?;
+    require_permission(role, Permission::Viewer)?;
+
+    let run = state.db.get_run(path.into_inner(), org_id).await?;
+    let saved_query = state.db.get_query(run.query_id, org_id).await?;
+    let result = RunResultResponse::from(state.results.get_run_result(run.id).await?);
+
+    let format = query.format;
+    let mut response = HttpResponse::Ok();
+    response
+        .content_type(format.content_type())
+        .insert_header(export::content_disposition(&saved_query.name, format));
+
+    if format.is_streamed() {
+        let chunks = export::text_chunks(format, &result.columns, result.rows);
+        Ok(response.streaming(futures_util::stream::iter(chunks)))
+    } else {
+        // Building XLSX and Parquet is CPU-bound, keep it off the async workers
+        let body = tokio::task::spawn_blocking(move || {
+            export::encode(format, &result.columns, result.rows)
+        })
+        .await
+        .map_err(|e| Error::Internal(format!("Result export failed: {}", e)))??;
+        Ok(response.body(body))
+    }
 }
 
 /// POST /api/v1/runs/{id}/cancel - Cancel a running query
@@ -306,8 +387,13 @@ async fn cancel_run(
     // Check if run can be cancelled
     match run.status {
         RunStatus::Queued | RunStatus::Running => {
-            // Cancel the run
-            state.db.cancel_run(run_id).await?;
+            // Cancel the run; runners are notified and abort the query. It
+            // may have finished since we read it.
+            if state.db.cancel_run(run_id).await?.is_none() {
+                return Err(Error::BadRequest(
+                    "Run finished before it could be cancelled".to_string(),
+                ));
+            }
 
             tracing::info!(
                 run_id = %run_id,
@@ -3,7 +3,10 @@ use crate::permissions::{get_user_context, require_permission, Permission};
 use actix_web::{HttpRequest, HttpResponse, web};
 use loupe::Error;
 use loupe::filtering::{ListParams, SortableColumns};
-use loupe::models::{CreateScheduleRequest, ScheduleResponse, TriggerScheduleResponse, UpdateScheduleRequest};
+use loupe::models::{
+    CreateScheduleRequest, RetryPolicy, RunPriority, ScheduleResponse, TriggerScheduleResponse,
+    UpdateScheduleRequest,
+};
 use loupe::validation::validate_request;
 use loupe::PaginatedResponse;
 use std::sync::Arc;
@@ -122,6 +125,7 @@ async fn create_schedule(
             &tags,
             body.enabled,
             user_id,
+            body.retry_policy.as_ref(),
         )
         .await?;
 
@@ -157,6 +161,7 @@ async fn update_schedule(
             body.parameters.as_ref(),
             tags.as_ref(),
             body.enabled,
+            body.retry_policy.as_ref().map(Option::as_ref),
         )
         .await?;
 
@@ -218,10 +223,14 @@ async fn trigger_schedule(
     // Get the query
     let query = state.db.get_query(schedule.query_id, org_id).await?;
 
+    let retry_policy = RetryPolicy::resolve(&query, Some(&schedule));
+    let org = state.db.get_organization(org_id).await?;
+
     // Merge schedule parameters with query defaults
     let parameters = schedule.parameters;
 
-    // Create a run for this query with the schedule's parameters
+    // Create a run for this query with the schedule's parameters; a manual
+    // trigger has someone waiting on it, so it goes in the interactive lane
     let run = state
         .db
         .create_run(
@@ -232,7 +241,10 @@ async fn trigger_schedule(
             &parameters,
             query.timeout_seconds,
             query.max_rows,
+            org.cap_result_bytes(query.max_bytes),
             user_id,
+            RunPriority::Interactive,
+            &retry_policy,
         )
         .await?;
 
@@ -0,0 +1,344 @@
+/// Cache of live datasource connectors
+///
+/// Connectors are expensive to build (a fresh connection pool, or a plugin
+/// process), so the API and runner keep them warm between requests. Entries
+/// are keyed by datasource id and config version: bumping `config_version`
+/// (done by `update_datasource` when the connection string changes) makes the
+/// cached connector stale, so every process picks up the new config on its
+/// next lookup.
+use crate::connectors::{Connector, create_connector};
+use crate::error::Result;
+use crate::metrics::Metrics;
+use crate::models::Datasource;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Configuration for the connector cache
+#[derive(Debug, Clone)]
+pub struct ConnectorCacheConfig {
+    /// Connectors unused for this long are closed
+    pub idle_timeout: Duration,
+    /// Maximum number of cached connectors; the least recently used is closed first
+    pub max_entries: usize,
+    /// How often idle connectors are swept and pool metrics refreshed
+    pub sweep_interval: Duration,
+}
+
+impl Default for ConnectorCacheConfig {
+    fn default() -> Self {
+        Self {
+            idle_timeout: Duration::from_secs(300),
+            max_entries: 64,
+            sweep_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ConnectorCacheConfig {
+    /// Create config from environment variables
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        let idle_timeout = std::env::var("CONNECTOR_CACHE_IDLE_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(defaults.idle_timeout);
+
+        let max_entries = std::env::var("CONNECTOR_CACHE_MAX_ENTRIES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(defaults.max_entries);
+
+        Self {
+            idle_timeout,
+            max_entries,
+            ..defaults
+        }
+    }
+}
+
+struct CachedConnector {
+    config_version: i32,
+    connector: Arc<dyn Connector>,
+    last_used: Instant,
+}
+
+/// Shared cache of connectors, keyed by datasource id + config version
+pub struct ConnectorCache {
+    config: ConnectorCacheConfig,
+    metrics: Arc<Metrics>,
+    entries: Mutex<HashMap<Uuid, CachedConnector>>,
+}
+
+impl ConnectorCache {
+    pub fn new(config: ConnectorCacheConfig, metrics: Arc<Metrics>) -> Self {
+        Self {
+            config,
+            metrics,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the connector for a datasource, connecting on a miss
+    pub async fn get(&self, datasource: &Datasource) -> Result<Arc<dyn Connector>> {
+        if let Some(connector) = self.lookup(datasource.id, datasource.config_version) {
+            self.record_event("hit");
+            return Ok(connector);
+        }
+        self.record_event("miss");
+
+        // Connect without holding the lock; a slow datasource shouldn't block the others
+        let connector: Arc<dyn Connector> =
+            create_connector(datasource.ds_type, &datasource.connection_string_encrypted)
+                .await?
+                .into();
+
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(existing) = entries.get_mut(&datasource.id) {
+            if existing.config_version == datasource.config_version {
+                // Another task connected first; share its connector
+                existing.last_used = Instant::now();
+                return Ok(existing.connector.clone());
+            }
+            if existing.config_version > datasource.config_version {
+                // We were handed an outdated datasource row; don't cache it
+                return Ok(connector);
+            }
+        }
+
+        entries.insert(
+            datasource.id,
+            CachedConnector {
+                config_version: datasource.config_version,
+                connector: connector.clone(),
+                last_used: Instant::now(),
+            },
+        );
+        self.enforce_capacity(&mut entries);
+        self.metrics.connector_cache_entries.set(entries.len() as i64);
+
+        Ok(connector)
+    }
+
+    fn lookup(&self, datasource_id: Uuid, config_version: i32) -> Option<Arc<dyn Connector>> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(&datasource_id)?;
+        if entry.config_version != config_version {
+            return None;
+        }
+        entry.last_used = Instant::now();
+        Some(entry.connector.clone())
+    }
+
+    /// Close the least recently used connectors until under `max_entries`
+    fn enforce_capacity(&self, entries: &mut HashMap<Uuid, CachedConnector>) {
+        while entries.len() > self.config.max_entries {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(id, _)| *id)
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+            self.metrics.remove_connector_pool_metrics(oldest);
+            self.record_event("evicted");
+        }
+    }
+
+    /// Drop the cached connector for a datasource (after an update, delete, or
+    /// a connection failure)
+    ///
+    /// Runs already holding the connector finish on it; its pool closes once
+    /// they release it.
+    pub fn invalidate(&self, datasource_id: Uuid) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.remove(&datasource_id).is_some() {
+            self.metrics.remove_connector_pool_metrics(datasource_id);
+            self.record_event("invalidated");
+        }
+        self.metrics.connector_cache_entries.set(entries.len() as i64);
+    }
+
+    /// Close connectors that have been idle longer than `idle_timeout`
+    ///
+    /// Returns the number of connectors closed.
+    pub fn evict_idle(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let idle: Vec<Uuid> = entries
+            .iter()
+            .filter(|(_, entry)| entry.last_used.elapsed() >= self.config.idle_timeout)
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in &idle {
+            entries.remove(id);
+            self.metrics.remove_connector_pool_metrics(*id);
+            self.record_event("evicted");
+        }
+        self.metrics.connector_cache_entries.set(entries.len() as i64);
+
+        idle.len()
+    }
+
+    /// Refresh per-datasource pool gauges for every cached connector
+    pub fn record_pool_metrics(&self) {
+        let entries = self.entries.lock().unwrap();
+        for (id, entry) in entries.iter() {
+            if let Some(stats) = entry.connector.pool_stats() {
+                self.metrics.update_connector_pool_metrics(*id, &stats);
+            }
+        }
+        self.metrics.connector_cache_entries.set(entries.len() as i64);
+    }
+
+    /// Number of cached connectors
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Periodically evict idle connectors and refresh pool metrics
+    pub fn spawn_sweeper(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let cache = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(cache.config.sweep_interval);
+            loop {
+                interval.tick().await;
+                let evicted = cache.evict_idle();
+                if evicted > 0 {
+                    tracing::debug!("Closed {} idle datasource connectors", evicted);
+                }
+                cache.record_pool_metrics();
+            }
+        })
+    }
+
+    fn record_event(&self, event: &str) {
+        self.metrics
+            .connector_cache_events_total
+            .with_label_values(&[event])
+            .inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DatasourceType;
+    use chrono::Utc;
+
+    // HTTP connectors don't connect on creation, so they exercise the cache
+    // without a live datasource
+    fn datasource(id: Uuid, config_version: i32) -> Datasource {
+        Datasource {
+            id,
+            org_id: Uuid::new_v4(),
+            name: "api".to_string(),
+            ds_type: DatasourceType::Http,
+            connection_string_encrypted: "http://localhost:9".to_string(),
+            config_version,
+            max_concurrent_runs: None,
+            created_by: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn cache(config: ConnectorCacheConfig) -> ConnectorCache {
+        ConnectorCache::new(config, Arc::new(Metrics::new().unwrap()))
+    }
+
+    fn events(cache: &ConnectorCache, event: &str) -> u64 {
+        cache
+            .metrics
+            .connector_cache_events_total
+            .with_label_values(&[event])
+            .get()
+    }
+
+    #[tokio::test]
+    async fn test_reuses_connector_for_same_version() {
+        let cache = cache(ConnectorCacheConfig::default());
+        let ds = datasource(Uuid::new_v4(), 1);
+
+        let first = cache.get(&ds).await.unwrap();
+        let second = cache.get(&ds).await.unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(events(&cache, "miss"), 1);
+        assert_eq!(events(&cache, "hit"), 1);
+        assert_eq!(cache.metrics.connector_cache_entries.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_new_config_version_replaces_connector() {
+        let cache = cache(ConnectorCacheConfig::default());
+        let id = Uuid::new_v4();
+
+        let old = cache.get(&datasource(id, 1)).await.unwrap();
+        let new = cache.get(&datasource(id, 2)).await.unwrap();
+        assert!(!Arc::ptr_eq(&old, &new));
+        assert_eq!(cache.len(), 1);
+
+        // A stale row doesn't displace the newer connector
+        cache.get(&datasource(id, 1)).await.unwrap();
+        let again = cache.get(&datasource(id, 2)).await.unwrap();
+        assert!(Arc::ptr_eq(&new, &again));
+    }
+
+    #[tokio::test]
+    async fn test_invalidate() {
+        let cache = cache(ConnectorCacheConfig::default());
+        let ds = datasource(Uuid::new_v4(), 1);
+
+        let first = cache.get(&ds).await.unwrap();
+        cache.invalidate(ds.id);
+        assert!(cache.is_empty());
+
+        let second = cache.get(&ds).await.unwrap();
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert_eq!(events(&cache, "invalidated"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_evict_idle() {
+        let cache = cache(ConnectorCacheConfig {
+            idle_timeout: Duration::ZERO,
+            ..Default::default()
+        });
+        cache.get(&datasource(Uuid::new_v4(), 1)).await.unwrap();
+        cache.get(&datasource(Uuid::new_v4(), 1)).await.unwrap();
+
+        assert_eq!(cache.evict_idle(), 2);
+        assert!(cache.is_empty());
+        assert_eq!(cache.metrics.connector_cache_entries.get(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_capacity_evicts_least_recently_used() {
+        let cache = cache(ConnectorCacheConfig {
+            max_entries: 2,
+            ..Default::default()
+        });
+        let a = datasource(Uuid::new_v4(), 1);
+        let b = datasource(Uuid::new_v4(), 1);
+        let c = datasource(Uuid::new_v4(), 1);
+
+        cache.get(&a).await.unwrap();
+        cache.get(&b).await.unwrap();
+        cache.get(&a).await.unwrap();
+        cache.get(&c).await.unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.lookup(a.id, 1).is_some());
+        assert!(cache.lookup(b.id, 1).is_none());
+        assert_eq!(events(&cache, "evicted"), 1);
+    }
+}
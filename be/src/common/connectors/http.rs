@@ -0,0 +1,694 @@
+use super::{Connector, QueryOutput, TableSchema};
+use crate::error::{Error, ErrorCode, Result};
+use crate::models::ColumnDef;
+use crate::params::TypedValue;
+use async_trait::async_trait;
+use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+use regex::Regex;
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, Method, Url};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+/// Largest response body the connector will read (50 MiB)
+const MAX_RESPONSE_BYTES: usize = 50 * 1024 * 1024;
+
+static POSITIONAL_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$(\d+)").unwrap());
+
+/// Datasource configuration, stored as JSON in the connection string
+///
+/// ```json
+/// {
+///   "base_url": "https://ops.internal/api",
+///   "headers": {"Accept": "application/json"},
+///   "auth": {"type": "bearer", "token": "..."},
+///   "health_path": "/health"
+/// }
+/// ```
+///
+/// A bare URL is accepted as shorthand for `{"base_url": "<url>"}`.
+#[derive(Debug, Deserialize)]
+struct HttpConfig {
+    base_url: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    auth: Option<HttpAuth>,
+    #[serde(default)]
+    health_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum HttpAuth {
+    Bearer { token: String },
+    Basic { username: String, password: String },
+    Header { name: String, value: String },
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+}
+
+/// A saved query against an HTTP datasource, stored as JSON in the query text
+///
+/// ```json
+/// {
+///   "method": "GET",
+///   "path": "/teams/$team/incidents",
+///   "params": {"status": "$status"},
+///   "select": "$.data.items[*]"
+/// }
+/// ```
+///
+/// `$name` parameters are bound like SQL parameters and substituted into the
+/// path (percent-encoded), query string, headers and body. A body string that is
+/// exactly one placeholder is replaced by the typed JSON value.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpRequestSpec {
+    #[serde(default)]
+    method: HttpMethod,
+    path: String,
+    #[serde(default)]
+    params: BTreeMap<String, String>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    body: Option<Value>,
+    /// JSONPath-style selector for the rows (defaults to the whole document)
+    #[serde(default = "default_select")]
+    select: String,
+}
+
+fn default_select() -> String {
+    "$".to_string()
+}
+
+impl HttpRequestSpec {
+    /// Parse and validate a request spec from query text
+    pub fn parse(text: &str) -> Result<Self> {
+        let spec: Self = serde_json::from_str(text)
+            .map_err(|e| Error::BadRequest(format!("Invalid HTTP request spec: {}", e)))?;
+
+        if spec.path.contains("://") || spec.path.starts_with("//") {
+            return Err(Error::BadRequest(
+                "HTTP request path must be relative to the datasource base URL".to_string(),
+            ));
+        }
+        parse_selector(&spec.select)?;
+
+        Ok(spec)
+    }
+}
+
+/// Connector for internal REST/JSON endpoints
+pub struct HttpConnector {
+    client: Client,
+    base_url: Url,
+    headers: HeaderMap,
+    health_path: Option<String>,
+}
+
+impl HttpConnector {
+    pub fn new(connection_string: &str) -> Result<Self> {
+        let trimmed = connection_string.trim();
+        let config: HttpConfig = if trimmed.starts_with('{') {
+            serde_json::from_str(trimmed)
+                .map_err(|e| Error::BadRequest(format!("Invalid HTTP datasource config: {}", e)))?
+        } else {
+            HttpConfig {
+                base_url: trimmed.to_string(),
+                headers: BTreeMap::new(),
+                auth: None,
+                health_path: None,
+            }
+        };
+
+        let base_url = Url::parse(&config.base_url)
+            .map_err(|e| Error::BadRequest(format!("Invalid base URL: {}", e)))?;
+        if !matches!(base_url.scheme(), "http" | "https") {
+            return Err(Error::BadRequest(
+                "HTTP datasource base URL must use http or https".to_string(),
+            ));
+        }
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &config.headers {
+            insert_header(&mut headers, name, value)?;
+        }
+        match &config.auth {
+            Some(HttpAuth::Bearer { token }) => {
+                insert_header(&mut headers, AUTHORIZATION.as_str(), &format!("Bearer {}", token))?
+            }
+            Some(HttpAuth::Basic { username, password }) => {
+                use base64::{Engine as _, engine::general_purpose};
+                let encoded = general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+                insert_header(&mut headers, AUTHORIZATION.as_str(), &format!("Basic {}", encoded))?
+            }
+            Some(HttpAuth::Header { name, value }) => insert_header(&mut headers, name, value)?,
+            None => {}
+        }
+        for value in headers.values_mut() {
+            value.set_sensitive(true);
+        }
+
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| Error::Connection(format!("Failed to build HTTP client: {}", e), None))?;
+
+        Ok(Self {
+            client,
+            base_url,
+            headers,
+            health_path: config.health_path,
+        })
+    }
+
+    /// Join a relative path onto the base URL, refusing to leave its origin or prefix
+    fn resolve(&self, path: &str) -> Result<Url> {
+        let base = self.base_url.as_str().trim_end_matches('/');
+        let joined = format!("{}/{}", base, path.trim_start_matches('/'));
+        let url = Url::parse(&joined)
+            .map_err(|e| Error::BadRequest(format!("Invalid request URL: {}", e)))?;
+
+        // Compare whole segments, so /api/v1 doesn't let through /api/v10
+        let base_path = self.base_url.path().trim_end_matches('/');
+        let under_base = url
+            .path()
+            .strip_prefix(base_path)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'));
+        if url.origin() != self.base_url.origin() || !under_base {
+            return Err(Error::BadRequest(
+                "HTTP request must stay under the datasource base URL".to_string(),
+            ));
+        }
+
+        Ok(url)
+    }
+
+    /// GET a JSON document from a path under the base URL with datasource headers
+    pub(super) async fn get_json(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+        timeout: Duration,
+    ) -> Result<Value> {
+        let mut url = self.resolve(path)?;
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        self.send(Method::GET, url, self.headers.clone(), None, timeout)
+            .await
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        url: Url,
+        headers: HeaderMap,
+        body: Option<&Value>,
+        timeout: Duration,
+    ) -> Result<Value> {
+        let mut request = self
+            .client
+            .request(method, url)
+            .headers(headers)
+            .timeout(timeout);
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let mut response = request.send().await.map_err(|e| map_reqwest_error(e, timeout))?;
+
+        // Stop reading an oversized body instead of buffering all of it
+        if response
+            .content_length()
+            .is_some_and(|len| len > MAX_RESPONSE_BYTES as u64)
+        {
+            return Err(response_too_large());
+        }
+        let status = response.status();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| map_reqwest_error(e, timeout))?
+        {
+            if bytes.len() + chunk.len() > MAX_RESPONSE_BYTES {
+                return Err(response_too_large());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        if !status.is_success() {
+            let snippet: String = String::from_utf8_lossy(&bytes).chars().take(200).collect();
+            return Err(Error::QueryExecution(
+                format!("HTTP {}: {}", status, snippet),
+                Some(ErrorCode::Http(status.as_u16())),
+            ));
+        }
+
+        serde_json::from_slice(&bytes)
+            .map_err(|e| Error::QueryExecution(format!("Response is not valid JSON: {}", e), None))
+    }
+}
+
+#[async_trait]
+impl Connector for HttpConnector {
+    async fn test_connection(&self) -> Result<Duration> {
+        let start = Instant::now();
+        let url = self.resolve(self.health_path.as_deref().unwrap_or(""))?;
+
+        let response = self
+            .client
+            .get(url)
+            .headers(self.headers.clone())
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| {
+                Error::Connection(format!("Connection test failed: {}", e), Some(ErrorCode::Network))
+            })?;
+
+        if !response.status().is_success() {
+            return Err(Error::Connection(
+                format!("Connection test failed: HTTP {}", response.status()),
+                Some(ErrorCode::Http(response.status().as_u16())),
+            ));
+        }
+
+        Ok(start.elapsed())
+    }
+
+    async fn execute(
+        &self,
+        sql: &str,
+        timeout: Duration,
+        max_rows: usize,
+    ) -> Result<QueryOutput> {
+        self.execute_with_params(sql, &[], timeout, max_rows).await
+    }
+
+    async fn execute_with_params(
+        &self,
+        sql: &str,
+        params: &[TypedValue],
+        timeout: Duration,
+        max_rows: usize,
+    ) -> Result<QueryOutput> {
+        let start = Instant::now();
+        let spec = HttpRequestSpec::parse(sql)?;
+
+        let path = substitute(&spec.path, params, |v| {
+            utf8_percent_encode(&v.to_plain_string(), NON_ALPHANUMERIC).to_string()
+        })?;
+        let mut url = self.resolve(&path)?;
+        if !spec.params.is_empty() {
+            let mut pairs = url.query_pairs_mut();
+            for (key, value) in &spec.params {
+                pairs.append_pair(key, &substitute(value, params, TypedValue::to_plain_string)?);
+            }
+        }
+
+        // Datasource headers (including auth) win over per-query headers
+        let mut headers = HeaderMap::new();
+        for (name, value) in &spec.headers {
+            insert_header(&mut headers, name, &substitute(value, params, TypedValue::to_plain_string)?)?;
+        }
+        for (name, value) in &self.headers {
+            headers.insert(name.clone(), value.clone());
+        }
+
+        let body = spec.body.as_ref().map(|b| substitute_json(b, params)).transpose()?;
+        let method = match spec.method {
+            HttpMethod::Get => Method::GET,
+            HttpMethod::Post => Method::POST,
+        };
+
+        let document = self.send(method, url, headers, body.as_ref(), timeout).await?;
+
+        let selector = parse_selector(&spec.select)?;
+        let (columns, rows) = flatten_rows(&document, &selector, max_rows);
+        let row_count = rows.len();
+
+        Ok(QueryOutput {
+            columns,
+            rows,
+            row_count,
+            execution_time: start.elapsed(),
+        })
+    }
+
+    async fn get_schema(&self) -> Result<Vec<TableSchema>> {
+        // REST endpoints have no discoverable schema
+        Ok(vec![])
+    }
+}
+
+fn insert_header(headers: &mut HeaderMap, name: &str, value: &str) -> Result<()> {
+    let name = HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| Error::BadRequest(format!("Invalid header name: {}", name)))?;
+    let value = HeaderValue::from_str(value)
+        .map_err(|_| Error::BadRequest(format!("Invalid value for header {}", name)))?;
+    headers.insert(name, value);
+    Ok(())
+}
+
+fn map_reqwest_error(e: reqwest::Error, timeout: Duration) -> Error {
+    if e.is_timeout() {
+        Error::Timeout(format!("Query timed out after {:?}", timeout))
+    } else if e.is_connect() {
+        Error::Connection(format!("HTTP connection failed: {}", e), Some(ErrorCode::Network))
+    } else {
+        let code = e.status().map(|status| ErrorCode::Http(status.as_u16()));
+        Error::QueryExecution(e.to_string(), code)
+    }
+}
+
+fn response_too_large() -> Error {
+    Error::QueryExecution(
+        format!("HTTP response exceeds {} bytes", MAX_RESPONSE_BYTES),
+        None,
+    )
+}
+
+/// Replace `$N` placeholders in a template using the given formatter
+pub(super) fn substitute(
+    template: &str,
+    params: &[TypedValue],
+    format: impl Fn(&TypedValue) -> String,
+) -> Result<String> {
+    let mut missing = None;
+    let result = POSITIONAL_REGEX.replace_all(template, |caps: &regex::Captures| {
+        let idx: usize = caps[1].parse().unwrap_or(0);
+        match idx.checked_sub(1).and_then(|i| params.get(i)) {
+            Some(value) => format(value),
+            None => {
+                missing = Some(idx);
+                String::new()
+            }
+        }
+    });
+
+    match missing {
+        Some(idx) => Err(Error::BadRequest(format!("No value bound for ${}", idx))),
+        None => Ok(result.into_owned()),
+    }
+}
+
+fn substitute_json(value: &Value, params: &[TypedValue]) -> Result<Value> {
+    Ok(match value {
+        Value::String(s) => {
+            let whole = POSITIONAL_REGEX
+                .captures(s)
+                .filter(|c| c[0].len() == s.len())
+                .and_then(|c| c[1].parse::<usize>().ok());
+            match whole.and_then(|i| i.checked_sub(1)).and_then(|i| params.get(i)) {
+                Some(param) => param.to_json(),
+                None => Value::String(substitute(s, params, TypedValue::to_plain_string)?),
+            }
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|v| substitute_json(v, params))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| Ok((k.clone(), substitute_json(v, params)?)))
+                .collect::<Result<_>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+/// Parse the supported JSONPath subset: `$`, `.key`, `['key']`, `[n]`, `[*]`, `.*`
+fn parse_selector(selector: &str) -> Result<Vec<Segment>> {
+    let invalid = |reason: &str| Error::BadRequest(format!("Invalid selector '{}': {}", selector, reason));
+
+    let rest = selector
+        .trim()
+        .strip_prefix('$')
+        .ok_or_else(|| invalid("must start with $"))?;
+    let chars: Vec<char> = rest.chars().collect();
+    let mut segments = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '.' => {
+                i += 1;
+                if chars.get(i) == Some(&'*') {
+                    segments.push(Segment::Wildcard);
+                    i += 1;
+                    continue;
+                }
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '-') {
+                    i += 1;
+                }
+                if start == i {
+                    return Err(invalid("expected a key after '.'"));
+                }
+                segments.push(Segment::Key(chars[start..i].iter().collect()));
+            }
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .map(|p| i + p)
+                    .ok_or_else(|| invalid("unclosed '['"))?;
+                let inner: String = chars[i + 1..end].iter().collect();
+                let inner = inner.trim();
+                let segment = if inner == "*" {
+                    Segment::Wildcard
+                } else if let Ok(idx) = inner.parse::<usize>() {
+                    Segment::Index(idx)
+                } else if inner.len() >= 2
+                    && ((inner.starts_with('\'') && inner.ends_with('\''))
+                        || (inner.starts_with('"') && inner.ends_with('"')))
+                {
+                    Segment::Key(inner[1..inner.len() - 1].to_string())
+                } else {
+                    return Err(invalid("expected [n], [*] or ['key']"));
+                };
+                segments.push(segment);
+                i = end + 1;
+            }
+            _ => return Err(invalid("expected '.' or '['")),
+        }
+    }
+
+    Ok(segments)
+}
+
+fn select<'a>(value: &'a Value, segments: &[Segment]) -> Vec<&'a Value> {
+    let mut nodes = vec![value];
+    for segment in segments {
+        nodes = nodes
+            .into_iter()
+            .flat_map(|node| -> Vec<&Value> {
+                match (segment, node) {
+                    (Segment::Key(k), Value::Object(map)) => map.get(k).into_iter().collect(),
+                    (Segment::Index(i), Value::Array(items)) => items.get(*i).into_iter().collect(),
+                    (Segment::Wildcard, Value::Array(items)) => items.iter().collect(),
+                    (Segment::Wildcard, Value::Object(map)) => map.values().collect(),
+                    _ => vec![],
+                }
+            })
+            .collect();
+    }
+    nodes
+}
+
+/// Turn the selected JSON nodes into columns and rows
+///
+/// A single selected array is expanded into one row per element. Objects are
+/// flattened with dotted keys; any other value becomes a `value` column.
+fn flatten_rows(document: &Value, selector: &[Segment], max_rows: usize) -> (Vec<ColumnDef>, Vec<Vec<Value>>) {
+    let mut nodes = select(document, selector);
+    if nodes.len() == 1
+        && !selector.contains(&Segment::Wildcard)
+        && let Value::Array(items) = nodes[0]
+    {
+        nodes = items.iter().collect();
+    }
+    nodes.truncate(max_rows);
+
+    let mut names: Vec<String> = Vec::new();
+    let records: Vec<BTreeMap<String, Value>> = nodes
+        .into_iter()
+        .map(|node| {
+            let mut fields = Vec::new();
+            match node {
+                Value::Object(_) => flatten_object("", node, &mut fields),
+                other => fields.push(("value".to_string(), other.clone())),
+            }
+            for (name, _) in &fields {
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+            fields.into_iter().collect()
+        })
+        .collect();
+
+    let rows: Vec<Vec<Value>> = records
+        .iter()
+        .map(|r| names.iter().map(|n| r.get(n).cloned().unwrap_or(Value::Null)).collect())
+        .collect();
+
+    let columns = names
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| ColumnDef {
+            name,
+            data_type: infer_json_type(rows.iter().map(|r| &r[i])).to_string(),
+        })
+        .collect();
+
+    (columns, rows)
+}
+
+fn flatten_object(prefix: &str, value: &Value, out: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() || prefix.is_empty() => {
+            for (key, v) in map {
+                let name = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_object(&name, v, out);
+            }
+        }
+        other => out.push((prefix.to_string(), other.clone())),
+    }
+}
+
+fn infer_json_type<'a>(values: impl Iterator<Item = &'a Value>) -> &'static str {
+    let mut inferred: Option<&'static str> = None;
+    for value in values {
+        let ty = match value {
+            Value::Null => continue,
+            Value::Bool(_) => "BOOLEAN",
+            Value::Number(n) if n.is_i64() || n.is_u64() => "INTEGER",
+            Value::Number(_) => "REAL",
+            Value::String(_) => "TEXT",
+            Value::Array(_) | Value::Object(_) => "JSON",
+        };
+        inferred = match (inferred, ty) {
+            (None, t) => Some(t),
+            (Some(a), b) if a == b => Some(a),
+            (Some("INTEGER"), "REAL") | (Some("REAL"), "INTEGER") => Some("REAL"),
+            _ => Some("JSON"),
+        };
+    }
+    inferred.unwrap_or("TEXT")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_selector() {
+        assert_eq!(parse_selector("$").unwrap(), vec![]);
+        assert_eq!(
+            parse_selector("$.data['items'][*].name").unwrap(),
+            vec![
+                Segment::Key("data".into()),
+                Segment::Key("items".into()),
+                Segment::Wildcard,
+                Segment::Key("name".into()),
+            ]
+        );
+        assert_eq!(parse_selector("$.rows[2]").unwrap()[1], Segment::Index(2));
+        assert!(parse_selector("data.items").is_err());
+        assert!(parse_selector("$.items[").is_err());
+        assert!(parse_selector("$..items").is_err());
+    }
+
+    #[test]
+    fn test_flatten_rows_expands_array_and_nested_objects() {
+        let doc = json!({"data": [
+            {"id": 1, "owner": {"name": "ana"}, "tags": ["a"]},
+            {"id": 2, "owner": {"name": "bo"}, "score": 1.5}
+        ]});
+
+        let (columns, rows) = flatten_rows(&doc, &parse_selector("$.data").unwrap(), 100);
+
+        let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "owner.name", "tags", "score"]);
+        assert_eq!(columns[0].data_type, "INTEGER");
+        assert_eq!(columns[2].data_type, "JSON");
+        assert_eq!(rows[0], vec![json!(1), json!("ana"), json!(["a"]), Value::Null]);
+        assert_eq!(rows[1][3], json!(1.5));
+    }
+
+    #[test]
+    fn test_flatten_rows_scalars_and_max_rows() {
+        let doc = json!({"ids": [3, 4, 5]});
+        let (columns, rows) = flatten_rows(&doc, &parse_selector("$.ids[*]").unwrap(), 2);
+        assert_eq!(columns[0].name, "value");
+        assert_eq!(rows, vec![vec![json!(3)], vec![json!(4)]]);
+    }
+
+    #[test]
+    fn test_substitute_encodes_path_values() {
+        let params = vec![TypedValue::String("a b/c".into()), TypedValue::Integer(7)];
+        let path = substitute("/teams/$1/items/$2", &params, |v| {
+            utf8_percent_encode(&v.to_plain_string(), NON_ALPHANUMERIC).to_string()
+        })
+        .unwrap();
+        assert_eq!(path, "/teams/a%20b%2Fc/items/7");
+        assert!(substitute("/x/$3", &params, TypedValue::to_plain_string).is_err());
+    }
+
+    #[test]
+    fn test_substitute_json_keeps_types() {
+        let params = vec![TypedValue::Integer(5), TypedValue::Boolean(true)];
+        let body = substitute_json(&json!({"limit": "$1", "note": "n=$1", "flags": ["$2"]}), &params).unwrap();
+        assert_eq!(body, json!({"limit": 5, "note": "n=5", "flags": [true]}));
+    }
+
+    #[test]
+    fn test_request_spec_rejects_absolute_urls() {
+        assert!(HttpRequestSpec::parse(r#"{"path": "https://evil.example/x"}"#).is_err());
+        assert!(HttpRequestSpec::parse(r#"{"path": "//evil.example/x"}"#).is_err());
+        assert!(HttpRequestSpec::parse(r#"{"path": "/ok", "select": "$.items"}"#).is_ok());
+    }
+
+    #[test]
+    fn test_resolve_stays_under_base_url() {
+        let connector = HttpConnector::new("https://ops.internal/api/v1").unwrap();
+        assert_eq!(
+            connector.resolve("/teams").unwrap().as_str(),
+            "https://ops.internal/api/v1/teams"
+        );
+        assert!(connector.resolve("/../../admin").is_err());
+        assert!(connector.resolve("/../v10/teams").is_err());
+        assert!(connector.resolve("").is_ok());
+
+        let connector = HttpConnector::new("https://ops.internal/").unwrap();
+        assert!(connector.resolve("/anything").is_ok());
+    }
+}
@@ -1,9 +1,30 @@
+mod http;
+mod mysql;
+mod plugin;
 mod postgres;
+mod prometheus;
+mod retry;
+mod sqlite;
+mod stream;
 
+pub use http::{HttpConnector, HttpRequestSpec};
+pub use mysql::MySqlConnector;
+pub use plugin::{
+    CONNECTOR_PLUGINS_ENV, PLUGIN_PROTOCOL_VERSION, PluginConnector, PluginRegistry, PluginSpec,
+    error_codes as plugin_error_codes, parse_plugin_connection_string,
+};
 pub use postgres::PostgresConnector;
+pub use prometheus::{PromQuery, PrometheusConnector};
+pub use retry::{code_retry_class, is_retryable, retry_class};
+pub use sqlite::{SQLITE_ALLOWED_DIRS_ENV, SqliteConnector};
+pub use stream::{
+    CollectedRows, DEFAULT_BATCH_SIZE, RowBatch, RowStream, collect_rows, output_to_stream,
+};
 
-use crate::error::Result;
-use crate::models::ColumnDef;
+use crate::db::PoolStats;
+use crate::error::{Error, Result};
+use crate::sql_validator::SqlValidator;
+use crate::models::{ColumnDef, DatasourceType};
 use crate::params::TypedValue;
 use async_trait::async_trait;
 use std::time::Duration;
@@ -40,18 +61,117 @@ pub trait Connector: Send + Sync {
         max_rows: usize,
     ) -> Result<QueryOutput>;
 
+    /// Execute a query with bound parameters, yielding rows in batches
+    ///
+    /// Connectors that can't read incrementally run the query to completion
+    /// and split the result.
+    async fn execute_stream(
+        &self,
+        sql: &str,
+        params: &[TypedValue],
+        timeout: Duration,
+        max_rows: usize,
+        batch_size: usize,
+    ) -> Result<RowStream> {
+        let output = self.execute_with_params(sql, params, timeout, max_rows).await?;
+        Ok(output_to_stream(output, batch_size))
+    }
+
     /// Get schema information (tables, columns)
     async fn get_schema(&self) -> Result<Vec<TableSchema>>;
+
+    /// Connection pool statistics, for connectors that keep a pool
+    fn pool_stats(&self) -> Option<PoolStats> {
+        None
+    }
+}
+
+/// Pool statistics for connectors backed by a sqlx pool
+fn sqlx_pool_stats<DB: sqlx::Database>(pool: &sqlx::Pool<DB>) -> PoolStats {
+    let size = pool.size();
+    let idle = pool.num_idle() as u32;
+
+    PoolStats {
+        connections_active: size.saturating_sub(idle),
+        connections_idle: idle,
+        connections_max: pool.options().get_max_connections(),
+    }
+}
+
+/// Maximum length of query text accepted for plugin datasources
+const MAX_PLUGIN_QUERY_LENGTH: usize = 100_000;
+
+/// Create a connector for the given datasource type
+///
+/// A plugin registered under the type's name takes precedence over the
+/// built-in connector.
+pub async fn create_connector(
+    ds_type: DatasourceType,
+    connection_string: &str,
+) -> Result<Box<dyn Connector>> {
+    let registry = PluginRegistry::global();
+    if let Some((name, spec)) = registry.for_type(ds_type) {
+        return Ok(Box::new(PluginConnector::spawn(name, spec, connection_string).await?));
+    }
+
+    let connector: Box<dyn Connector> = match ds_type {
+        DatasourceType::Postgres => Box::new(PostgresConnector::new(connection_string).await?),
+        DatasourceType::Mysql => Box::new(MySqlConnector::new(connection_string).await?),
+        DatasourceType::Sqlite => Box::new(SqliteConnector::new(connection_string).await?),
+        DatasourceType::Http => Box::new(HttpConnector::new(connection_string)?),
+        DatasourceType::Prometheus => Box::new(PrometheusConnector::new(connection_string)?),
+        DatasourceType::Plugin => {
+            let (name, inner) = parse_plugin_connection_string(connection_string)?;
+            let spec = registry.get(name).ok_or_else(|| {
+                Error::BadRequest(format!("Connector plugin '{}' is not registered", name))
+            })?;
+            Box::new(PluginConnector::spawn(name, spec, inner).await?)
+        }
+    };
+
+    Ok(connector)
+}
+
+/// Validate query text in the language of the given datasource type
+///
+/// SQL datasources go through the `SqlValidator`; other connectors check
+/// their own request format.
+pub fn validate_query(ds_type: DatasourceType, query: &str) -> Result<()> {
+    match ds_type {
+        DatasourceType::Postgres | DatasourceType::Mysql | DatasourceType::Sqlite => {
+            SqlValidator::new().validate(query)?;
+        }
+        DatasourceType::Http => {
+            HttpRequestSpec::parse(query)?;
+        }
+        DatasourceType::Prometheus => {
+            PromQuery::parse(query)?;
+        }
+        DatasourceType::Plugin => {
+            // Plugins validate their own query language when executing
+            if query.trim().is_empty() {
+                return Err(Error::BadRequest("Query cannot be empty".to_string()));
+            }
+            if query.len() > MAX_PLUGIN_QUERY_LENGTH {
+                return Err(Error::BadRequest(format!(
+                    "Query exceeds maximum length of {} characters",
+                    MAX_PLUGIN_QUERY_LENGTH
+                )));
+            }
+        }
+    }
+
+    Ok(())
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct TableSchema {
     pub schema: String,
     pub name: String,
     pub columns: Vec<ColumnSchema>,
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct ColumnSchema {
     pub name: String,
     pub data_type: String,
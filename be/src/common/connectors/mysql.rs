@@ -0,0 +1,368 @@
+use super::retry::sqlx_error_code;
+use super::stream::spawn_row_stream;
+use super::{ColumnSchema, Connector, PoolStats, QueryOutput, RowStream, TableSchema, sqlx_pool_stats};
+use crate::error::{Error, ErrorCode, Result};
+use crate::models::ColumnDef;
+use crate::params::{TypedValue, to_question_placeholders};
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use sqlx::mysql::{MySqlArguments, MySqlDatabaseError, MySqlPoolOptions, MySqlRow};
+use sqlx::{Arguments, Column, MySqlPool, Row, TypeInfo};
+use std::time::{Duration, Instant};
+
+pub struct MySqlConnector {
+    pool: MySqlPool,
+}
+
+impl MySqlConnector {
+    pub async fn new(connection_string: &str) -> Result<Self> {
+        let pool = MySqlPoolOptions::new()
+            .max_connections(5)
+            .acquire_timeout(Duration::from_secs(10))
+            .connect(connection_string)
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to connect to MySQL: {}", e), error_code(&e)))?;
+
+        Ok(Self { pool })
+    }
+
+    async fn fetch(
+        &self,
+        sql: &str,
+        args: MySqlArguments,
+        timeout: Duration,
+        max_rows: usize,
+    ) -> Result<QueryOutput> {
+        let start = Instant::now();
+
+        // Wrap query with limit (MySQL requires an alias on derived tables)
+        let limited_sql = format!(
+            "SELECT * FROM ({}) AS _q LIMIT {}",
+            sql.trim().trim_end_matches(';'),
+            max_rows
+        );
+
+        let rows = tokio::time::timeout(
+            timeout,
+            sqlx::query_with(&limited_sql, args).fetch_all(&self.pool),
+        )
+        .await
+        .map_err(|_| Error::Timeout(format!("Query timed out after {:?}", timeout)))?
+        .map_err(|e| Error::QueryExecution(e.to_string(), error_code(&e)))?;
+
+        let execution_time = start.elapsed();
+
+        if rows.is_empty() {
+            return Ok(QueryOutput {
+                columns: vec![],
+                rows: vec![],
+                row_count: 0,
+                execution_time,
+            });
+        }
+
+        // Extract column information
+        let columns = column_defs(&rows[0]);
+
+        // Extract row data
+        let result_rows: Vec<Vec<serde_json::Value>> =
+            rows.iter().map(|row| row_values(row, &columns)).collect();
+
+        let row_count = result_rows.len();
+
+        Ok(QueryOutput {
+            columns,
+            rows: result_rows,
+            row_count,
+            execution_time,
+        })
+    }
+}
+
+#[async_trait]
+impl Connector for MySqlConnector {
+    async fn test_connection(&self) -> Result<Duration> {
+        let start = Instant::now();
+        sqlx::query("SELECT 1")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Error::Connection(format!("Connection test failed: {}", e), error_code(&e)))?;
+        Ok(start.elapsed())
+    }
+
+    async fn execute(
+        &self,
+        sql: &str,
+        timeout: Duration,
+        max_rows: usize,
+    ) -> Result<QueryOutput> {
+        self.fetch(sql, MySqlArguments::default(), timeout, max_rows)
+            .await
+    }
+
+    async fn execute_with_params(
+        &self,
+        sql: &str,
+        params: &[TypedValue],
+        timeout: Duration,
+        max_rows: usize,
+    ) -> Result<QueryOutput> {
+        // MySQL binds by order of appearance, so $1, $2 become ? with values reordered
+        let (sql, ordered) = to_question_placeholders(sql, params)?;
+
+        // Build arguments
+        let args = bind_args(&ordered)?;
+
+        self.fetch(&sql, args, timeout, max_rows).await
+    }
+
+    async fn execute_stream(
+        &self,
+        sql: &str,
+        params: &[TypedValue],
+        timeout: Duration,
+        max_rows: usize,
+        batch_size: usize,
+    ) -> Result<RowStream> {
+        let (sql, ordered) = to_question_placeholders(sql, params)?;
+        let limited_sql = format!(
+            "SELECT * FROM ({}) AS _q LIMIT {}",
+            sql.trim().trim_end_matches(';'),
+            max_rows
+        );
+        let args = bind_args(&ordered)?;
+        let pool = self.pool.clone();
+
+        Ok(spawn_row_stream(timeout, batch_size, move |mut out| async move {
+            // Hold one connection so its query can be killed by thread id
+            let mut conn = pool
+                .acquire()
+                .await
+                .map_err(|e| Error::Connection(format!("Failed to acquire connection: {}", e), error_code(&e)))?;
+            let connection_id: u64 = sqlx::query_scalar("SELECT CONNECTION_ID()")
+                .fetch_one(&mut *conn)
+                .await
+                .map_err(|e| Error::Connection(e.to_string(), error_code(&e)))?;
+
+            let stop = out.stop_signal();
+            let read = async {
+                let mut rows = sqlx::query_with(&limited_sql, args).fetch(&mut *conn);
+                while let Some(row) = rows
+                    .try_next()
+                    .await
+                    .map_err(|e| Error::QueryExecution(e.to_string(), error_code(&e)))?
+                {
+                    if out.columns().is_none() {
+                        out.set_columns(column_defs(&row));
+                    }
+                    let values = row_values(&row, out.columns().unwrap_or_default());
+                    if !out.push(values).await {
+                        // Reusing the connection would first drain the rest of the result
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            };
+
+            let error = tokio::select! {
+                result = read => match result {
+                    Ok(true) => return Ok(out),
+                    Ok(false) => None,
+                    Err(e) => return Err(e),
+                },
+                error = stop.wait() => Some(error),
+            };
+
+            // The output is full, the consumer is gone or the deadline passed:
+            // stop the query on the server too, and don't hand the half-read
+            // connection back
+            kill_query(&pool, connection_id).await;
+            let _ = conn.close().await;
+            match error {
+                Some(error) => Err(error),
+                None => Ok(out),
+            }
+        }))
+    }
+
+    async fn get_schema(&self) -> Result<Vec<TableSchema>> {
+        // information_schema columns are BLOB/LONGTEXT on MySQL 8, so cast to CHAR for decoding
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                CAST(table_schema AS CHAR) AS table_schema,
+                CAST(table_name AS CHAR) AS table_name,
+                CAST(column_name AS CHAR) AS column_name,
+                CAST(data_type AS CHAR) AS data_type,
+                CAST(is_nullable AS CHAR) AS is_nullable
+            FROM information_schema.columns
+            WHERE table_schema = DATABASE()
+            ORDER BY table_schema, table_name, ordinal_position
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::QueryExecution(format!("Failed to get schema: {}", e), error_code(&e)))?;
+
+        let mut tables: Vec<TableSchema> = Vec::new();
+        let mut current_table: Option<(String, String)> = None;
+
+        for row in rows {
+            let schema: String = row.get("table_schema");
+            let table: String = row.get("table_name");
+            let column_name: String = row.get("column_name");
+            let data_type: String = row.get("data_type");
+            let is_nullable: String = row.get("is_nullable");
+
+            let col = ColumnSchema {
+                name: column_name,
+                data_type,
+                is_nullable: is_nullable == "YES",
+            };
+
+            match &current_table {
+                Some((s, t)) if s == &schema && t == &table => {
+                    if let Some(last) = tables.last_mut() {
+                        last.columns.push(col);
+                    }
+                }
+                _ => {
+                    tables.push(TableSchema {
+                        schema: schema.clone(),
+                        name: table.clone(),
+                        columns: vec![col],
+                    });
+                    current_table = Some((schema, table));
+                }
+            }
+        }
+
+        Ok(tables)
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        Some(sqlx_pool_stats(&self.pool))
+    }
+}
+
+/// MySQL's SQLSTATE is often the generic `HY000`, so use the error number
+/// (see `retry::MYSQL_RETRYABLE`)
+fn error_code(e: &sqlx::Error) -> Option<ErrorCode> {
+    sqlx_error_code(e, |db| {
+        db.try_downcast_ref::<MySqlDatabaseError>()
+            .map(|e| ErrorCode::MySql(e.number()))
+    })
+}
+
+/// Ask the server to abort the statement running on a connection
+async fn kill_query(pool: &MySqlPool, connection_id: u64) {
+    // KILL doesn't accept placeholders; the id is a server-issued integer
+    if let Err(e) = sqlx::query(&format!("KILL QUERY {}", connection_id))
+        .execute(pool)
+        .await
+    {
+        tracing::warn!(connection_id, "Failed to kill MySQL query: {}", e);
+    }
+}
+
+fn bind_args(params: &[TypedValue]) -> Result<MySqlArguments> {
+    let mut args = MySqlArguments::default();
+    for param in params {
+        match param {
+            TypedValue::String(s) => args.add(s.clone()).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Number(n) => args.add(*n).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Integer(i) => args.add(*i).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Boolean(b) => args.add(*b).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Date(d) => args.add(*d).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::DateTime(dt) => args.add(*dt).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Null => {
+                let null_val: Option<String> = None;
+                args.add(null_val).map_err(|e| Error::BadRequest(e.to_string()))?;
+            }
+        }
+    }
+    Ok(args)
+}
+
+fn column_defs(row: &MySqlRow) -> Vec<ColumnDef> {
+    row.columns()
+        .iter()
+        .map(|c| ColumnDef {
+            name: c.name().to_string(),
+            data_type: c.type_info().name().to_string(),
+        })
+        .collect()
+}
+
+fn row_values(row: &MySqlRow, columns: &[ColumnDef]) -> Vec<serde_json::Value> {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| mysql_value_to_json(row, i, &col.data_type))
+        .collect()
+}
+
+fn mysql_value_to_json(row: &MySqlRow, idx: usize, type_name: &str) -> serde_json::Value {
+    let type_name = type_name.to_uppercase();
+
+    // Unsigned integers don't fit the signed decoders
+    if type_name.ends_with("UNSIGNED") {
+        return row
+            .try_get::<u64, _>(idx)
+            .map(serde_json::Value::from)
+            .unwrap_or(serde_json::Value::Null);
+    }
+
+    match type_name.as_str() {
+        "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" | "YEAR" => row
+            .try_get::<i64, _>(idx)
+            .map(serde_json::Value::from)
+            .unwrap_or(serde_json::Value::Null),
+        "FLOAT" => row
+            .try_get::<f32, _>(idx)
+            .map(|v| serde_json::json!(v))
+            .unwrap_or(serde_json::Value::Null),
+        "DOUBLE" => row
+            .try_get::<f64, _>(idx)
+            .map(|v| serde_json::json!(v))
+            .unwrap_or(serde_json::Value::Null),
+        "DECIMAL" => {
+            // DECIMAL is sent as text on the wire; decode unchecked and parse
+            row.try_get_unchecked::<Option<String>, _>(idx)
+                .ok()
+                .flatten()
+                .and_then(|s| s.parse::<f64>().ok())
+                .map(|v| serde_json::json!(v))
+                .unwrap_or(serde_json::Value::Null)
+        }
+        "BOOLEAN" => row
+            .try_get::<bool, _>(idx)
+            .map(serde_json::Value::from)
+            .unwrap_or(serde_json::Value::Null),
+        "TIMESTAMP" => row
+            .try_get::<chrono::DateTime<chrono::Utc>, _>(idx)
+            .map(|v| serde_json::Value::from(v.to_rfc3339()))
+            .unwrap_or(serde_json::Value::Null),
+        "DATETIME" => row
+            .try_get::<chrono::NaiveDateTime, _>(idx)
+            .map(|v| serde_json::Value::from(v.and_utc().to_rfc3339()))
+            .unwrap_or(serde_json::Value::Null),
+        "DATE" => row
+            .try_get::<chrono::NaiveDate, _>(idx)
+            .map(|v| serde_json::Value::from(v.to_string()))
+            .unwrap_or(serde_json::Value::Null),
+        "TIME" => row
+            .try_get::<chrono::NaiveTime, _>(idx)
+            .map(|v| serde_json::Value::from(v.to_string()))
+            .unwrap_or(serde_json::Value::Null),
+        "JSON" => row
+            .try_get::<serde_json::Value, _>(idx)
+            .unwrap_or(serde_json::Value::Null),
+        _ => {
+            // Fallback: try as string (VARCHAR, TEXT, ENUM, ...)
+            row.try_get::<String, _>(idx)
+                .map(serde_json::Value::from)
+                .unwrap_or(serde_json::Value::Null)
+        }
+    }
+}
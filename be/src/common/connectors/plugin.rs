@@ -0,0 +1,472 @@
+//! Out-of-process connector plugins
+//!
+//! A plugin is an executable speaking JSON-RPC 2.0 over stdio, one message per
+//! line. Loupe launches it, sends `initialize` with the datasource connection
+//! string, then forwards `test_connection`, `execute` and `get_schema` calls.
+//! See `docs/PLUGINS.md` for the full protocol and `examples/echo_plugin.rs`
+//! for a reference implementation.
+
+use super::{Connector, QueryOutput, TableSchema};
+use crate::error::{Error, ErrorCode, Result};
+use crate::models::{ColumnDef, DatasourceType};
+use crate::params::TypedValue;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{Mutex, oneshot};
+
+/// Protocol version sent in `initialize`; plugins must reject versions they don't speak
+pub const PLUGIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Environment variable pointing at the plugin registry file
+pub const CONNECTOR_PLUGINS_ENV: &str = "CONNECTOR_PLUGINS";
+
+/// JSON-RPC error codes plugins use to classify failures
+pub mod error_codes {
+    pub const CONNECTION: i64 = -32001;
+    pub const TIMEOUT: i64 = -32002;
+    pub const QUERY: i64 = -32003;
+    pub const BAD_REQUEST: i64 = -32004;
+}
+
+/// Time allowed for a plugin to start and answer `initialize`
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Extra time given to a plugin past the query timeout before giving up on it
+const RESPONSE_GRACE: Duration = Duration::from_secs(5);
+
+/// How to launch a plugin executable
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginSpec {
+    pub command: PathBuf,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+/// Configured plugins, keyed by name
+///
+/// Loaded from the JSON file named by `CONNECTOR_PLUGINS`:
+///
+/// ```json
+/// {
+///   "clickhouse": {"command": "/opt/loupe/plugins/clickhouse", "args": ["--stdio"]},
+///   "mysql": {"command": "/opt/loupe/plugins/mysql-with-ssh"}
+/// }
+/// ```
+///
+/// `plugin` datasources select an entry by name (`plugin://clickhouse/...`).
+/// An entry named after a built-in datasource type replaces that connector.
+#[derive(Debug, Default, Clone)]
+pub struct PluginRegistry {
+    plugins: HashMap<String, PluginSpec>,
+}
+
+impl PluginRegistry {
+    pub fn new(plugins: HashMap<String, PluginSpec>) -> Self {
+        Self { plugins }
+    }
+
+    /// Load the registry from `CONNECTOR_PLUGINS` (empty when unset)
+    pub fn from_env() -> Result<Self> {
+        let Ok(path) = std::env::var(CONNECTOR_PLUGINS_ENV) else {
+            return Ok(Self::default());
+        };
+
+        let contents = std::fs::read_to_string(&path).map_err(|e| {
+            Error::Internal(format!("Failed to read plugin registry {}: {}", path, e))
+        })?;
+        let plugins: HashMap<String, PluginSpec> = serde_json::from_str(&contents).map_err(|e| {
+            Error::Internal(format!("Invalid plugin registry {}: {}", path, e))
+        })?;
+
+        if plugins.contains_key("plugin") {
+            return Err(Error::Internal(
+                "Plugin registry cannot define an entry named 'plugin'".to_string(),
+            ));
+        }
+
+        Ok(Self { plugins })
+    }
+
+    /// Process-wide registry, loaded from the environment on first use
+    ///
+    /// An invalid registry file is logged and treated as empty.
+    pub fn global() -> &'static PluginRegistry {
+        static REGISTRY: OnceLock<PluginRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(|| match Self::from_env() {
+            Ok(registry) => {
+                if !registry.plugins.is_empty() {
+                    let mut names: Vec<&str> = registry.plugins.keys().map(String::as_str).collect();
+                    names.sort_unstable();
+                    tracing::info!("Connector plugins registered: {}", names.join(", "));
+                }
+                registry
+            }
+            Err(e) => {
+                tracing::error!("{}", e);
+                Self::default()
+            }
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PluginSpec> {
+        self.plugins.get(name)
+    }
+
+    /// Check that a `plugin` datasource connection string names a registered plugin
+    pub fn validate_connection_string(&self, connection_string: &str) -> Result<()> {
+        let (name, _) = parse_plugin_connection_string(connection_string)?;
+        if !self.plugins.contains_key(name) {
+            return Err(Error::BadRequest(format!(
+                "Connector plugin '{}' is not registered",
+                name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Plugin replacing the built-in connector for a datasource type, if any,
+    /// along with its registered name
+    pub fn for_type(&self, ds_type: DatasourceType) -> Option<(&str, &PluginSpec)> {
+        let name = serde_json::to_value(ds_type).ok()?;
+        self.plugins
+            .get_key_value(name.as_str()?)
+            .map(|(name, spec)| (name.as_str(), spec))
+    }
+}
+
+/// Split a `plugin://<name>/<connection string>` into the plugin name and
+/// the connection string passed through to the plugin
+pub fn parse_plugin_connection_string(connection_string: &str) -> Result<(&str, &str)> {
+    let rest = connection_string.strip_prefix("plugin://").ok_or_else(|| {
+        Error::BadRequest("Plugin datasources must use plugin://<name>/<connection string>".to_string())
+    })?;
+    let (name, inner) = rest.split_once('/').unwrap_or((rest, ""));
+    if name.is_empty() {
+        return Err(Error::BadRequest("Plugin name is missing".to_string()));
+    }
+    Ok((name, inner))
+}
+
+#[derive(Serialize)]
+struct RpcRequest<'a> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct ExecuteResult {
+    columns: Vec<ColumnDef>,
+    rows: Vec<Vec<Value>>,
+}
+
+/// Callers waiting on a response, keyed by request id; `None` once the
+/// plugin's stdout has closed
+type PendingCalls = Arc<std::sync::Mutex<Option<HashMap<u64, oneshot::Sender<RpcResponse>>>>>;
+
+/// Connector adapter for an out-of-process plugin
+///
+/// Calls are multiplexed over the one process: requests are written as they
+/// come and a reader task hands each response to the caller with its id, so a
+/// slow query doesn't hold up other runs, connection tests or schema loads.
+pub struct PluginConnector {
+    name: String,
+    // Held so the child is killed when the connector is dropped
+    _child: Child,
+    stdin: Mutex<ChildStdin>,
+    pending: PendingCalls,
+    next_id: AtomicU64,
+}
+
+impl PluginConnector {
+    /// Launch a plugin and initialize it with the datasource connection string
+    pub async fn spawn(name: &str, spec: &PluginSpec, connection_string: &str) -> Result<Self> {
+        let mut child = Command::new(&spec.command)
+            .args(&spec.args)
+            .envs(&spec.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                Error::Connection(format!("Failed to start plugin '{}': {}", name, e), None)
+            })?;
+
+        let stdin = child.stdin.take().ok_or_else(|| Error::Internal("Plugin stdin unavailable".to_string()))?;
+        let stdout = child.stdout.take().ok_or_else(|| Error::Internal("Plugin stdout unavailable".to_string()))?;
+
+        let pending: PendingCalls = Arc::new(std::sync::Mutex::new(Some(HashMap::new())));
+        tokio::spawn(dispatch_responses(
+            name.to_string(),
+            BufReader::new(stdout).lines(),
+            pending.clone(),
+        ));
+
+        let connector = Self {
+            name: name.to_string(),
+            _child: child,
+            stdin: Mutex::new(stdin),
+            pending,
+            next_id: AtomicU64::new(1),
+        };
+
+        connector
+            .call(
+                "initialize",
+                serde_json::json!({
+                    "protocol_version": PLUGIN_PROTOCOL_VERSION,
+                    "connection_string": connection_string,
+                }),
+                STARTUP_TIMEOUT,
+            )
+            .await
+            .map_err(|e| {
+                let code = e.code().cloned();
+                Error::Connection(format!("Plugin '{}' failed to initialize: {}", name, e), code)
+            })?;
+
+        Ok(connector)
+    }
+
+    /// Send one request and wait for its response
+    ///
+    /// Only the write holds the stdin lock; other calls may be in flight while
+    /// this one waits. A late response to a timed-out call is dropped.
+    async fn call(&self, method: &str, params: Value, timeout: Duration) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        let mut line = serde_json::to_string(&RpcRequest {
+            jsonrpc: "2.0",
+            id,
+            method,
+            params,
+        })
+        .map_err(|e| Error::Internal(e.to_string()))?;
+        line.push('\n');
+
+        let (tx, rx) = oneshot::channel();
+        match self.pending.lock().unwrap().as_mut() {
+            Some(pending) => pending.insert(id, tx),
+            None => return Err(self.exited()),
+        };
+
+        let exchange = async {
+            {
+                let mut stdin = self.stdin.lock().await;
+                stdin.write_all(line.as_bytes()).await.map_err(|e| self.io_error(e))?;
+                stdin.flush().await.map_err(|e| self.io_error(e))?;
+            }
+            rx.await.map_err(|_| self.exited())
+        };
+
+        let response = match tokio::time::timeout(timeout, exchange).await {
+            Ok(response) => response,
+            Err(_) => Err(Error::Timeout(format!("Query timed out after {:?}", timeout))),
+        };
+        if response.is_err()
+            && let Some(pending) = self.pending.lock().unwrap().as_mut()
+        {
+            pending.remove(&id);
+        }
+        let response = response?;
+
+        match (response.result, response.error) {
+            (_, Some(err)) => Err(map_rpc_error(err)),
+            (Some(result), None) => Ok(result),
+            (None, None) => Ok(Value::Null),
+        }
+    }
+
+    fn exited(&self) -> Error {
+        Error::Connection(format!("Plugin '{}' exited", self.name), None)
+    }
+
+    fn io_error(&self, e: std::io::Error) -> Error {
+        Error::Connection(format!("Plugin '{}' I/O error: {}", self.name, e), None)
+    }
+}
+
+/// Read responses from a plugin and hand each to the caller waiting on its id
+///
+/// Runs until the plugin's stdout closes, then fails every outstanding call.
+/// Lines with an unknown id (e.g. replies to timed-out calls) are dropped.
+async fn dispatch_responses(
+    name: String,
+    mut stdout: Lines<BufReader<ChildStdout>>,
+    pending: PendingCalls,
+) {
+    loop {
+        let line = match stdout.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::warn!(plugin = %name, "Failed to read from plugin: {}", e);
+                break;
+            }
+        };
+
+        let response: RpcResponse = match serde_json::from_str(&line) {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::warn!(plugin = %name, "Plugin sent invalid JSON-RPC: {}", e);
+                break;
+            }
+        };
+
+        let waiter = response
+            .id
+            .and_then(|id| pending.lock().unwrap().as_mut()?.remove(&id));
+        if let Some(waiter) = waiter {
+            let _ = waiter.send(response);
+        }
+    }
+
+    // Dropping the senders fails the outstanding calls
+    pending.lock().unwrap().take();
+}
+
+fn map_rpc_error(err: RpcError) -> Error {
+    match err.code {
+        error_codes::CONNECTION => Error::Connection(err.message, Some(ErrorCode::Plugin(err.code))),
+        error_codes::TIMEOUT => Error::Timeout(err.message),
+        error_codes::BAD_REQUEST => Error::BadRequest(err.message),
+        code => Error::QueryExecution(err.message, Some(ErrorCode::Plugin(code))),
+    }
+}
+
+#[async_trait]
+impl Connector for PluginConnector {
+    async fn test_connection(&self) -> Result<Duration> {
+        let start = Instant::now();
+        self.call("test_connection", serde_json::json!({}), STARTUP_TIMEOUT)
+            .await?;
+        Ok(start.elapsed())
+    }
+
+    async fn execute(
+        &self,
+        sql: &str,
+        timeout: Duration,
+        max_rows: usize,
+    ) -> Result<QueryOutput> {
+        self.execute_with_params(sql, &[], timeout, max_rows).await
+    }
+
+    async fn execute_with_params(
+        &self,
+        sql: &str,
+        params: &[TypedValue],
+        timeout: Duration,
+        max_rows: usize,
+    ) -> Result<QueryOutput> {
+        let start = Instant::now();
+        let request = serde_json::json!({
+            "sql": sql,
+            "params": params.iter().map(TypedValue::to_tagged_json).collect::<Vec<_>>(),
+            "timeout_ms": timeout.as_millis() as u64,
+            "max_rows": max_rows,
+        });
+
+        let result = self.call("execute", request, timeout + RESPONSE_GRACE).await?;
+        let mut output: ExecuteResult = serde_json::from_value(result).map_err(|e| {
+            Error::QueryExecution(
+                format!("Plugin '{}' returned an invalid result: {}", self.name, e),
+                None,
+            )
+        })?;
+
+        // Don't trust the plugin to honour max_rows
+        output.rows.truncate(max_rows);
+        let row_count = output.rows.len();
+
+        Ok(QueryOutput {
+            columns: output.columns,
+            rows: output.rows,
+            row_count,
+            execution_time: start.elapsed(),
+        })
+    }
+
+    async fn get_schema(&self) -> Result<Vec<TableSchema>> {
+        let result = self
+            .call("get_schema", serde_json::json!({}), Duration::from_secs(60))
+            .await?;
+        serde_json::from_value(result).map_err(|e| {
+            Error::QueryExecution(
+                format!("Plugin '{}' returned an invalid schema: {}", self.name, e),
+                None,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plugin_connection_string() {
+        assert_eq!(
+            parse_plugin_connection_string("plugin://clickhouse/https://ch:8443?db=x").unwrap(),
+            ("clickhouse", "https://ch:8443?db=x")
+        );
+        assert_eq!(parse_plugin_connection_string("plugin://bare").unwrap(), ("bare", ""));
+        assert!(parse_plugin_connection_string("plugin:///x").is_err());
+        assert!(parse_plugin_connection_string("postgres://x").is_err());
+    }
+
+    #[test]
+    fn test_registry_for_type() {
+        let spec = PluginSpec {
+            command: PathBuf::from("/bin/true"),
+            args: vec![],
+            env: HashMap::new(),
+        };
+        let registry = PluginRegistry::new(HashMap::from([("mysql".to_string(), spec)]));
+
+        assert!(registry.for_type(DatasourceType::Mysql).is_some());
+        assert!(registry.for_type(DatasourceType::Postgres).is_none());
+        assert!(registry.validate_connection_string("plugin://mysql/x").is_ok());
+        assert!(registry.validate_connection_string("plugin://other/x").is_err());
+    }
+
+    #[test]
+    fn test_map_rpc_error() {
+        let err = |code| RpcError {
+            code,
+            message: "boom".to_string(),
+        };
+        assert!(matches!(map_rpc_error(err(error_codes::CONNECTION)), Error::Connection(..)));
+        assert!(matches!(map_rpc_error(err(error_codes::TIMEOUT)), Error::Timeout(_)));
+        assert!(matches!(map_rpc_error(err(-32603)), Error::QueryExecution(..)));
+    }
+}
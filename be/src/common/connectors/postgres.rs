@@ -1,12 +1,24 @@
-use super::{ColumnSchema, Connector, QueryOutput, TableSchema};
-use crate::error::{Error, Result};
+use super::retry::sqlx_error_code;
+use super::stream::spawn_row_stream;
+use super::{ColumnSchema, Connector, PoolStats, QueryOutput, RowStream, TableSchema, sqlx_pool_stats};
+use crate::error::{Error, ErrorCode, Result};
 use crate::models::ColumnDef;
 use crate::params::TypedValue;
 use async_trait::async_trait;
-use sqlx::postgres::{PgArguments, PgPoolOptions};
-use sqlx::{Arguments, Column, PgPool, Row, TypeInfo};
+use futures_util::TryStreamExt;
+use sqlx::postgres::{PgArguments, PgPoolOptions, PgRow};
+use sqlx::{Arguments, Column, Connection, PgConnection, PgPool, Row, TypeInfo};
 use std::time::{Duration, Instant};
 
+/// Runs execute in a read-only transaction, so the server rejects writes
+/// even if `SqlValidator` is bypassed
+const BEGIN_READ_ONLY: &str = "BEGIN READ ONLY";
+
+/// How long past the run's timeout the client waits before giving up on the
+/// server. The server's `statement_timeout` normally fires first and cancels
+/// the statement; this only catches a server that stopped responding.
+const CLIENT_TIMEOUT_GRACE: Duration = Duration::from_secs(5);
+
 pub struct PostgresConnector {
     pool: PgPool,
 }
@@ -18,7 +30,7 @@ impl PostgresConnector {
             .acquire_timeout(Duration::from_secs(10))
             .connect(connection_string)
             .await
-            .map_err(|e| Error::Connection(format!("Failed to connect to Postgres: {}", e)))?;
+            .map_err(|e| Error::Connection(format!("Failed to connect to Postgres: {}", e), error_code(&e)))?;
 
         Ok(Self { pool })
     }
@@ -31,7 +43,7 @@ impl Connector for PostgresConnector {
         sqlx::query("SELECT 1")
             .fetch_one(&self.pool)
             .await
-            .map_err(|e| Error::Connection(format!("Connection test failed: {}", e)))?;
+            .map_err(|e| Error::Connection(format!("Connection test failed: {}", e), error_code(&e)))?;
         Ok(start.elapsed())
     }
 
@@ -40,20 +52,49 @@ impl Connector for PostgresConnector {
         sql: &str,
         timeout: Duration,
         max_rows: usize,
+    ) -> Result<QueryOutput> {
+        self.execute_with_params(sql, &[], timeout, max_rows).await
+    }
+
+    async fn execute_with_params(
+        &self,
+        sql: &str,
+        params: &[TypedValue],
+        timeout: Duration,
+        max_rows: usize,
     ) -> Result<QueryOutput> {
         let start = Instant::now();
 
-        // Wrap query with timeout and limit
+        // Wrap query with limit
         let limited_sql = format!(
             "SELECT * FROM ({}) AS _q LIMIT {}",
             sql.trim().trim_end_matches(';'),
             max_rows
         );
 
-        let rows = tokio::time::timeout(timeout, sqlx::query(&limited_sql).fetch_all(&self.pool))
+        // Build arguments
+        let args = bind_args(params)?;
+
+        let fetch = async {
+            let mut tx = self
+                .pool
+                .begin_with(BEGIN_READ_ONLY)
+                .await
+                .map_err(|e| Error::Connection(format!("Failed to begin transaction: {}", e), error_code(&e)))?;
+            set_limits(&mut tx, timeout).await?;
+            let rows = sqlx::query_with(&limited_sql, args)
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(|e| query_error(e, timeout))?;
+            // Nothing to keep; ending with ROLLBACK also discards any side
+            // effects of functions the query called
+            tx.rollback().await.map_err(|e| query_error(e, timeout))?;
+            Ok::<_, Error>(rows)
+        };
+
+        let rows = tokio::time::timeout(timeout + CLIENT_TIMEOUT_GRACE, fetch)
             .await
-            .map_err(|_| Error::Timeout(format!("Query timed out after {:?}", timeout)))?
-            .map_err(|e| Error::QueryExecution(e.to_string()))?;
+            .map_err(|_| timeout_error(timeout))??;
 
         let execution_time = start.elapsed();
 
@@ -67,26 +108,11 @@ impl Connector for PostgresConnector {
         }
 
         // Extract column information
-        let columns: Vec<ColumnDef> = rows[0]
-            .columns()
-            .iter()
-            .map(|c| ColumnDef {
-                name: c.name().to_string(),
-                data_type: c.type_info().name().to_string(),
-            })
-            .collect();
+        let columns = column_defs(&rows[0]);
 
         // Extract row data
-        let result_rows: Vec<Vec<serde_json::Value>> = rows
-            .iter()
-            .map(|row| {
-                columns
-                    .iter()
-                    .enumerate()
-                    .map(|(i, col)| pg_value_to_json(row, i, &col.data_type))
-                    .collect()
-            })
-            .collect();
+        let result_rows: Vec<Vec<serde_json::Value>> =
+            rows.iter().map(|row| row_values(row, &columns)).collect();
 
         let row_count = result_rows.len();
 
@@ -98,89 +124,76 @@ impl Connector for PostgresConnector {
         })
     }
 
-    async fn execute_with_params(
+    async fn execute_stream(
         &self,
         sql: &str,
         params: &[TypedValue],
         timeout: Duration,
         max_rows: usize,
-    ) -> Result<QueryOutput> {
-        let start = Instant::now();
-
-        // Wrap query with limit
+        batch_size: usize,
+    ) -> Result<RowStream> {
         let limited_sql = format!(
             "SELECT * FROM ({}) AS _q LIMIT {}",
             sql.trim().trim_end_matches(';'),
             max_rows
         );
+        let args = bind_args(params)?;
+        let pool = self.pool.clone();
 
-        // Build arguments
-        let mut args = PgArguments::default();
-        for param in params {
-            match param {
-                TypedValue::String(s) => args.add(s.as_str()).map_err(|e| Error::BadRequest(e.to_string()))?,
-                TypedValue::Number(n) => args.add(*n).map_err(|e| Error::BadRequest(e.to_string()))?,
-                TypedValue::Integer(i) => args.add(*i).map_err(|e| Error::BadRequest(e.to_string()))?,
-                TypedValue::Boolean(b) => args.add(*b).map_err(|e| Error::BadRequest(e.to_string()))?,
-                TypedValue::Date(d) => args.add(*d).map_err(|e| Error::BadRequest(e.to_string()))?,
-                TypedValue::DateTime(dt) => args.add(*dt).map_err(|e| Error::BadRequest(e.to_string()))?,
-                TypedValue::Null => {
-                    // For null, we need to bind as Option<String>
-                    let null_val: Option<String> = None;
-                    args.add(null_val).map_err(|e| Error::BadRequest(e.to_string()))?;
-                }
-            }
-        }
-
-        let rows = tokio::time::timeout(
-            timeout,
-            sqlx::query_with(&limited_sql, args).fetch_all(&self.pool),
-        )
-        .await
-        .map_err(|_| Error::Timeout(format!("Query timed out after {:?}", timeout)))?
-        .map_err(|e| Error::QueryExecution(e.to_string()))?;
-
-        let execution_time = start.elapsed();
-
-        if rows.is_empty() {
-            return Ok(QueryOutput {
-                columns: vec![],
-                rows: vec![],
-                row_count: 0,
-                execution_time,
-            });
-        }
+        Ok(spawn_row_stream(timeout, batch_size, move |mut out| async move {
+            // Hold one connection so its backend can be cancelled by PID
+            let mut conn = pool
+                .acquire()
+                .await
+                .map_err(|e| Error::Connection(format!("Failed to acquire connection: {}", e), error_code(&e)))?;
+            let pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()")
+                .fetch_one(&mut *conn)
+                .await
+                .map_err(|e| Error::Connection(e.to_string(), error_code(&e)))?;
 
-        // Extract column information
-        let columns: Vec<ColumnDef> = rows[0]
-            .columns()
-            .iter()
-            .map(|c| ColumnDef {
-                name: c.name().to_string(),
-                data_type: c.type_info().name().to_string(),
-            })
-            .collect();
+            let stop = out.stop_signal();
+            let read = async {
+                let mut tx = conn
+                    .begin_with(BEGIN_READ_ONLY)
+                    .await
+                    .map_err(|e| Error::Connection(format!("Failed to begin transaction: {}", e), error_code(&e)))?;
+                set_limits(&mut tx, timeout).await?;
 
-        // Extract row data
-        let result_rows: Vec<Vec<serde_json::Value>> = rows
-            .iter()
-            .map(|row| {
-                columns
-                    .iter()
-                    .enumerate()
-                    .map(|(i, col)| pg_value_to_json(row, i, &col.data_type))
-                    .collect()
-            })
-            .collect();
+                let mut rows = sqlx::query_with(&limited_sql, args).fetch(&mut *tx);
+                while let Some(row) = rows.try_next().await.map_err(|e| query_error(e, timeout))? {
+                    if out.columns().is_none() {
+                        out.set_columns(column_defs(&row));
+                    }
+                    let values = row_values(&row, out.columns().unwrap_or_default());
+                    if !out.push(values).await {
+                        // Rolling back would first drain the rest of the result
+                        return Ok(false);
+                    }
+                }
+                drop(rows);
+                tx.rollback().await.map_err(|e| query_error(e, timeout))?;
+                Ok(true)
+            };
 
-        let row_count = result_rows.len();
+            let error = tokio::select! {
+                result = read => match result {
+                    Ok(true) => return Ok(out),
+                    Ok(false) => None,
+                    Err(e) => return Err(e),
+                },
+                error = stop.wait() => Some(error),
+            };
 
-        Ok(QueryOutput {
-            columns,
-            rows: result_rows,
-            row_count,
-            execution_time,
-        })
+            // The output is full, the consumer is gone or the deadline passed:
+            // stop the query on the server too, and don't hand the half-read
+            // connection back
+            cancel_backend(&pool, pid).await;
+            let _ = conn.close().await;
+            match error {
+                Some(error) => Err(error),
+                None => Ok(out),
+            }
+        }))
     }
 
     async fn get_schema(&self) -> Result<Vec<TableSchema>> {
@@ -199,7 +212,7 @@ impl Connector for PostgresConnector {
         )
         .fetch_all(&self.pool)
         .await
-        .map_err(|e| Error::QueryExecution(format!("Failed to get schema: {}", e)))?;
+        .map_err(|e| Error::QueryExecution(format!("Failed to get schema: {}", e), error_code(&e)))?;
 
         let mut tables: Vec<TableSchema> = Vec::new();
         let mut current_table: Option<(String, String)> = None;
@@ -236,6 +249,107 @@ impl Connector for PostgresConnector {
 
         Ok(tables)
     }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        Some(sqlx_pool_stats(&self.pool))
+    }
+}
+
+/// Postgres reports SQLSTATEs (see `retry::POSTGRES_RETRYABLE`)
+fn error_code(e: &sqlx::Error) -> Option<ErrorCode> {
+    sqlx_error_code(e, |db| db.code().map(|state| ErrorCode::Postgres(state.into_owned())))
+}
+
+/// SQLSTATE of a statement cancelled by `statement_timeout` (or by
+/// `pg_cancel_backend`)
+const QUERY_CANCELED: &str = "57014";
+
+/// Map a failed statement, reporting server-side cancellation as a timeout
+fn query_error(e: sqlx::Error, timeout: Duration) -> Error {
+    match error_code(&e) {
+        Some(ErrorCode::Postgres(state)) if state == QUERY_CANCELED => timeout_error(timeout),
+        code => Error::QueryExecution(e.to_string(), code),
+    }
+}
+
+fn timeout_error(timeout: Duration) -> Error {
+    Error::Timeout(format!("Query timed out after {:?}", timeout))
+}
+
+/// Bound the current transaction by the run's timeout
+///
+/// Equivalent to `SET LOCAL` of `statement_timeout`, `lock_timeout` and
+/// `idle_in_transaction_session_timeout`, using `set_config(.., true)` so the
+/// value can be bound. All three get the whole timeout: the statement can't
+/// run longer, can't wait longer on locks, and if the client disappears
+/// mid-transaction the server ends the session after the same time.
+async fn set_limits(conn: &mut PgConnection, timeout: Duration) -> Result<()> {
+    sqlx::query(
+        r#"
+        SELECT set_config('statement_timeout', $1, true),
+               set_config('lock_timeout', $1, true),
+               set_config('idle_in_transaction_session_timeout', $1, true)
+        "#,
+    )
+    .bind(limit_ms(timeout))
+    .execute(conn)
+    .await
+    .map_err(|e| Error::QueryExecution(format!("Failed to set query limits: {}", e), error_code(&e)))?;
+    Ok(())
+}
+
+/// Setting value in milliseconds; at least 1, since 0 disables the limit
+fn limit_ms(timeout: Duration) -> String {
+    timeout.as_millis().max(1).to_string()
+}
+
+/// Ask the server to cancel whatever the backend is running
+async fn cancel_backend(pool: &PgPool, pid: i32) {
+    if let Err(e) = sqlx::query("SELECT pg_cancel_backend($1)")
+        .bind(pid)
+        .execute(pool)
+        .await
+    {
+        tracing::warn!(pid, "Failed to cancel Postgres backend: {}", e);
+    }
+}
+
+fn bind_args(params: &[TypedValue]) -> Result<PgArguments> {
+    let mut args = PgArguments::default();
+    for param in params {
+        match param {
+            TypedValue::String(s) => args.add(s.clone()).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Number(n) => args.add(*n).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Integer(i) => args.add(*i).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Boolean(b) => args.add(*b).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Date(d) => args.add(*d).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::DateTime(dt) => args.add(*dt).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Null => {
+                // For null, we need to bind as Option<String>
+                let null_val: Option<String> = None;
+                args.add(null_val).map_err(|e| Error::BadRequest(e.to_string()))?;
+            }
+        }
+    }
+    Ok(args)
+}
+
+fn column_defs(row: &PgRow) -> Vec<ColumnDef> {
+    row.columns()
+        .iter()
+        .map(|c| ColumnDef {
+            name: c.name().to_string(),
+            data_type: c.type_info().name().to_string(),
+        })
+        .collect()
+}
+
+fn row_values(row: &PgRow, columns: &[ColumnDef]) -> Vec<serde_json::Value> {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| pg_value_to_json(row, i, &col.data_type))
+        .collect()
 }
 
 fn pg_value_to_json(row: &sqlx::postgres::PgRow, idx: usize, type_name: &str) -> serde_json::Value {
@@ -0,0 +1,453 @@
+use super::http::{HttpConnector, substitute};
+use super::{ColumnSchema, Connector, QueryOutput, TableSchema};
+use crate::error::{Error, Result};
+use crate::models::ColumnDef;
+use crate::params::TypedValue;
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+/// Target number of points per series when a range query has no explicit step
+const DEFAULT_POINTS: i64 = 250;
+
+/// A saved PromQL query
+///
+/// Plain PromQL text runs as an instant query at the current time. For range
+/// queries (or an instant query at a fixed time) the query text is JSON:
+///
+/// ```json
+/// {"query": "sum by (job) (rate(http_requests_total{env=\"$env\"}[5m]))",
+///  "start": "$start", "end": "$end", "step": "$step"}
+/// ```
+///
+/// Times accept RFC 3339, dates, unix seconds, `now` and `now-<duration>`
+/// (e.g. `now-6h`). A range query needs `start`; `end` defaults to now and
+/// `step` to roughly 250 points across the range.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromQuery {
+    query: String,
+    #[serde(default)]
+    time: Option<String>,
+    #[serde(default)]
+    start: Option<String>,
+    #[serde(default)]
+    end: Option<String>,
+    #[serde(default)]
+    step: Option<String>,
+}
+
+impl PromQuery {
+    /// Parse query text as plain PromQL or a JSON query spec
+    ///
+    /// Only a JSON object with a `query` key is a spec, so selectors such as
+    /// `{job="api"}` still run as PromQL.
+    pub fn parse(text: &str) -> Result<Self> {
+        let trimmed = text.trim();
+        let spec = serde_json::from_str::<Value>(trimmed)
+            .ok()
+            .filter(|value| value.get("query").is_some());
+        let query = match spec {
+            Some(spec) => serde_json::from_value(spec)
+                .map_err(|e| Error::BadRequest(format!("Invalid PromQL query spec: {}", e)))?,
+            None => PromQuery {
+                query: trimmed.to_string(),
+                time: None,
+                start: None,
+                end: None,
+                step: None,
+            },
+        };
+
+        if query.query.trim().is_empty() {
+            return Err(Error::BadRequest("PromQL query is empty".to_string()));
+        }
+        if query.start.is_none() && (query.end.is_some() || query.step.is_some()) {
+            return Err(Error::BadRequest(
+                "PromQL range queries need a start time".to_string(),
+            ));
+        }
+
+        Ok(query)
+    }
+}
+
+/// Connector for the Prometheus HTTP API
+///
+/// The connection string is the Prometheus base URL, or the same JSON config
+/// the HTTP connector accepts (headers and auth).
+pub struct PrometheusConnector {
+    http: HttpConnector,
+}
+
+impl PrometheusConnector {
+    pub fn new(connection_string: &str) -> Result<Self> {
+        Ok(Self {
+            http: HttpConnector::new(connection_string)?,
+        })
+    }
+
+    async fn api(&self, path: &str, query: &[(&str, String)], timeout: Duration) -> Result<Value> {
+        let response = self.http.get_json(path, query, timeout).await?;
+
+        if response["status"] != "success" {
+            let message = response["error"].as_str().unwrap_or("unknown error");
+            return Err(Error::QueryExecution(format!("Prometheus error: {}", message), None));
+        }
+
+        Ok(response["data"].clone())
+    }
+}
+
+#[async_trait]
+impl Connector for PrometheusConnector {
+    async fn test_connection(&self) -> Result<Duration> {
+        let start = Instant::now();
+        self.api("/api/v1/status/buildinfo", &[], Duration::from_secs(10))
+            .await
+            .map_err(|e| {
+                let code = e.code().cloned();
+                Error::Connection(format!("Connection test failed: {}", e), code)
+            })?;
+        Ok(start.elapsed())
+    }
+
+    async fn execute(
+        &self,
+        sql: &str,
+        timeout: Duration,
+        max_rows: usize,
+    ) -> Result<QueryOutput> {
+        self.execute_with_params(sql, &[], timeout, max_rows).await
+    }
+
+    async fn execute_with_params(
+        &self,
+        sql: &str,
+        params: &[TypedValue],
+        timeout: Duration,
+        max_rows: usize,
+    ) -> Result<QueryOutput> {
+        let started = Instant::now();
+        let spec = PromQuery::parse(sql)?;
+        let now = Utc::now();
+
+        // String values land inside PromQL string literals, so escape them
+        let promql = substitute(&spec.query, params, |v| match v {
+            TypedValue::String(s) => s.replace('\\', "\\\\").replace('"', "\\\""),
+            other => other.to_plain_string(),
+        })?;
+        let bind = |value: &str| substitute(value, params, TypedValue::to_plain_string);
+
+        let mut query = vec![
+            ("query", promql),
+            ("timeout", format!("{}s", timeout.as_secs().max(1))),
+        ];
+
+        let path = match &spec.start {
+            Some(start) => {
+                let start = parse_time(&bind(start)?, now)?;
+                let end = match &spec.end {
+                    Some(end) => parse_time(&bind(end)?, now)?,
+                    None => now,
+                };
+                if end < start {
+                    return Err(Error::BadRequest("PromQL range end is before start".to_string()));
+                }
+                let step = match &spec.step {
+                    Some(step) => bind(step)?,
+                    None => format!("{}s", ((end - start).num_seconds() / DEFAULT_POINTS).max(1)),
+                };
+                query.push(("start", format_time(start)));
+                query.push(("end", format_time(end)));
+                query.push(("step", step));
+                "/api/v1/query_range"
+            }
+            None => {
+                if let Some(time) = &spec.time {
+                    query.push(("time", format_time(parse_time(&bind(time)?, now)?)));
+                }
+                "/api/v1/query"
+            }
+        };
+
+        let data = self.api(path, &query, timeout).await?;
+        let (columns, rows) = result_to_rows(&data, max_rows)?;
+        let row_count = rows.len();
+
+        Ok(QueryOutput {
+            columns,
+            rows,
+            row_count,
+            execution_time: started.elapsed(),
+        })
+    }
+
+    async fn get_schema(&self) -> Result<Vec<TableSchema>> {
+        let timeout = Duration::from_secs(30);
+        let metrics = self.api("/api/v1/label/__name__/values", &[], timeout).await?;
+        let labels = self.api("/api/v1/labels", &[], timeout).await?;
+
+        // Metrics are listed as tables; label keys apply across all metrics,
+        // so they are listed once rather than per metric
+        let mut tables: Vec<TableSchema> = string_list(&metrics)
+            .into_iter()
+            .map(|name| TableSchema {
+                schema: "metrics".to_string(),
+                name,
+                columns: vec![
+                    ColumnSchema {
+                        name: "timestamp".to_string(),
+                        data_type: "TIMESTAMP".to_string(),
+                        is_nullable: false,
+                    },
+                    ColumnSchema {
+                        name: "value".to_string(),
+                        data_type: "REAL".to_string(),
+                        is_nullable: true,
+                    },
+                ],
+            })
+            .collect();
+
+        tables.push(TableSchema {
+            schema: "labels".to_string(),
+            name: "labels".to_string(),
+            columns: string_list(&labels)
+                .into_iter()
+                .filter(|l| l != "__name__")
+                .map(|name| ColumnSchema {
+                    name,
+                    data_type: "TEXT".to_string(),
+                    is_nullable: true,
+                })
+                .collect(),
+        });
+
+        Ok(tables)
+    }
+}
+
+fn string_list(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| items.iter().filter_map(|v| v.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+/// Parse a query time: RFC 3339, date, unix seconds, `now` or `now-<duration>`
+fn parse_time(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let v = value.trim();
+    let invalid = || Error::BadRequest(format!("Invalid PromQL time '{}'", value));
+
+    if let Some(rest) = v.strip_prefix("now") {
+        if rest.is_empty() {
+            return Ok(now);
+        }
+        let offset = rest.strip_prefix('-').ok_or_else(invalid)?;
+        let seconds = parse_duration_secs(offset).ok_or_else(invalid)?;
+        return Ok(now - chrono::Duration::seconds(seconds));
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(v) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+    if let Ok(d) = chrono::NaiveDate::parse_from_str(v, "%Y-%m-%d") {
+        return Ok(d.and_hms_opt(0, 0, 0).ok_or_else(invalid)?.and_utc());
+    }
+    if let Ok(secs) = v.parse::<f64>() {
+        return DateTime::from_timestamp_millis((secs * 1000.0) as i64).ok_or_else(invalid);
+    }
+
+    Err(invalid())
+}
+
+/// Parse a Prometheus-style duration such as `90s`, `5m`, `6h`, `7d` or `1w`
+fn parse_duration_secs(value: &str) -> Option<i64> {
+    let split = value.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse().ok()?;
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return None,
+    };
+    amount.checked_mul(multiplier)
+}
+
+fn format_time(t: DateTime<Utc>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn sample_timestamp(value: &Value) -> Value {
+    value
+        .as_f64()
+        .and_then(|secs| DateTime::from_timestamp_millis((secs * 1000.0).round() as i64))
+        .map(|t| Value::from(format_time(t)))
+        .unwrap_or(Value::Null)
+}
+
+fn sample_value(value: &Value) -> Value {
+    // Prometheus encodes sample values as strings; NaN/Inf have no JSON form
+    value
+        .as_str()
+        .and_then(|s| s.parse::<f64>().ok())
+        .and_then(serde_json::Number::from_f64)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+/// Map vector/matrix/scalar/string results into long-format rows:
+/// `timestamp`, one column per label key, `value`
+fn result_to_rows(data: &Value, max_rows: usize) -> Result<(Vec<ColumnDef>, Vec<Vec<Value>>)> {
+    let result_type = data["resultType"].as_str().unwrap_or_default();
+    let result = &data["result"];
+
+    let column = |name: &str, data_type: &str| ColumnDef {
+        name: name.to_string(),
+        data_type: data_type.to_string(),
+    };
+
+    match result_type {
+        "scalar" | "string" => {
+            let value_type = if result_type == "scalar" { "REAL" } else { "TEXT" };
+            let value = if result_type == "scalar" {
+                sample_value(&result[1])
+            } else {
+                result[1].clone()
+            };
+            Ok((
+                vec![column("timestamp", "TIMESTAMP"), column("value", value_type)],
+                vec![vec![sample_timestamp(&result[0]), value]],
+            ))
+        }
+        "vector" | "matrix" => {
+            let series = result.as_array().cloned().unwrap_or_default();
+
+            let label_keys: Vec<String> = series
+                .iter()
+                .filter_map(|s| s["metric"].as_object())
+                .flat_map(|m| m.keys().cloned())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+
+            let mut rows = Vec::new();
+            'series: for s in &series {
+                let labels: Vec<Value> = label_keys
+                    .iter()
+                    .map(|k| s["metric"].get(k).cloned().unwrap_or(Value::Null))
+                    .collect();
+
+                let samples: Vec<&Value> = match s.get("values").and_then(Value::as_array) {
+                    Some(values) => values.iter().collect(),
+                    None => s.get("value").into_iter().collect(),
+                };
+
+                for sample in samples {
+                    if rows.len() >= max_rows {
+                        break 'series;
+                    }
+                    let mut row = Vec::with_capacity(label_keys.len() + 2);
+                    row.push(sample_timestamp(&sample[0]));
+                    row.extend(labels.iter().cloned());
+                    row.push(sample_value(&sample[1]));
+                    rows.push(row);
+                }
+            }
+
+            let mut columns = vec![column("timestamp", "TIMESTAMP")];
+            columns.extend(label_keys.iter().map(|k| column(k, "TEXT")));
+            columns.push(column("value", "REAL"));
+
+            Ok((columns, rows))
+        }
+        other => Err(Error::QueryExecution(
+            format!("Unsupported Prometheus result type '{}'", other),
+            None,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_plain_and_json_queries() {
+        let plain = PromQuery::parse("up").unwrap();
+        assert_eq!(plain.query, "up");
+        assert!(plain.start.is_none());
+
+        let range = PromQuery::parse(r#"{"query": "up", "start": "now-1h", "step": "30s"}"#).unwrap();
+        assert_eq!(range.start.as_deref(), Some("now-1h"));
+
+        assert!(PromQuery::parse("  ").is_err());
+        assert!(PromQuery::parse(r#"{"query": "up", "end": "now"}"#).is_err());
+        assert!(PromQuery::parse(r#"{"query": "up", "bogus": 1}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_bare_selectors_as_promql() {
+        for selector in [r#"{job="api"}"#, r#"{__name__=~"http_.*"}"#, r#" {job="api"} "#] {
+            let query = PromQuery::parse(selector).unwrap();
+            assert_eq!(query.query, selector.trim());
+            assert!(query.start.is_none());
+        }
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let now = DateTime::parse_from_rfc3339("2024-01-15T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(parse_time("now", now).unwrap(), now);
+        assert_eq!(format_time(parse_time("now-6h", now).unwrap()), "2024-01-15T06:00:00.000Z");
+        assert_eq!(format_time(parse_time("2024-01-01", now).unwrap()), "2024-01-01T00:00:00.000Z");
+        assert_eq!(format_time(parse_time("1700000000", now).unwrap()), "2023-11-14T22:13:20.000Z");
+        assert!(parse_time("now+1h", now).is_err());
+        assert!(parse_time("yesterday", now).is_err());
+    }
+
+    #[test]
+    fn test_matrix_to_rows() {
+        let data = json!({
+            "resultType": "matrix",
+            "result": [
+                {"metric": {"job": "api", "instance": "a"}, "values": [[1700000000, "1"], [1700000060, "2.5"]]},
+                {"metric": {"job": "db"}, "values": [[1700000000, "NaN"]]}
+            ]
+        });
+
+        let (columns, rows) = result_to_rows(&data, 100).unwrap();
+        let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["timestamp", "instance", "job", "value"]);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1], vec![json!("2023-11-14T22:14:20.000Z"), json!("a"), json!("api"), json!(2.5)]);
+        assert_eq!(rows[2][1], Value::Null);
+        assert_eq!(rows[2][3], Value::Null);
+
+        let (_, limited) = result_to_rows(&data, 2).unwrap();
+        assert_eq!(limited.len(), 2);
+    }
+
+    #[test]
+    fn test_vector_and_scalar_to_rows() {
+        let vector = json!({
+            "resultType": "vector",
+            "result": [{"metric": {"__name__": "up"}, "value": [1700000000.5, "1"]}]
+        });
+        let (columns, rows) = result_to_rows(&vector, 100).unwrap();
+        assert_eq!(columns[1].name, "__name__");
+        assert_eq!(rows, vec![vec![json!("2023-11-14T22:13:20.500Z"), json!("up"), json!(1.0)]]);
+
+        let scalar = json!({"resultType": "scalar", "result": [1700000000, "42"]});
+        let (columns, rows) = result_to_rows(&scalar, 100).unwrap();
+        assert_eq!(columns.len(), 2);
+        assert_eq!(rows[0][1], json!(42.0));
+    }
+}
@@ -0,0 +1,222 @@
+//! Retry classification of connector errors
+//!
+//! Connectors attach the datasource's own error code to `Error::Connection`
+//! and `Error::QueryExecution` (see `ErrorCode`). Whether a failed run is
+//! worth retrying is decided from that code alone, using one table per
+//! connector: transient conditions (lost connections, deadlocks, overload)
+//! get a `RetryClass`, anything the query itself caused (syntax,
+//! permissions, missing tables, bad data) is never retried. A run's
+//! `RetryPolicy` picks which classes it retries.
+
+use crate::error::{Error, ErrorCode};
+use crate::models::RetryClass;
+use sqlx::error::DatabaseError;
+
+use RetryClass::{Connection, Timeout, Transient};
+
+/// Retryable Postgres SQLSTATEs; two-character entries cover a whole class
+pub const POSTGRES_RETRYABLE: &[(&str, RetryClass)] = &[
+    ("08", Connection),    // connection_exception
+    ("53", Transient),     // insufficient_resources (too_many_connections, out_of_memory, ...)
+    ("40001", Transient),  // serialization_failure
+    ("40P01", Transient),  // deadlock_detected
+    ("55P03", Transient),  // lock_not_available
+    ("57P01", Connection), // admin_shutdown
+    ("57P02", Connection), // crash_shutdown
+    ("57P03", Connection), // cannot_connect_now
+];
+
+/// Retryable MySQL error numbers
+pub const MYSQL_RETRYABLE: &[(u16, RetryClass)] = &[
+    (1040, Transient),  // ER_CON_COUNT_ERROR (too many connections)
+    (1053, Connection), // ER_SERVER_SHUTDOWN
+    (1158, Connection), // ER_NET_READ_ERROR
+    (1159, Connection), // ER_NET_READ_INTERRUPTED
+    (1160, Connection), // ER_NET_ERROR_ON_WRITE
+    (1161, Connection), // ER_NET_WRITE_INTERRUPTED
+    (1205, Transient),  // ER_LOCK_WAIT_TIMEOUT
+    (1213, Transient),  // ER_LOCK_DEADLOCK
+    (1927, Connection), // ER_CONNECTION_KILLED
+    (2006, Connection), // CR_SERVER_GONE_ERROR
+    (2013, Connection), // CR_SERVER_LOST
+];
+
+/// Retryable SQLite primary result codes (extended codes are reduced to
+/// their primary code first)
+pub const SQLITE_RETRYABLE: &[(i32, RetryClass)] = &[
+    (5, Transient), // SQLITE_BUSY
+    (6, Transient), // SQLITE_LOCKED
+];
+
+/// Retryable HTTP statuses from HTTP and Prometheus datasources
+pub const HTTP_RETRYABLE: &[(u16, RetryClass)] = &[
+    (408, Timeout),    // Request Timeout
+    (429, Transient),  // Too Many Requests
+    (502, Connection), // Bad Gateway
+    (503, Transient),  // Service Unavailable
+    (504, Timeout),    // Gateway Timeout
+];
+
+/// Retryable connector plugin error codes
+pub const PLUGIN_RETRYABLE: &[(i64, RetryClass)] =
+    &[(super::plugin::error_codes::CONNECTION, Connection)];
+
+/// Whether a run that failed with this error could be retried
+pub fn is_retryable(error: &Error) -> bool {
+    retry_class(error).is_some()
+}
+
+/// Kind of transient failure, or None if retrying can't help
+///
+/// Timeouts always classify; connection and query errors only when their
+/// code is in their connector's table. Errors without a code never do.
+pub fn retry_class(error: &Error) -> Option<RetryClass> {
+    match error {
+        Error::Timeout(_) => Some(Timeout),
+        Error::Connection(_, Some(code)) | Error::QueryExecution(_, Some(code)) => {
+            code_retry_class(code)
+        }
+        _ => None,
+    }
+}
+
+/// Look a code up in its connector's table
+pub fn code_retry_class(code: &ErrorCode) -> Option<RetryClass> {
+    fn find<T: PartialEq>(table: &[(T, RetryClass)], code: &T) -> Option<RetryClass> {
+        table.iter().find(|(c, _)| c == code).map(|(_, class)| *class)
+    }
+
+    match code {
+        ErrorCode::Network => Some(Connection),
+        ErrorCode::Postgres(state) => POSTGRES_RETRYABLE
+            .iter()
+            .find(|(prefix, _)| state.starts_with(prefix))
+            .map(|(_, class)| *class),
+        ErrorCode::MySql(number) => find(MYSQL_RETRYABLE, number),
+        ErrorCode::Sqlite(code) => find(SQLITE_RETRYABLE, &(code & 0xff)),
+        ErrorCode::Http(status) => find(HTTP_RETRYABLE, status),
+        ErrorCode::Plugin(code) => find(PLUGIN_RETRYABLE, code),
+    }
+}
+
+/// Code for an error from a sqlx-based connector
+///
+/// Failures below the protocol map to `ErrorCode::Network`; errors reported
+/// by the server are translated by the connector's `database` function.
+pub(super) fn sqlx_error_code(
+    error: &sqlx::Error,
+    database: fn(&dyn DatabaseError) -> Option<ErrorCode>,
+) -> Option<ErrorCode> {
+    match error {
+        sqlx::Error::Database(e) => database(e.as_ref()),
+        sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::PoolTimedOut
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed => Some(ErrorCode::Network),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query_error(code: ErrorCode) -> Error {
+        Error::QueryExecution("failed".to_string(), Some(code))
+    }
+
+    #[test]
+    fn test_postgres_codes() {
+        let retryable = ["08006", "08001", "53300", "40001", "40P01", "55P03", "57P01", "57P03"];
+        for state in retryable {
+            assert!(is_retryable(&query_error(ErrorCode::Postgres(state.into()))), "{state}");
+        }
+
+        // syntax_error, undefined_column, insufficient_privilege,
+        // invalid_password, division_by_zero, unique_violation, query_canceled
+        let permanent = ["42601", "42703", "42501", "28P01", "22012", "23505", "57014"];
+        for state in permanent {
+            assert!(!is_retryable(&query_error(ErrorCode::Postgres(state.into()))), "{state}");
+        }
+    }
+
+    #[test]
+    fn test_mysql_codes() {
+        for number in [1040, 1205, 1213, 2006, 2013] {
+            assert!(is_retryable(&query_error(ErrorCode::MySql(number))), "{number}");
+        }
+        // syntax, access denied, unknown table, unknown column
+        for number in [1064, 1045, 1146, 1054] {
+            assert!(!is_retryable(&query_error(ErrorCode::MySql(number))), "{number}");
+        }
+    }
+
+    #[test]
+    fn test_sqlite_codes() {
+        assert!(is_retryable(&query_error(ErrorCode::Sqlite(5))));
+        // SQLITE_BUSY_SNAPSHOT reduces to SQLITE_BUSY
+        assert!(is_retryable(&query_error(ErrorCode::Sqlite(517))));
+        // SQLITE_ERROR, SQLITE_READONLY, SQLITE_CANTOPEN
+        for code in [1, 8, 14] {
+            assert!(!is_retryable(&query_error(ErrorCode::Sqlite(code))), "{code}");
+        }
+    }
+
+    #[test]
+    fn test_http_and_plugin_codes() {
+        for status in [429, 502, 503, 504] {
+            assert!(is_retryable(&query_error(ErrorCode::Http(status))), "{status}");
+        }
+        for status in [400, 401, 403, 404, 500] {
+            assert!(!is_retryable(&query_error(ErrorCode::Http(status))), "{status}");
+        }
+
+        let connection = super::super::plugin::error_codes::CONNECTION;
+        let query = super::super::plugin::error_codes::QUERY;
+        assert!(is_retryable(&query_error(ErrorCode::Plugin(connection))));
+        assert!(!is_retryable(&query_error(ErrorCode::Plugin(query))));
+    }
+
+    #[test]
+    fn test_uncoded_errors_are_not_retried() {
+        // Error text no longer matters, e.g. a column named connection_id
+        let err = Error::QueryExecution(
+            "column \"connection_id\" does not exist".to_string(),
+            Some(ErrorCode::Postgres("42703".into())),
+        );
+        assert!(!is_retryable(&err));
+        assert!(!is_retryable(&Error::QueryExecution("connection reset".to_string(), None)));
+        assert!(!is_retryable(&Error::Connection("Plugin 'x' exited".to_string(), None)));
+        assert!(!is_retryable(&Error::Database("connection lost".to_string())));
+    }
+
+    #[test]
+    fn test_network_and_timeouts_are_retried() {
+        assert!(is_retryable(&Error::Connection("refused".to_string(), Some(ErrorCode::Network))));
+        assert!(is_retryable(&Error::Timeout("Query timed out".to_string())));
+    }
+
+    #[test]
+    fn test_retry_classes() {
+        let class = |code| retry_class(&query_error(code));
+        assert_eq!(class(ErrorCode::Postgres("08006".into())), Some(Connection));
+        assert_eq!(class(ErrorCode::Postgres("40P01".into())), Some(Transient));
+        assert_eq!(class(ErrorCode::MySql(2013)), Some(Connection));
+        assert_eq!(class(ErrorCode::MySql(1213)), Some(Transient));
+        assert_eq!(class(ErrorCode::Http(504)), Some(Timeout));
+        assert_eq!(class(ErrorCode::Network), Some(Connection));
+        assert_eq!(retry_class(&Error::Timeout("slow".to_string())), Some(Timeout));
+    }
+
+    #[test]
+    fn test_sqlx_transport_errors_are_network() {
+        assert_eq!(
+            sqlx_error_code(&sqlx::Error::PoolTimedOut, |_| None),
+            Some(ErrorCode::Network)
+        );
+        let io = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset");
+        assert_eq!(sqlx_error_code(&sqlx::Error::Io(io), |_| None), Some(ErrorCode::Network));
+        assert_eq!(sqlx_error_code(&sqlx::Error::RowNotFound, |_| None), None);
+    }
+}
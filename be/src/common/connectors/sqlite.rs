@@ -0,0 +1,424 @@
+use super::retry::sqlx_error_code;
+use super::stream::spawn_row_stream;
+use super::{ColumnSchema, Connector, PoolStats, QueryOutput, RowStream, TableSchema, sqlx_pool_stats};
+use crate::error::{Error, ErrorCode, Result};
+use crate::models::ColumnDef;
+use crate::params::TypedValue;
+use crate::uploads;
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose};
+use futures_util::TryStreamExt;
+use sqlx::sqlite::{SqliteArguments, SqliteConnectOptions, SqlitePoolOptions, SqliteRow};
+use sqlx::{Arguments, Column, Row, SqlitePool, TypeInfo, ValueRef};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Environment variable listing directories SQLite datasources may read from
+pub const SQLITE_ALLOWED_DIRS_ENV: &str = "SQLITE_ALLOWED_DIRS";
+
+/// Connector for file-backed SQLite databases
+///
+/// The connection string is a `sqlite://` URL holding an absolute path to the
+/// database file (e.g. `sqlite:///srv/data/ops.db`). Files are opened read-only
+/// and must live inside one of the directories listed in `SQLITE_ALLOWED_DIRS`.
+pub struct SqliteConnector {
+    pool: SqlitePool,
+}
+
+impl SqliteConnector {
+    /// Open a SQLite datasource, checking the path against `SQLITE_ALLOWED_DIRS`
+    pub async fn new(connection_string: &str) -> Result<Self> {
+        Self::with_allowed_dirs(connection_string, &allowed_dirs_from_env()).await
+    }
+
+    /// Check a user-supplied connection string without opening it
+    ///
+    /// Uploaded files are only reachable through the datasource created by the
+    /// upload endpoint, so paths inside `UPLOAD_DIR` are rejected here.
+    pub fn validate_path(connection_string: &str) -> Result<PathBuf> {
+        let path = resolve_path(connection_string, &allowed_dirs_from_env())?;
+        if uploads::is_upload_path(&path) {
+            return Err(Error::Forbidden(
+                "SQLite datasources cannot point into the upload directory".to_string(),
+            ));
+        }
+        Ok(path)
+    }
+
+    /// Open a SQLite datasource, checking the path against an explicit allow-list
+    pub async fn with_allowed_dirs(connection_string: &str, allowed_dirs: &[PathBuf]) -> Result<Self> {
+        let path = resolve_path(connection_string, allowed_dirs)?;
+
+        let options = SqliteConnectOptions::new()
+            .filename(&path)
+            .read_only(true)
+            .create_if_missing(false);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .acquire_timeout(Duration::from_secs(10))
+            .connect_with(options)
+            .await
+            .map_err(|e| Error::Connection(format!("Failed to open SQLite database: {}", e), error_code(&e)))?;
+
+        Ok(Self { pool })
+    }
+
+    async fn fetch(
+        &self,
+        sql: &str,
+        args: SqliteArguments<'_>,
+        timeout: Duration,
+        max_rows: usize,
+    ) -> Result<QueryOutput> {
+        let start = Instant::now();
+
+        // Wrap query with limit
+        let limited_sql = format!(
+            "SELECT * FROM ({}) AS _q LIMIT {}",
+            sql.trim().trim_end_matches(';'),
+            max_rows
+        );
+
+        let rows = tokio::time::timeout(
+            timeout,
+            sqlx::query_with(&limited_sql, args).fetch_all(&self.pool),
+        )
+        .await
+        .map_err(|_| Error::Timeout(format!("Query timed out after {:?}", timeout)))?
+        .map_err(|e| Error::QueryExecution(e.to_string(), error_code(&e)))?;
+
+        let execution_time = start.elapsed();
+
+        if rows.is_empty() {
+            return Ok(QueryOutput {
+                columns: vec![],
+                rows: vec![],
+                row_count: 0,
+                execution_time,
+            });
+        }
+
+        // Extract column information
+        let columns = column_defs(&rows[0]);
+
+        // Extract row data
+        let result_rows: Vec<Vec<serde_json::Value>> =
+            rows.iter().map(|row| row_values(row, &columns)).collect();
+
+        let row_count = result_rows.len();
+
+        Ok(QueryOutput {
+            columns,
+            rows: result_rows,
+            row_count,
+            execution_time,
+        })
+    }
+}
+
+#[async_trait]
+impl Connector for SqliteConnector {
+    async fn test_connection(&self) -> Result<Duration> {
+        let start = Instant::now();
+        sqlx::query("SELECT 1")
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| Error::Connection(format!("Connection test failed: {}", e), error_code(&e)))?;
+        Ok(start.elapsed())
+    }
+
+    async fn execute(
+        &self,
+        sql: &str,
+        timeout: Duration,
+        max_rows: usize,
+    ) -> Result<QueryOutput> {
+        self.fetch(sql, SqliteArguments::default(), timeout, max_rows)
+            .await
+    }
+
+    async fn execute_with_params(
+        &self,
+        sql: &str,
+        params: &[TypedValue],
+        timeout: Duration,
+        max_rows: usize,
+    ) -> Result<QueryOutput> {
+        let args = bind_args(params)?;
+        self.fetch(sql, args, timeout, max_rows).await
+    }
+
+    async fn execute_stream(
+        &self,
+        sql: &str,
+        params: &[TypedValue],
+        timeout: Duration,
+        max_rows: usize,
+        batch_size: usize,
+    ) -> Result<RowStream> {
+        let limited_sql = format!(
+            "SELECT * FROM ({}) AS _q LIMIT {}",
+            sql.trim().trim_end_matches(';'),
+            max_rows
+        );
+        let args = bind_args(params)?;
+        let pool = self.pool.clone();
+
+        Ok(spawn_row_stream(timeout, batch_size, move |mut out| async move {
+            let mut rows = sqlx::query_with(&limited_sql, args).fetch(&pool);
+            while let Some(row) = rows
+                .try_next()
+                .await
+                .map_err(|e| Error::QueryExecution(e.to_string(), error_code(&e)))?
+            {
+                if out.columns().is_none() {
+                    out.set_columns(column_defs(&row));
+                }
+                let values = row_values(&row, out.columns().unwrap_or_default());
+                if !out.push(values).await {
+                    break;
+                }
+            }
+            drop(rows);
+            Ok(out)
+        }))
+    }
+
+    async fn get_schema(&self) -> Result<Vec<TableSchema>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                m.name AS table_name,
+                p.name AS column_name,
+                p.type AS data_type,
+                p."notnull" AS not_null
+            FROM sqlite_master m
+            JOIN pragma_table_info(m.name) p
+            WHERE m.type IN ('table', 'view')
+              AND m.name NOT LIKE 'sqlite_%'
+            ORDER BY m.name, p.cid
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::QueryExecution(format!("Failed to get schema: {}", e), error_code(&e)))?;
+
+        let mut tables: Vec<TableSchema> = Vec::new();
+
+        for row in rows {
+            let table: String = row.get("table_name");
+            let column_name: String = row.get("column_name");
+            let data_type: String = row.get("data_type");
+            let not_null: bool = row.get("not_null");
+
+            let col = ColumnSchema {
+                name: column_name,
+                data_type,
+                is_nullable: !not_null,
+            };
+
+            match tables.last_mut() {
+                Some(last) if last.name == table => last.columns.push(col),
+                _ => tables.push(TableSchema {
+                    schema: "main".to_string(),
+                    name: table,
+                    columns: vec![col],
+                }),
+            }
+        }
+
+        Ok(tables)
+    }
+
+    fn pool_stats(&self) -> Option<PoolStats> {
+        Some(sqlx_pool_stats(&self.pool))
+    }
+}
+
+/// Read the SQLite allow-list from `SQLITE_ALLOWED_DIRS` (comma-separated)
+///
+/// The upload directory is always allowed so uploaded datasources can be opened.
+/// With neither variable set, no SQLite datasources can be opened.
+fn allowed_dirs_from_env() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::env::var(SQLITE_ALLOWED_DIRS_ENV)
+        .map(|dirs| {
+            dirs.split(',')
+                .map(str::trim)
+                .filter(|d| !d.is_empty())
+                .map(PathBuf::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.extend(uploads::upload_dir());
+    dirs
+}
+
+/// Resolve a SQLite connection string to a canonical file path inside the allow-list
+///
+/// Canonicalization follows symlinks and removes `..`, so a path cannot escape
+/// an allowed directory.
+fn resolve_path(connection_string: &str, allowed_dirs: &[PathBuf]) -> Result<PathBuf> {
+    let raw = connection_string
+        .strip_prefix("sqlite://")
+        .or_else(|| connection_string.strip_prefix("sqlite:"))
+        .unwrap_or(connection_string);
+    // Drop URL query options (e.g. ?mode=rwc); access mode is always read-only
+    let raw = raw.split('?').next().unwrap_or(raw);
+
+    let path = Path::new(raw);
+    if !path.is_absolute() {
+        return Err(Error::BadRequest(
+            "SQLite datasource path must be absolute".to_string(),
+        ));
+    }
+
+    let canonical = path.canonicalize().map_err(|_| {
+        Error::BadRequest("SQLite database file does not exist or is not readable".to_string())
+    })?;
+
+    if !canonical.is_file() {
+        return Err(Error::BadRequest(
+            "SQLite datasource path must point to a file".to_string(),
+        ));
+    }
+
+    let allowed = allowed_dirs
+        .iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .any(|dir| canonical.starts_with(&dir));
+
+    if !allowed {
+        return Err(Error::Forbidden(format!(
+            "SQLite database path is outside the directories allowed by {}",
+            SQLITE_ALLOWED_DIRS_ENV
+        )));
+    }
+
+    Ok(canonical)
+}
+
+/// SQLite reports its (extended) result code (see `retry::SQLITE_RETRYABLE`)
+fn error_code(e: &sqlx::Error) -> Option<ErrorCode> {
+    sqlx_error_code(e, |db| db.code().and_then(|code| code.parse().ok()).map(ErrorCode::Sqlite))
+}
+
+fn bind_args(params: &[TypedValue]) -> Result<SqliteArguments<'static>> {
+    // SQLite understands $1, $2 natively; dates are stored as ISO-8601 text
+    let mut args = SqliteArguments::default();
+    for param in params {
+        match param {
+            TypedValue::String(s) => args.add(s.clone()).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Number(n) => args.add(*n).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Integer(i) => args.add(*i).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Boolean(b) => args.add(*b).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Date(d) => args.add(d.to_string()).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::DateTime(dt) => args.add(dt.to_rfc3339()).map_err(|e| Error::BadRequest(e.to_string()))?,
+            TypedValue::Null => {
+                let null_val: Option<String> = None;
+                args.add(null_val).map_err(|e| Error::BadRequest(e.to_string()))?;
+            }
+        }
+    }
+    Ok(args)
+}
+
+/// Column definitions from the first row of a result
+///
+/// Expression columns have no declared type, so fall back to the storage
+/// class of the row's value.
+fn column_defs(row: &SqliteRow) -> Vec<ColumnDef> {
+    row.columns()
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let declared = c.type_info().name().to_string();
+            let data_type = if declared == "NULL" {
+                row.try_get_raw(i)
+                    .map(|v| v.type_info().name().to_string())
+                    .unwrap_or(declared)
+            } else {
+                declared
+            };
+            ColumnDef {
+                name: c.name().to_string(),
+                data_type,
+            }
+        })
+        .collect()
+}
+
+fn row_values(row: &SqliteRow, columns: &[ColumnDef]) -> Vec<serde_json::Value> {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| sqlite_value_to_json(row, i, &col.data_type))
+        .collect()
+}
+
+fn sqlite_value_to_json(row: &SqliteRow, idx: usize, declared_type: &str) -> serde_json::Value {
+    // SQLite is dynamically typed, so decode by the value's storage class
+    let storage_class = match row.try_get_raw(idx) {
+        Ok(raw) if raw.is_null() => return serde_json::Value::Null,
+        Ok(raw) => raw.type_info().name().to_uppercase(),
+        Err(_) => return serde_json::Value::Null,
+    };
+
+    match storage_class.as_str() {
+        // Booleans are stored as 0/1; honour the declared column type
+        "INTEGER" if declared_type.eq_ignore_ascii_case("BOOLEAN") => row
+            .try_get_unchecked::<bool, _>(idx)
+            .map(serde_json::Value::from)
+            .unwrap_or(serde_json::Value::Null),
+        "INTEGER" | "BOOLEAN" => row
+            .try_get_unchecked::<i64, _>(idx)
+            .map(serde_json::Value::from)
+            .unwrap_or(serde_json::Value::Null),
+        "REAL" | "NUMERIC" => row
+            .try_get_unchecked::<f64, _>(idx)
+            .map(|v| serde_json::json!(v))
+            .unwrap_or(serde_json::Value::Null),
+        "BLOB" => row
+            .try_get_unchecked::<Vec<u8>, _>(idx)
+            .map(|v| serde_json::Value::from(general_purpose::STANDARD.encode(v)))
+            .unwrap_or(serde_json::Value::Null),
+        _ => row
+            .try_get_unchecked::<String, _>(idx)
+            .map(serde_json::Value::from)
+            .unwrap_or(serde_json::Value::Null),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_path_requires_absolute() {
+        let result = resolve_path("sqlite://relative/db.sqlite", &[std::env::temp_dir()]);
+        assert!(matches!(result, Err(Error::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_resolve_path_rejects_outside_allow_list() {
+        let dir = std::env::temp_dir().join(format!("loupe-sqlite-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("ops.db");
+        std::fs::write(&file, b"").unwrap();
+
+        let other = std::env::temp_dir().join(format!("loupe-other-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&other).unwrap();
+
+        let conn = format!("sqlite://{}", file.display());
+        assert!(matches!(resolve_path(&conn, std::slice::from_ref(&other)), Err(Error::Forbidden(_))));
+        assert!(matches!(resolve_path(&conn, &[]), Err(Error::Forbidden(_))));
+        assert!(resolve_path(&conn, std::slice::from_ref(&dir)).is_ok());
+
+        // Traversal out of the allowed directory is caught after canonicalization
+        let escape = format!("{}/../{}/ops.db", other.display(), dir.file_name().unwrap().to_string_lossy());
+        assert!(matches!(resolve_path(&escape, std::slice::from_ref(&other)), Err(Error::Forbidden(_))));
+
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::remove_dir_all(&other).ok();
+    }
+}
@@ -0,0 +1,353 @@
+//! Streaming query results
+//!
+//! Connectors that can read rows incrementally produce them on a background
+//! task and hand them over in batches through a small bounded channel, so at
+//! most a couple of batches are in memory at once. Dropping the stream stops
+//! the producer, which lets callers stop reading as soon as a limit is hit.
+//! Producers that can cancel work on the server side (Postgres, MySQL) watch
+//! a `StopSignal` so a dropped or timed-out stream also stops the query.
+
+use super::QueryOutput;
+use crate::error::{Error, Result};
+use crate::models::{ColumnDef, TruncationReason};
+use futures_util::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use serde_json::Value;
+use serde_json::value::RawValue;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// Default number of rows per batch
+pub const DEFAULT_BATCH_SIZE: usize = 1000;
+
+/// Batches buffered between the producer and the consumer
+const CHANNEL_CAPACITY: usize = 2;
+
+/// Extra time a producer gets past its deadline to cancel its query before
+/// the task gives up on it
+const STOP_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// A batch of rows from a streaming query
+#[derive(Debug, Clone)]
+pub struct RowBatch {
+    /// Result columns, shared by every batch of the query
+    pub columns: Arc<Vec<ColumnDef>>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Stream of row batches; an error ends the stream
+pub type RowStream = BoxStream<'static, Result<RowBatch>>;
+
+/// Split a fully materialized result into batches
+pub fn output_to_stream(output: QueryOutput, batch_size: usize) -> RowStream {
+    let columns = Arc::new(output.columns);
+    let mut batches = Vec::new();
+    let mut rows = output.rows.into_iter().peekable();
+
+    while rows.peek().is_some() {
+        batches.push(Ok(RowBatch {
+            columns: columns.clone(),
+            rows: rows.by_ref().take(batch_size.max(1)).collect(),
+        }));
+    }
+
+    stream::iter(batches).boxed()
+}
+
+/// Producer half of a streaming query, batching rows as they are read
+pub(super) struct BatchSender {
+    tx: mpsc::Sender<Result<RowBatch>>,
+    columns: Option<Arc<Vec<ColumnDef>>>,
+    rows: Vec<Vec<Value>>,
+    batch_size: usize,
+    deadline: Instant,
+    timeout: Duration,
+}
+
+impl BatchSender {
+    /// Columns set by the first row, if any
+    pub fn columns(&self) -> Option<&[ColumnDef]> {
+        self.columns.as_deref().map(Vec::as_slice)
+    }
+
+    pub fn set_columns(&mut self, columns: Vec<ColumnDef>) {
+        self.columns = Some(Arc::new(columns));
+    }
+
+    /// Queue a row, sending a batch once full
+    ///
+    /// Returns false when the consumer has gone away and reading should stop.
+    pub async fn push(&mut self, row: Vec<Value>) -> bool {
+        self.rows.push(row);
+        if self.rows.len() >= self.batch_size {
+            return self.flush().await;
+        }
+        true
+    }
+
+    async fn flush(&mut self) -> bool {
+        if self.rows.is_empty() {
+            return true;
+        }
+        let batch = RowBatch {
+            columns: self.columns.clone().unwrap_or_default(),
+            rows: std::mem::replace(&mut self.rows, Vec::with_capacity(self.batch_size)),
+        };
+        self.tx.send(Ok(batch)).await.is_ok()
+    }
+
+    /// Signal that fires when the consumer goes away or the timeout passes
+    pub fn stop_signal(&self) -> StopSignal {
+        StopSignal {
+            tx: self.tx.clone(),
+            deadline: self.deadline,
+            timeout: self.timeout,
+        }
+    }
+}
+
+/// Tells a producer to stop reading and cancel its query
+pub(super) struct StopSignal {
+    tx: mpsc::Sender<Result<RowBatch>>,
+    deadline: Instant,
+    timeout: Duration,
+}
+
+impl StopSignal {
+    /// Wait until the stream is dropped or times out, returning the error the
+    /// producer should finish with
+    pub async fn wait(&self) -> Error {
+        tokio::select! {
+            _ = self.tx.closed() => Error::QueryExecution("Query cancelled".to_string(), None),
+            _ = tokio::time::sleep_until(self.deadline) => {
+                Error::Timeout(format!("Query timed out after {:?}", self.timeout))
+            }
+        }
+    }
+}
+
+/// Run `producer` on a background task and stream the batches it sends
+///
+/// The producer gets the whole `timeout`, plus a short grace period to
+/// cancel its query if it watches the stop signal; it returns its sender so
+/// any partial batch can be flushed. Errors and timeouts are delivered as the
+/// final stream item.
+pub(super) fn spawn_row_stream<F, Fut>(timeout: Duration, batch_size: usize, producer: F) -> RowStream
+where
+    F: FnOnce(BatchSender) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<BatchSender>> + Send + 'static,
+{
+    let batch_size = batch_size.max(1);
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    let sender = BatchSender {
+        tx: tx.clone(),
+        columns: None,
+        rows: Vec::with_capacity(batch_size),
+        batch_size,
+        deadline: Instant::now() + timeout,
+        timeout,
+    };
+
+    tokio::spawn(async move {
+        let error = match tokio::time::timeout(timeout + STOP_GRACE_PERIOD, producer(sender)).await {
+            Ok(Ok(mut sender)) => {
+                sender.flush().await;
+                return;
+            }
+            Ok(Err(e)) => e,
+            Err(_) => Error::Timeout(format!("Query timed out after {:?}", timeout)),
+        };
+        let _ = tx.send(Err(error)).await;
+    });
+
+    stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|item| (item, rx)) }).boxed()
+}
+
+/// Rows read from a `RowStream`, serialized as they arrived
+pub struct CollectedRows {
+    pub columns: Vec<ColumnDef>,
+    /// JSON array of row arrays, exactly as it will be stored
+    pub rows: Box<RawValue>,
+    pub row_count: usize,
+    /// Set when rows were left unread because a limit was reached
+    pub truncated: Option<TruncationReason>,
+}
+
+impl CollectedRows {
+    /// Size of the serialized rows in bytes
+    pub fn byte_count(&self) -> usize {
+        self.rows.get().len()
+    }
+}
+
+/// Read a stream into a serialized JSON array, stopping once `max_rows` rows
+/// have been read or the next row would push the array past `max_bytes`
+///
+/// Only the serialized output and the batch being read are held in memory;
+/// the stream is dropped (cancelling its producer) as soon as a limit is hit.
+/// `max_bytes` bounds the output, not the batches: each batch arrives whole,
+/// so memory can briefly exceed it by a few batches (see `CHANNEL_CAPACITY`),
+/// or by the whole result for connectors that can't stream.
+/// The result counts as truncated by `max_rows` only if the stream had
+/// another row, so ask the connector for one row more than `max_rows`.
+pub async fn collect_rows(
+    mut stream: RowStream,
+    max_rows: usize,
+    max_bytes: usize,
+) -> Result<CollectedRows> {
+    let mut columns: Option<Arc<Vec<ColumnDef>>> = None;
+    let mut buf = vec![b'['];
+    let mut row_count = 0;
+    let mut truncated = None;
+
+    'batches: while let Some(batch) = stream.try_next().await? {
+        if columns.is_none() {
+            columns = Some(batch.columns.clone());
+        }
+
+        for row in &batch.rows {
+            if row_count >= max_rows {
+                truncated = Some(TruncationReason::MaxRows);
+                break 'batches;
+            }
+
+            let mark = buf.len();
+            if row_count > 0 {
+                buf.push(b',');
+            }
+            serde_json::to_writer(&mut buf, row)
+                .map_err(|e| Error::Internal(format!("Failed to serialize row: {}", e)))?;
+
+            // Leave room for the closing bracket
+            if buf.len() + 1 > max_bytes {
+                buf.truncate(mark);
+                truncated = Some(TruncationReason::MaxBytes);
+                break 'batches;
+            }
+            row_count += 1;
+        }
+    }
+    drop(stream);
+    buf.push(b']');
+
+    let json = String::from_utf8(buf)
+        .map_err(|e| Error::Internal(format!("Serialized rows are not UTF-8: {}", e)))?;
+    let rows = RawValue::from_string(json)
+        .map_err(|e| Error::Internal(format!("Serialized rows are not valid JSON: {}", e)))?;
+
+    Ok(CollectedRows {
+        columns: columns.map(|c| c.to_vec()).unwrap_or_default(),
+        rows,
+        row_count,
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn output(n: i64) -> QueryOutput {
+        QueryOutput {
+            columns: vec![ColumnDef {
+                name: "n".to_string(),
+                data_type: "int8".to_string(),
+            }],
+            rows: (0..n).map(|i| vec![json!(i)]).collect(),
+            row_count: n as usize,
+            execution_time: Duration::ZERO,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_output_to_stream_batches() {
+        let batches: Vec<RowBatch> = output_to_stream(output(5), 2).try_collect().await.unwrap();
+        let sizes: Vec<usize> = batches.iter().map(|b| b.rows.len()).collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_collect_rows() {
+        let collected = collect_rows(output_to_stream(output(3), 2), 100, 1024).await.unwrap();
+
+        assert_eq!(collected.rows.get(), "[[0],[1],[2]]");
+        assert_eq!(collected.row_count, 3);
+        assert_eq!(collected.byte_count(), 13);
+        assert_eq!(collected.columns[0].name, "n");
+        assert_eq!(collected.truncated, None);
+    }
+
+    #[tokio::test]
+    async fn test_collect_rows_stops_at_byte_limit() {
+        // "[[0],[1]" is 8 bytes; the closing bracket makes 9
+        let collected = collect_rows(output_to_stream(output(10), 3), 100, 9).await.unwrap();
+
+        assert_eq!(collected.rows.get(), "[[0],[1]]");
+        assert_eq!(collected.row_count, 2);
+        assert_eq!(collected.truncated, Some(TruncationReason::MaxBytes));
+    }
+
+    #[tokio::test]
+    async fn test_collect_rows_stops_at_row_limit() {
+        let collected = collect_rows(output_to_stream(output(10), 4), 5, 1024).await.unwrap();
+
+        assert_eq!(collected.row_count, 5);
+        assert_eq!(collected.truncated, Some(TruncationReason::MaxRows));
+    }
+
+    #[tokio::test]
+    async fn test_collect_rows_exactly_at_row_limit() {
+        // No row beyond the limit, so nothing was left out
+        let collected = collect_rows(output_to_stream(output(5), 4), 5, 1024).await.unwrap();
+
+        assert_eq!(collected.row_count, 5);
+        assert_eq!(collected.truncated, None);
+    }
+
+    #[tokio::test]
+    async fn test_collect_empty_stream() {
+        let collected = collect_rows(output_to_stream(output(0), 4), 5, 1024).await.unwrap();
+
+        assert_eq!(collected.rows.get(), "[]");
+        assert!(collected.columns.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_spawned_stream_reports_timeout() {
+        let stream = spawn_row_stream(Duration::from_millis(20), 10, |mut sender| async move {
+            sender.set_columns(vec![]);
+            sender.push(vec![json!(1)]).await;
+            Err(sender.stop_signal().wait().await)
+        });
+
+        let err = collect_rows(stream, 100, 1024).await.err().unwrap();
+        assert!(matches!(err, Error::Timeout(_)));
+    }
+
+    #[tokio::test]
+    async fn test_stop_signal_fires_when_stream_dropped() {
+        let (stopped_tx, stopped_rx) = tokio::sync::oneshot::channel();
+        let stream = spawn_row_stream(Duration::from_secs(30), 1, |mut sender| async move {
+            sender.set_columns(vec![]);
+            let stop = sender.stop_signal();
+            sender.push(vec![json!(1)]).await;
+            sender.push(vec![json!(2)]).await;
+            let _ = stopped_tx.send(stop.wait().await);
+            Ok(sender)
+        });
+
+        // Stops reading at the row past the limit, dropping the stream
+        let collected = collect_rows(stream, 1, 1024).await.unwrap();
+        assert_eq!(collected.row_count, 1);
+        assert_eq!(collected.truncated, Some(TruncationReason::MaxRows));
+
+        let err = tokio::time::timeout(Duration::from_secs(1), stopped_rx)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(err, Error::QueryExecution(..)));
+    }
+}
@@ -8,6 +8,39 @@ use std::str::FromStr;
 use std::time::Duration;
 use uuid::Uuid;
 
+/// How long a claimed run stays leased to its runner without a heartbeat
+pub const RUN_LEASE: Duration = Duration::from_secs(60);
+
+/// Datasources found full before a claim gives up until the next poll
+const MAX_CLAIM_ATTEMPTS: usize = 5;
+
+/// How long a run waits before it's claimed as if one lane higher; keeps
+/// scheduled and backfill runs moving while interactive work keeps arriving
+const PRIORITY_AGING: Duration = Duration::from_secs(120);
+
+/// NOTIFY channel carrying the id of each newly queued run
+pub const RUNS_CHANNEL: &str = "loupe_runs";
+
+/// NOTIFY channel carrying the id of each cancelled run
+pub const RUN_CANCELLED_CHANNEL: &str = "loupe_run_cancelled";
+
+/// SQL for a retention policy setting in effect for query `q` of org `o`:
+/// the query's policy, else the org's, else `default`
+fn retention_sql(field: &str, cast: &str, default: &str) -> String {
+    format!(
+        "COALESCE((COALESCE(q.retention_policy, o.retention_policy)->>'{}')::{}, {})",
+        field, cast, default
+    )
+}
+
+/// SQL for when a result stored now for run `$2` expires
+fn result_expires_at_sql() -> String {
+    format!(
+        "NOW() + make_interval(days => (SELECT {} FROM runs r JOIN queries q ON q.id = r.query_id JOIN organizations o ON o.id = r.org_id WHERE r.id = $2))",
+        retention_sql("keep_days", "int", &DEFAULT_RETENTION_DAYS.to_string())
+    )
+}
+
 #[derive(Clone)]
 pub struct Database {
     pub pool: PgPool,
@@ -24,6 +57,21 @@ pub struct PoolStats {
     pub connections_max: u32,
 }
 
+/// Outcome of trying to take a shared query slot
+#[derive(Debug, Clone, Copy)]
+pub struct QuerySlotAttempt {
+    /// The slot taken, or None if a limit was reached
+    pub slot_id: Option<Uuid>,
+    /// Slots held across all orgs before this attempt
+    pub global_count: i64,
+    /// Slots held by the org before this attempt
+    pub org_count: i64,
+}
+
+/// Advisory lock serializing slot acquisition, so two runners can't both
+/// take the last slot
+const QUERY_SLOTS_LOCK_KEY: i64 = 0x6c6f_7570_655f_736c;
+
 /// Database connection configuration
 pub struct DatabaseConfig {
     /// Minimum number of connections in the pool
@@ -287,6 +335,50 @@ impl Database {
         Ok(org)
     }
 
+    /// Set or clear (`None`) the org's ceiling on result size
+    pub async fn set_organization_max_result_bytes(
+        &self,
+        id: Uuid,
+        max_result_bytes: Option<i64>,
+    ) -> Result<Organization> {
+        let org = sqlx::query_as::<_, Organization>(
+            r#"
+            UPDATE organizations
+            SET max_result_bytes = $2, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(max_result_bytes)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(org)
+    }
+
+    /// Set or clear (`None`, back to the default) an org's retention policy
+    pub async fn set_organization_retention_policy(
+        &self,
+        id: Uuid,
+        retention_policy: Option<&RetentionPolicy>,
+    ) -> Result<Organization> {
+        let org = sqlx::query_as::<_, Organization>(
+            r#"
+            UPDATE organizations
+            SET retention_policy = $2, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(retention_policy.map(sqlx::types::Json))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(org)
+    }
+
     // ==================== Users ====================
 
     pub async fn create_user(
@@ -438,11 +530,12 @@ impl Database {
         ds_type: DatasourceType,
         connection_string_encrypted: &str,
         created_by: Uuid,
+        max_concurrent_runs: Option<i32>,
     ) -> Result<Datasource> {
         let ds = sqlx::query_as::<_, Datasource>(
             r#"
-            INSERT INTO datasources (id, org_id, name, ds_type, connection_string_encrypted, created_by, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, NOW(), NOW())
+            INSERT INTO datasources (id, org_id, name, ds_type, connection_string_encrypted, created_by, max_concurrent_runs, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW(), NOW())
             RETURNING *
             "#,
         )
@@ -452,6 +545,7 @@ impl Database {
         .bind(ds_type)
         .bind(connection_string_encrypted)
         .bind(created_by)
+        .bind(max_concurrent_runs)
         .fetch_one(&self.pool)
         .await?;
 
@@ -533,18 +627,23 @@ impl Database {
         Ok((datasources, total.0))
     }
 
+    /// Update a datasource; `max_concurrent_runs` is left unchanged when None
+    /// and cleared when `Some(None)`
     pub async fn update_datasource(
         &self,
         id: Uuid,
         org_id: Uuid,
         name: Option<&str>,
         connection_string_encrypted: Option<&str>,
+        max_concurrent_runs: Option<Option<i32>>,
     ) -> Result<Datasource> {
         let ds = sqlx::query_as::<_, Datasource>(
             r#"
             UPDATE datasources 
             SET name = COALESCE($3, name),
                 connection_string_encrypted = COALESCE($4, connection_string_encrypted),
+                config_version = config_version + CASE WHEN $4 IS NULL THEN 0 ELSE 1 END,
+                max_concurrent_runs = CASE WHEN $5 THEN $6 ELSE max_concurrent_runs END,
                 updated_at = NOW()
             WHERE id = $1 AND org_id = $2
             RETURNING *
@@ -554,20 +653,38 @@ impl Database {
         .bind(org_id)
         .bind(name)
         .bind(connection_string_encrypted)
+        .bind(max_concurrent_runs.is_some())
+        .bind(max_concurrent_runs.flatten())
         .fetch_one(&self.pool)
         .await?;
 
         Ok(ds)
     }
 
-    pub async fn delete_datasource(&self, id: Uuid, org_id: Uuid) -> Result<()> {
-        sqlx::query("DELETE FROM datasources WHERE id = $1 AND org_id = $2")
-            .bind(id)
-            .bind(org_id)
-            .execute(&self.pool)
-            .await?;
+    /// Delete a datasource with its queries and runs, returning the
+    /// `storage_key` of every offloaded result that went with them
+    pub async fn delete_datasource(&self, id: Uuid, org_id: Uuid) -> Result<Vec<String>> {
+        let keys: Vec<String> = sqlx::query_scalar(
+            r#"
+            WITH keys AS (
+                SELECT rr.storage_key FROM run_results rr
+                JOIN runs r ON r.id = rr.run_id
+                JOIN queries q ON q.id = r.query_id
+                WHERE (r.datasource_id = $1 OR q.datasource_id = $1)
+                  AND rr.storage_key IS NOT NULL
+            ),
+            deleted AS (
+                DELETE FROM datasources WHERE id = $1 AND org_id = $2 RETURNING id
+            )
+            SELECT storage_key FROM keys WHERE EXISTS (SELECT 1 FROM deleted)
+            "#,
+        )
+        .bind(id)
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await?;
 
-        Ok(())
+        Ok(keys)
     }
 
     // ==================== Queries ====================
@@ -583,12 +700,14 @@ impl Database {
         tags: &serde_json::Value,
         timeout_seconds: i32,
         max_rows: i32,
+        max_bytes: Option<i64>,
         created_by: Uuid,
+        retry_policy: Option<&RetryPolicy>,
     ) -> Result<Query> {
         let query = sqlx::query_as::<_, Query>(
             r#"
-            INSERT INTO queries (id, org_id, datasource_id, name, description, sql, parameters, tags, timeout_seconds, max_rows, created_by, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NOW(), NOW())
+            INSERT INTO queries (id, org_id, datasource_id, name, description, sql, parameters, tags, timeout_seconds, max_rows, max_bytes, created_by, retry_policy, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, NOW(), NOW())
             RETURNING *
             "#,
         )
@@ -602,7 +721,9 @@ impl Database {
         .bind(tags)
         .bind(timeout_seconds)
         .bind(max_rows)
+        .bind(max_bytes)
         .bind(created_by)
+        .bind(retry_policy.map(sqlx::types::Json))
         .fetch_one(&self.pool)
         .await?;
 
@@ -858,6 +979,9 @@ impl Database {
         Ok((queries, total.0))
     }
 
+    /// Update a query; `max_bytes` and `retry_policy` are left unchanged when
+    /// None and cleared (back to the org ceiling and the default policy) when
+    /// `Some(None)`
     pub async fn update_query(
         &self,
         id: Uuid,
@@ -869,6 +993,8 @@ impl Database {
         tags: Option<&serde_json::Value>,
         timeout_seconds: Option<i32>,
         max_rows: Option<i32>,
+        max_bytes: Option<Option<i64>>,
+        retry_policy: Option<Option<&RetryPolicy>>,
     ) -> Result<Query> {
         let query = sqlx::query_as::<_, Query>(
             r#"
@@ -880,6 +1006,8 @@ impl Database {
                 tags = COALESCE($7, tags),
                 timeout_seconds = COALESCE($8, timeout_seconds),
                 max_rows = COALESCE($9, max_rows),
+                max_bytes = CASE WHEN $10 THEN $11 ELSE max_bytes END,
+                retry_policy = CASE WHEN $12 THEN $13 ELSE retry_policy END,
                 updated_at = NOW()
             WHERE id = $1 AND org_id = $2
             RETURNING *
@@ -894,20 +1022,63 @@ impl Database {
         .bind(tags)
         .bind(timeout_seconds)
         .bind(max_rows)
+        .bind(max_bytes.is_some())
+        .bind(max_bytes.flatten())
+        .bind(retry_policy.is_some())
+        .bind(retry_policy.flatten().map(sqlx::types::Json))
         .fetch_one(&self.pool)
         .await?;
 
         Ok(query)
     }
 
-    pub async fn delete_query(&self, id: Uuid, org_id: Uuid) -> Result<()> {
-        sqlx::query("DELETE FROM queries WHERE id = $1 AND org_id = $2")
-            .bind(id)
-            .bind(org_id)
-            .execute(&self.pool)
-            .await?;
+    /// Set or clear (`None`, back to the org's policy) a query's retention
+    /// policy
+    pub async fn set_query_retention_policy(
+        &self,
+        id: Uuid,
+        org_id: Uuid,
+        retention_policy: Option<&RetentionPolicy>,
+    ) -> Result<Query> {
+        let query = sqlx::query_as::<_, Query>(
+            r#"
+            UPDATE queries
+            SET retention_policy = $3, updated_at = NOW()
+            WHERE id = $1 AND org_id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(org_id)
+        .bind(retention_policy.map(sqlx::types::Json))
+        .fetch_one(&self.pool)
+        .await?;
 
-        Ok(())
+        Ok(query)
+    }
+
+    /// Delete a query with its runs, returning the `storage_key` of every
+    /// offloaded result that went with them
+    pub async fn delete_query(&self, id: Uuid, org_id: Uuid) -> Result<Vec<String>> {
+        let keys: Vec<String> = sqlx::query_scalar(
+            r#"
+            WITH keys AS (
+                SELECT rr.storage_key FROM run_results rr
+                JOIN runs r ON r.id = rr.run_id
+                WHERE r.query_id = $1 AND rr.storage_key IS NOT NULL
+            ),
+            deleted AS (
+                DELETE FROM queries WHERE id = $1 AND org_id = $2 RETURNING id
+            )
+            SELECT storage_key FROM keys WHERE EXISTS (SELECT 1 FROM deleted)
+            "#,
+        )
+        .bind(id)
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(keys)
     }
 
     // ==================== Runs ====================
@@ -921,12 +1092,15 @@ impl Database {
         parameters: &serde_json::Value,
         timeout_seconds: i32,
         max_rows: i32,
+        max_bytes: Option<i64>,
         created_by: Uuid,
+        priority: RunPriority,
+        retry_policy: &RetryPolicy,
     ) -> Result<Run> {
         let run = sqlx::query_as::<_, Run>(
             r#"
-            INSERT INTO runs (id, org_id, query_id, datasource_id, executed_sql, parameters, status, timeout_seconds, max_rows, created_by, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, 'queued', $7, $8, $9, NOW())
+            INSERT INTO runs (id, org_id, query_id, datasource_id, executed_sql, parameters, status, timeout_seconds, max_rows, created_by, priority, retry_policy, max_retries, max_bytes, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, 'queued', $7, $8, $9, $10, $11, $12, $13, NOW())
             RETURNING *
             "#,
         )
@@ -939,9 +1113,24 @@ impl Database {
         .bind(timeout_seconds)
         .bind(max_rows)
         .bind(created_by)
+        .bind(priority)
+        .bind(sqlx::types::Json(retry_policy))
+        .bind(retry_policy.max_retries)
+        .bind(max_bytes)
         .fetch_one(&self.pool)
         .await?;
 
+        // Wake idle runners; if this fails they still find the run on their
+        // next poll
+        if let Err(e) = sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(RUNS_CHANNEL)
+            .bind(run.id.to_string())
+            .execute(&self.pool)
+            .await
+        {
+            tracing::warn!(run_id = %run.id, "Failed to notify runners of queued run: {}", e);
+        }
+
         Ok(run)
     }
 
@@ -1183,21 +1372,137 @@ impl Database {
     }
 
     /// Claim a queued run for execution (called by runner)
+    ///
+    /// Higher priority lanes go first, orgs are served round-robin and
+    /// datasource concurrency limits are respected (see `claim_next`). The run is leased to the runner for
+    /// `RUN_LEASE`; heartbeats renew it.
     pub async fn claim_run(&self, runner_id: &str) -> Result<Option<Run>> {
-        let run = sqlx::query_as::<_, Run>(
+        self.claim_next(
+            runner_id,
+            "r.status = 'queued' AND (r.throttled_until IS NULL OR r.throttled_until <= NOW())",
+            "r.created_at",
+        )
+        .await
+    }
+
+    /// Claim the next ready run, skipping datasources at their concurrency limit
+    ///
+    /// Candidates come from the highest priority lane, then the org whose
+    /// last claim is oldest, then the oldest by `age_column`. A run moves up
+    /// one lane for every `PRIORITY_AGING` it has waited, so lower lanes are
+    /// never starved. For a datasource with `max_concurrent_runs`,
+    /// its row is locked while running runs are recounted, so runners
+    /// claiming concurrently can't both take the last slot. A datasource that
+    /// turns out to be full is excluded and the next candidate tried.
+    async fn claim_next(&self, runner_id: &str, ready: &str, age_column: &str) -> Result<Option<Run>> {
+        let candidate_sql = format!(
             r#"
-            UPDATE runs 
-            SET status = 'running', runner_id = $1, started_at = NOW()
-            WHERE id = (
-                SELECT id FROM runs 
-                WHERE status = 'queued' 
-                ORDER BY created_at ASC 
-                LIMIT 1
-                FOR UPDATE SKIP LOCKED
+            SELECT r.id, r.datasource_id, d.max_concurrent_runs
+            FROM runs r
+            JOIN datasources d ON d.id = r.datasource_id
+            JOIN organizations o ON o.id = r.org_id
+            WHERE {ready}
+              AND r.datasource_id <> ALL($1)
+              AND (d.max_concurrent_runs IS NULL OR d.max_concurrent_runs > (
+                  SELECT COUNT(*) FROM runs x
+                  WHERE x.datasource_id = r.datasource_id AND x.status = 'running'
+              ))
+            ORDER BY GREATEST(
+                         CASE r.priority
+                             WHEN 'interactive' THEN 0
+                             WHEN 'dashboard' THEN 1
+                             WHEN 'scheduled' THEN 2
+                             ELSE 3
+                         END
+                         - FLOOR(EXTRACT(EPOCH FROM NOW() - {age_column}) / $2),
+                         0
+                     ) ASC,
+                     o.last_run_claimed_at ASC NULLS FIRST,
+                     {age_column} ASC
+            LIMIT 1
+            FOR UPDATE OF r SKIP LOCKED
+            "#
+        );
+
+        let mut saturated: Vec<Uuid> = Vec::new();
+        for _ in 0..MAX_CLAIM_ATTEMPTS {
+            let mut tx = self.pool.begin().await?;
+
+            let candidate: Option<(Uuid, Uuid, Option<i32>)> = sqlx::query_as(&candidate_sql)
+                .bind(&saturated)
+                .bind(PRIORITY_AGING.as_secs_f64())
+                .fetch_optional(&mut *tx)
+                .await?;
+            let Some((run_id, datasource_id, max_concurrent_runs)) = candidate else {
+                return Ok(None);
+            };
+
+            if let Some(limit) = max_concurrent_runs {
+                sqlx::query("SELECT 1 FROM datasources WHERE id = $1 FOR NO KEY UPDATE")
+                    .bind(datasource_id)
+                    .execute(&mut *tx)
+                    .await?;
+                let running: (i64,) = sqlx::query_as(
+                    "SELECT COUNT(*) FROM runs WHERE datasource_id = $1 AND status = 'running'",
+                )
+                .bind(datasource_id)
+                .fetch_one(&mut *tx)
+                .await?;
+
+                if running.0 >= i64::from(limit) {
+                    tx.rollback().await?;
+                    saturated.push(datasource_id);
+                    continue;
+                }
+            }
+
+            let run = sqlx::query_as::<_, Run>(
+                r#"
+                UPDATE runs
+                SET status = 'running',
+                    runner_id = $2,
+                    started_at = NOW(),
+                    lease_expires_at = NOW() + make_interval(secs => $3),
+                    next_retry_at = NULL,
+                    throttled_until = NULL,
+                    error_message = NULL
+                WHERE id = $1
+                RETURNING *
+                "#,
             )
+            .bind(run_id)
+            .bind(runner_id)
+            .bind(RUN_LEASE.as_secs_f64())
+            .fetch_one(&mut *tx)
+            .await?;
+
+            sqlx::query("UPDATE organizations SET last_run_claimed_at = NOW() WHERE id = $1")
+                .bind(run.org_id)
+                .execute(&mut *tx)
+                .await?;
+
+            tx.commit().await?;
+            return Ok(Some(run));
+        }
+
+        Ok(None)
+    }
+
+    /// Complete a run with success
+    ///
+    /// Only the runner holding the run's lease can finish it. Returns None,
+    /// leaving the run as it is, if it is no longer running on `runner_id`:
+    /// it was cancelled, or its lease lapsed and it was reclaimed.
+    pub async fn complete_run(&self, id: Uuid, runner_id: &str, _result_id: Uuid) -> Result<Option<Run>> {
+        let run = sqlx::query_as::<_, Run>(
+            r#"
+            UPDATE runs
+            SET status = 'completed', completed_at = NOW()
+            WHERE id = $1 AND runner_id = $2 AND status = 'running'
             RETURNING *
             "#,
         )
+        .bind(id)
         .bind(runner_id)
         .fetch_optional(&self.pool)
         .await?;
@@ -1205,136 +1510,384 @@ impl Database {
         Ok(run)
     }
 
-    /// Complete a run with success
-    pub async fn complete_run(&self, id: Uuid, _result_id: Uuid) -> Result<Run> {
+    /// Fail a run with an error message
+    ///
+    /// Returns None if the run is no longer running on `runner_id` (see
+    /// `complete_run`).
+    pub async fn fail_run(&self, id: Uuid, runner_id: &str, error_message: &str) -> Result<Option<Run>> {
         let run = sqlx::query_as::<_, Run>(
             r#"
-            UPDATE runs 
-            SET status = 'completed', completed_at = NOW()
-            WHERE id = $1
+            UPDATE runs
+            SET status = 'failed', completed_at = NOW(), error_message = $3
+            WHERE id = $1 AND runner_id = $2 AND status = 'running'
             RETURNING *
             "#,
         )
         .bind(id)
-        .fetch_one(&self.pool)
+        .bind(runner_id)
+        .bind(error_message)
+        .fetch_optional(&self.pool)
         .await?;
 
         Ok(run)
     }
 
-    /// Fail a run with an error message
-    pub async fn fail_run(&self, id: Uuid, error_message: &str) -> Result<Run> {
+    /// Timeout a run
+    ///
+    /// Returns None if the run is no longer running on `runner_id` (see
+    /// `complete_run`).
+    pub async fn timeout_run(&self, id: Uuid, runner_id: &str) -> Result<Option<Run>> {
         let run = sqlx::query_as::<_, Run>(
             r#"
-            UPDATE runs 
-            SET status = 'failed', completed_at = NOW(), error_message = $2
-            WHERE id = $1
+            UPDATE runs
+            SET status = 'timeout', completed_at = NOW(), error_message = 'Query execution timed out'
+            WHERE id = $1 AND runner_id = $2 AND status = 'running'
             RETURNING *
             "#,
         )
         .bind(id)
-        .bind(error_message)
-        .fetch_one(&self.pool)
+        .bind(runner_id)
+        .fetch_optional(&self.pool)
         .await?;
 
         Ok(run)
     }
 
-    /// Timeout a run
-    pub async fn timeout_run(&self, id: Uuid) -> Result<Run> {
+    /// Put a run the query limiter turned away back in the queue for `backoff`
+    ///
+    /// Returns None, leaving the run as it is, once it has been throttled for
+    /// longer than `max_wait` (or if it is no longer running on `runner_id`);
+    /// the caller then fails it. The wait starts over once the run is retried
+    /// or reclaimed, having got a slot in between.
+    pub async fn requeue_throttled_run(
+        &self,
+        id: Uuid,
+        runner_id: &str,
+        backoff: Duration,
+        max_wait: Duration,
+    ) -> Result<Option<Run>> {
         let run = sqlx::query_as::<_, Run>(
             r#"
             UPDATE runs
-            SET status = 'timeout', completed_at = NOW(), error_message = 'Query execution timed out'
+            SET status = 'queued',
+                runner_id = NULL,
+                started_at = NULL,
+                lease_expires_at = NULL,
+                throttled_until = NOW() + make_interval(secs => $2),
+                first_throttled_at = COALESCE(first_throttled_at, NOW())
             WHERE id = $1
+              AND runner_id = $4
+              AND status = 'running'
+              AND COALESCE(first_throttled_at, NOW()) > NOW() - make_interval(secs => $3)
             RETURNING *
             "#,
         )
         .bind(id)
-        .fetch_one(&self.pool)
+        .bind(backoff.as_secs_f64())
+        .bind(max_wait.as_secs_f64())
+        .bind(runner_id)
+        .fetch_optional(&self.pool)
         .await?;
 
         Ok(run)
     }
 
-    pub async fn cancel_run(&self, id: Uuid) -> Result<Run> {
+    /// Cancel a queued or running run and notify runners
+    ///
+    /// Returns None if the run had already finished. Runners listen on
+    /// `RUN_CANCELLED_CHANNEL` to abort the query on the datasource.
+    pub async fn cancel_run(&self, id: Uuid) -> Result<Option<Run>> {
         let run = sqlx::query_as::<_, Run>(
             r#"
             UPDATE runs
             SET status = 'cancelled', completed_at = NOW(), error_message = 'Query execution cancelled by user'
-            WHERE id = $1
+            WHERE id = $1 AND status IN ('queued', 'running')
             RETURNING *
             "#,
         )
         .bind(id)
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
         .await?;
 
+        if run.is_some() {
+            sqlx::query("SELECT pg_notify($1, $2)")
+                .bind(RUN_CANCELLED_CHANNEL)
+                .bind(id.to_string())
+                .execute(&self.pool)
+                .await?;
+        }
+
         Ok(run)
     }
 
-    /// Schedule a run for retry with exponential backoff
+    /// Of the given runs, return those that have been cancelled
+    ///
+    /// Runners poll this as a fallback for missed cancel notifications.
+    pub async fn cancelled_run_ids(&self, ids: &[Uuid]) -> Result<Vec<Uuid>> {
+        let rows: Vec<(Uuid,)> =
+            sqlx::query_as("SELECT id FROM runs WHERE id = ANY($1) AND status = 'cancelled'")
+                .bind(ids)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Schedule a run for retry after `backoff`, less up to `jitter` of it
+    /// at random
     ///
-    /// Uses exponential backoff: base_delay * 2^retry_count
-    /// Example: 30s, 60s, 120s, 240s, ...
-    pub async fn schedule_retry(&self, id: Uuid, error_message: &str) -> Result<Option<Run>> {
+    /// Returns None, leaving the run as it is, once it is out of retries (the
+    /// caller then fails it) or if it is no longer running on `runner_id`.
+    pub async fn schedule_retry(
+        &self,
+        id: Uuid,
+        runner_id: &str,
+        error_message: &str,
+        backoff: Duration,
+        jitter: f64,
+    ) -> Result<Option<Run>> {
         let run = sqlx::query_as::<_, Run>(
             r#"
             UPDATE runs
             SET status = 'failed',
-                error_message = $2,
+                error_message = $3,
                 retry_count = retry_count + 1,
-                next_retry_at = CASE
-                    WHEN retry_count < max_retries THEN
-                        NOW() + (INTERVAL '30 seconds' * POWER(2, retry_count))
-                    ELSE
-                        NULL
-                END
+                next_retry_at = NOW() + make_interval(secs => $4 * (1 - $5 * random())),
+                lease_expires_at = NULL,
+                throttled_until = NULL,
+                first_throttled_at = NULL
             WHERE id = $1
+              AND runner_id = $2
+              AND status = 'running'
+              AND retry_count < max_retries
             RETURNING *
             "#,
         )
         .bind(id)
+        .bind(runner_id)
         .bind(error_message)
-        .fetch_one(&self.pool)
+        .bind(backoff.as_secs_f64())
+        .bind(jitter)
+        .fetch_optional(&self.pool)
         .await?;
 
-        // Return Some if retry is scheduled, None if max retries exceeded
-        if run.next_retry_at.is_some() {
-            Ok(Some(run))
-        } else {
-            Ok(None)
-        }
+        Ok(run)
     }
 
     /// Claim a run that's ready for retry
     ///
-    /// Finds runs where next_retry_at is in the past and status is 'failed'
+    /// Finds runs where next_retry_at is in the past and status is 'failed',
+    /// with the same fairness and limits as `claim_run`
     pub async fn claim_retry_run(&self, runner_id: &str) -> Result<Option<Run>> {
-        let run = sqlx::query_as::<_, Run>(
+        self.claim_next(
+            runner_id,
+            "r.status = 'failed' AND r.next_retry_at IS NOT NULL AND r.next_retry_at <= NOW()",
+            "r.next_retry_at",
+        )
+        .await
+    }
+
+    /// Record a runner heartbeat and renew the leases on the runs it is
+    /// executing and its query slots
+    ///
+    /// Only `run_ids` are renewed, so a run the runner has stopped working
+    /// on without finishing lapses and is reclaimed. Registers the runner on
+    /// its first heartbeat. Returns the number of leases renewed.
+    pub async fn runner_heartbeat(
+        &self,
+        runner_id: &str,
+        hostname: Option<&str>,
+        run_ids: &[Uuid],
+    ) -> Result<u64> {
+        sqlx::query(
+            r#"
+            INSERT INTO runners (id, hostname, started_at, last_heartbeat_at)
+            VALUES ($1, $2, NOW(), NOW())
+            ON CONFLICT (id) DO UPDATE SET last_heartbeat_at = NOW()
+            "#,
+        )
+        .bind(runner_id)
+        .bind(hostname)
+        .execute(&self.pool)
+        .await?;
+
+        let renewed = sqlx::query(
             r#"
             UPDATE runs
-            SET status = 'queued',
-                runner_id = $1,
-                next_retry_at = NULL,
-                error_message = NULL
-            WHERE id = (
+            SET lease_expires_at = NOW() + make_interval(secs => $2)
+            WHERE id = ANY($3) AND runner_id = $1 AND status = 'running'
+            "#,
+        )
+        .bind(runner_id)
+        .bind(RUN_LEASE.as_secs_f64())
+        .bind(run_ids)
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "UPDATE query_slots SET expires_at = NOW() + make_interval(secs => $2) WHERE holder = $1",
+        )
+        .bind(runner_id)
+        .bind(RUN_LEASE.as_secs_f64())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(renewed.rows_affected())
+    }
+
+    /// Remove a runner that is shutting down
+    pub async fn deregister_runner(&self, runner_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM runners WHERE id = $1")
+            .bind(runner_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Count runners that have heartbeated within one lease period
+    pub async fn count_active_runners(&self) -> Result<i64> {
+        let count: (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM runners WHERE last_heartbeat_at > NOW() - make_interval(secs => $1)",
+        )
+        .bind(RUN_LEASE.as_secs_f64())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count.0)
+    }
+
+    /// Reclaim running runs whose runner stopped renewing its lease
+    ///
+    /// Runs with retries left go back to the queue (counting as a retry);
+    /// the rest are failed. Runners that haven't heartbeated for a day are
+    /// dropped from the `runners` table. Returns the reclaimed runs with
+    /// their new status.
+    pub async fn reclaim_expired_runs(&self) -> Result<Vec<Run>> {
+        let runs = sqlx::query_as::<_, Run>(
+            r#"
+            UPDATE runs
+            SET status = CASE WHEN retry_count < max_retries THEN 'queued' ELSE 'failed' END,
+                retry_count = retry_count + 1,
+                completed_at = CASE WHEN retry_count < max_retries THEN NULL ELSE NOW() END,
+                error_message = CASE
+                    WHEN retry_count < max_retries THEN NULL
+                    ELSE 'Runner ' || COALESCE(runner_id, 'unknown') || ' stopped responding'
+                END,
+                runner_id = NULL,
+                started_at = NULL,
+                lease_expires_at = NULL,
+                throttled_until = NULL,
+                first_throttled_at = NULL
+            WHERE id IN (
                 SELECT id FROM runs
-                WHERE status = 'failed'
-                  AND next_retry_at IS NOT NULL
-                  AND next_retry_at <= NOW()
-                ORDER BY next_retry_at ASC
+                WHERE status = 'running'
+                  AND lease_expires_at < NOW()
                 FOR UPDATE SKIP LOCKED
-                LIMIT 1
             )
             RETURNING *
             "#,
         )
-        .bind(runner_id)
-        .fetch_optional(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(run)
+        // Wake runners for the requeued runs
+        if runs.iter().any(|run| run.status == RunStatus::Queued) {
+            sqlx::query("SELECT pg_notify($1, '')")
+                .bind(RUNS_CHANNEL)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        sqlx::query("DELETE FROM runners WHERE last_heartbeat_at < NOW() - INTERVAL '1 day'")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(runs)
+    }
+
+    // ==================== Query slots ====================
+
+    /// Take a query slot for an org if both the org and global counts are
+    /// under their limits
+    ///
+    /// Expired slots (whose holder stopped renewing them) are freed first.
+    /// The slot expires after `RUN_LEASE` unless `runner_heartbeat` renews it.
+    pub async fn acquire_query_slot(
+        &self,
+        org_id: Uuid,
+        holder: &str,
+        max_per_org: i64,
+        max_global: i64,
+    ) -> Result<QuerySlotAttempt> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(QUERY_SLOTS_LOCK_KEY)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM query_slots WHERE expires_at < NOW()")
+            .execute(&mut *tx)
+            .await?;
+
+        let (global_count, org_count): (i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*), COUNT(*) FILTER (WHERE org_id = $1) FROM query_slots",
+        )
+        .bind(org_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let mut attempt = QuerySlotAttempt {
+            slot_id: None,
+            global_count,
+            org_count,
+        };
+        if global_count >= max_global || org_count >= max_per_org {
+            tx.rollback().await?;
+            return Ok(attempt);
+        }
+
+        let slot_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO query_slots (id, org_id, holder, acquired_at, expires_at)
+            VALUES ($1, $2, $3, NOW(), NOW() + make_interval(secs => $4))
+            "#,
+        )
+        .bind(slot_id)
+        .bind(org_id)
+        .bind(holder)
+        .bind(RUN_LEASE.as_secs_f64())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        attempt.slot_id = Some(slot_id);
+        Ok(attempt)
+    }
+
+    /// Free a query slot
+    pub async fn release_query_slot(&self, slot_id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM query_slots WHERE id = $1")
+            .bind(slot_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Count unexpired query slots
+    ///
+    /// Returns (total slots, orgs holding slots)
+    pub async fn query_slot_counts(&self) -> Result<(i64, i64)> {
+        let counts: (i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*), COUNT(DISTINCT org_id) FROM query_slots WHERE expires_at >= NOW()",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(counts)
     }
 
     /// Get queue depth statistics
@@ -1365,6 +1918,17 @@ impl Database {
         Ok((pending.0, retry.0, dead_letter.0))
     }
 
+    /// Count queued runs in each priority lane (lanes with none are omitted)
+    pub async fn get_queue_depth_by_priority(&self) -> Result<Vec<(RunPriority, i64)>> {
+        let depths = sqlx::query_as(
+            "SELECT priority, COUNT(*) FROM runs WHERE status = 'queued' GROUP BY priority",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(depths)
+    }
+
     /// Move a permanently failed run to the dead letter queue
     ///
     /// This should be called when a run exceeds max retries.
@@ -1384,9 +1948,9 @@ impl Database {
             INSERT INTO run_failures (
                 run_id, org_id, query_id, datasource_id, executed_sql, parameters,
                 error_message, retry_count, max_retries, first_failed_at, last_failed_at,
-                created_by
+                created_by, timeout_seconds, max_rows
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, NOW(), $11)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, NOW(), $11, $12, $13)
             "#,
         )
         .bind(run.id)
@@ -1400,6 +1964,8 @@ impl Database {
         .bind(run.max_retries)
         .bind(run.started_at.unwrap_or(run.created_at))
         .bind(run.created_by)
+        .bind(run.timeout_seconds)
+        .bind(run.max_rows)
         .execute(&self.pool)
         .await?;
 
@@ -1412,31 +1978,227 @@ impl Database {
         Ok(())
     }
 
+    /// List an org's dead-lettered runs, optionally filtered by query,
+    /// datasource, and when they were dead-lettered
+    pub async fn list_run_failures_paginated(
+        &self,
+        org_id: Uuid,
+        query_id: Option<Uuid>,
+        datasource_id: Option<Uuid>,
+        start_date: Option<chrono::DateTime<chrono::Utc>>,
+        end_date: Option<chrono::DateTime<chrono::Utc>>,
+        sort_column: &str,
+        sort_direction: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<RunFailure>, i64)> {
+        const FILTER: &str = r#"
+            WHERE org_id = $1
+              AND ($2::uuid IS NULL OR query_id = $2)
+              AND ($3::uuid IS NULL OR datasource_id = $3)
+              AND ($4::timestamptz IS NULL OR created_at >= $4)
+              AND ($5::timestamptz IS NULL OR created_at <= $5)
+        "#;
+
+        let failures = sqlx::query_as::<_, RunFailure>(&format!(
+            "SELECT * FROM run_failures {} ORDER BY {} {} LIMIT $6 OFFSET $7",
+            FILTER, sort_column, sort_direction
+        ))
+        .bind(org_id)
+        .bind(query_id)
+        .bind(datasource_id)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total: (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM run_failures {}", FILTER))
+            .bind(org_id)
+            .bind(query_id)
+            .bind(datasource_id)
+            .bind(start_date)
+            .bind(end_date)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok((failures, total.0))
+    }
+
+    pub async fn get_run_failure(&self, id: Uuid, org_id: Uuid) -> Result<RunFailure> {
+        let failure = sqlx::query_as::<_, RunFailure>(
+            "SELECT * FROM run_failures WHERE id = $1 AND org_id = $2",
+        )
+        .bind(id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| Error::NotFound("Run failure not found".into()))?;
+
+        Ok(failure)
+    }
+
+    /// Record that a dead-lettered run was replayed as `run_id`
+    pub async fn mark_run_failure_replayed(
+        &self,
+        id: Uuid,
+        org_id: Uuid,
+        run_id: Uuid,
+    ) -> Result<RunFailure> {
+        let failure = sqlx::query_as::<_, RunFailure>(
+            r#"
+            UPDATE run_failures
+            SET replayed_run_id = $3, replayed_at = NOW()
+            WHERE id = $1 AND org_id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(org_id)
+        .bind(run_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(failure)
+    }
+
+    /// Delete an org's dead-lettered runs matching every given criterion
+    ///
+    /// Returns the number deleted. With no criteria, everything in the org
+    /// is deleted; callers should guard against that.
+    pub async fn purge_run_failures(
+        &self,
+        org_id: Uuid,
+        ids: Option<&[Uuid]>,
+        query_id: Option<Uuid>,
+        datasource_id: Option<Uuid>,
+        before: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM run_failures
+            WHERE org_id = $1
+              AND ($2::uuid[] IS NULL OR id = ANY($2))
+              AND ($3::uuid IS NULL OR query_id = $3)
+              AND ($4::uuid IS NULL OR datasource_id = $4)
+              AND ($5::timestamptz IS NULL OR created_at < $5)
+            "#,
+        )
+        .bind(org_id)
+        .bind(ids)
+        .bind(query_id)
+        .bind(datasource_id)
+        .bind(before)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
     // ==================== Run Results ====================
 
     pub async fn create_run_result(
         &self,
         run_id: Uuid,
         columns: &serde_json::Value,
-        rows: &serde_json::Value,
+        rows: &(impl serde::Serialize + Sync + ?Sized),
         row_count: i64,
         byte_count: i64,
         execution_time_ms: i64,
+        truncation_reason: Option<TruncationReason>,
     ) -> Result<RunResult> {
-        let result = sqlx::query_as::<_, RunResult>(
+        let result = sqlx::query_as::<_, RunResult>(&format!(
             r#"
-            INSERT INTO run_results (id, run_id, columns, rows, row_count, byte_count, execution_time_ms, created_at, expires_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, NOW(), NOW() + INTERVAL '7 days')
+            INSERT INTO run_results (id, run_id, columns, rows, row_count, byte_count, execution_time_ms, truncated, truncation_reason, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, NOW(), {})
             RETURNING *
             "#,
-        )
+            result_expires_at_sql()
+        ))
+        .bind(Uuid::new_v4())
+        .bind(run_id)
+        .bind(columns)
+        .bind(sqlx::types::Json(rows))
+        .bind(row_count)
+        .bind(byte_count)
+        .bind(execution_time_ms)
+        .bind(truncation_reason.is_some())
+        .bind(truncation_reason)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Record a result whose rows were written to object storage under
+    /// `storage_key`, serialized with `encoding`
+    pub async fn create_offloaded_run_result(
+        &self,
+        run_id: Uuid,
+        columns: &serde_json::Value,
+        storage_key: &str,
+        encoding: ResultEncoding,
+        row_count: i64,
+        byte_count: i64,
+        execution_time_ms: i64,
+        truncation_reason: Option<TruncationReason>,
+    ) -> Result<RunResult> {
+        let result = sqlx::query_as::<_, RunResult>(&format!(
+            r#"
+            INSERT INTO run_results (id, run_id, columns, storage_key, encoding, row_count, byte_count, execution_time_ms, truncated, truncation_reason, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, NOW(), {})
+            RETURNING *
+            "#,
+            result_expires_at_sql()
+        ))
         .bind(Uuid::new_v4())
         .bind(run_id)
         .bind(columns)
-        .bind(rows)
+        .bind(storage_key)
+        .bind(encoding)
         .bind(row_count)
         .bind(byte_count)
         .bind(execution_time_ms)
+        .bind(truncation_reason.is_some())
+        .bind(truncation_reason)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Record a result whose rows are stored inline, already serialized with
+    /// a non-JSON `encoding`
+    pub async fn create_encoded_run_result(
+        &self,
+        run_id: Uuid,
+        columns: &serde_json::Value,
+        encoding: ResultEncoding,
+        data: &[u8],
+        row_count: i64,
+        byte_count: i64,
+        execution_time_ms: i64,
+        truncation_reason: Option<TruncationReason>,
+    ) -> Result<RunResult> {
+        let result = sqlx::query_as::<_, RunResult>(&format!(
+            r#"
+            INSERT INTO run_results (id, run_id, columns, encoding, data, row_count, byte_count, execution_time_ms, truncated, truncation_reason, created_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, NOW(), {})
+            RETURNING *
+            "#,
+            result_expires_at_sql()
+        ))
+        .bind(Uuid::new_v4())
+        .bind(run_id)
+        .bind(columns)
+        .bind(encoding)
+        .bind(data)
+        .bind(row_count)
+        .bind(byte_count)
+        .bind(execution_time_ms)
+        .bind(truncation_reason.is_some())
+        .bind(truncation_reason)
         .fetch_one(&self.pool)
         .await?;
 
@@ -1452,6 +2214,107 @@ impl Database {
         Ok(result)
     }
 
+    // ==================== Retention ====================
+
+    /// Delete up to `limit` results that have expired or fall outside their
+    /// query's `keep_last`, sparing the latest result of each query on a
+    /// dashboard where the policy keeps those
+    ///
+    /// Returns one entry per deleted result: its object storage key if its
+    /// rows were offloaded, for the caller to delete.
+    pub async fn delete_expired_run_results(&self, limit: i64) -> Result<Vec<Option<String>>> {
+        let keys = sqlx::query_scalar(&format!(
+            r#"
+            WITH ranked AS (
+                SELECT rr.id, rr.expires_at, r.query_id,
+                       ROW_NUMBER() OVER (PARTITION BY r.query_id ORDER BY rr.created_at DESC) AS recency,
+                       {} AS keep_last,
+                       {} AS keep_dashboard_results
+                FROM run_results rr
+                JOIN runs r ON r.id = rr.run_id
+                JOIN queries q ON q.id = r.query_id
+                JOIN organizations o ON o.id = r.org_id
+            ),
+            expired AS (
+                SELECT id FROM ranked
+                WHERE (expires_at < NOW() OR recency > keep_last)
+                  AND NOT (
+                      recency = 1
+                      AND keep_dashboard_results
+                      AND EXISTS (
+                          SELECT 1 FROM visualizations v
+                          JOIN tiles t ON t.visualization_id = v.id
+                          WHERE v.query_id = ranked.query_id
+                      )
+                  )
+                LIMIT $1
+            )
+            DELETE FROM run_results
+            WHERE id IN (SELECT id FROM expired)
+            RETURNING storage_key
+            "#,
+            retention_sql("keep_last", "int", "NULL"),
+            retention_sql("keep_dashboard_results", "boolean", "TRUE"),
+        ))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(keys)
+    }
+
+    /// Delete up to `limit` finished runs older than their retention period
+    /// that no longer have a result; runs waiting to be retried are kept
+    pub async fn delete_expired_runs(&self, limit: i64) -> Result<u64> {
+        let result = sqlx::query(&format!(
+            r#"
+            WITH expired AS (
+                SELECT r.id FROM runs r
+                JOIN queries q ON q.id = r.query_id
+                JOIN organizations o ON o.id = r.org_id
+                WHERE r.status IN ('completed', 'failed', 'cancelled', 'timeout')
+                  AND NOT (r.status = 'failed' AND r.next_retry_at IS NOT NULL)
+                  AND r.created_at < NOW() - make_interval(days => {})
+                  AND NOT EXISTS (SELECT 1 FROM run_results rr WHERE rr.run_id = r.id)
+                LIMIT $1
+            )
+            DELETE FROM runs
+            WHERE id IN (SELECT id FROM expired)
+            "#,
+            retention_sql("keep_days", "int", &DEFAULT_RETENTION_DAYS.to_string()),
+        ))
+        .bind(limit)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete up to `limit` dead-lettered failures whose last attempt is
+    /// older than their retention period
+    pub async fn delete_expired_run_failures(&self, limit: i64) -> Result<u64> {
+        // The failure's query may have been deleted since; the org's policy applies then
+        let result = sqlx::query(&format!(
+            r#"
+            WITH expired AS (
+                SELECT f.id FROM run_failures f
+                JOIN organizations o ON o.id = f.org_id
+                LEFT JOIN queries q ON q.id = f.query_id
+                WHERE f.last_failed_at < NOW() - make_interval(days => {})
+                LIMIT $1
+            )
+            DELETE FROM run_failures
+            WHERE id IN (SELECT id FROM expired)
+            "#,
+            retention_sql("keep_days", "int", &DEFAULT_RETENTION_DAYS.to_string()),
+        ))
+        .bind(limit)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
     // ==================== Visualizations ====================
 
     pub async fn create_visualization(
@@ -2121,13 +2984,14 @@ impl Database {
         tags: &serde_json::Value,
         enabled: bool,
         created_by: Uuid,
+        retry_policy: Option<&RetryPolicy>,
     ) -> Result<Schedule> {
         let next_run_at = Self::calculate_next_run(cron_expression, enabled);
 
         let schedule = sqlx::query_as::<_, Schedule>(
             r#"
-            INSERT INTO schedules (id, org_id, query_id, name, cron_expression, parameters, tags, enabled, next_run_at, created_by, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, NOW(), NOW())
+            INSERT INTO schedules (id, org_id, query_id, name, cron_expression, parameters, tags, enabled, next_run_at, created_by, retry_policy, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NOW(), NOW())
             RETURNING *
             "#,
         )
@@ -2141,6 +3005,7 @@ impl Database {
         .bind(enabled)
         .bind(next_run_at)
         .bind(created_by)
+        .bind(retry_policy.map(sqlx::types::Json))
         .fetch_one(&self.pool)
         .await?;
 
@@ -2391,6 +3256,8 @@ impl Database {
         Ok(schedule)
     }
 
+    /// Update a schedule; `retry_policy` is left unchanged when None and
+    /// cleared (back to the query's policy) when `Some(None)`
     pub async fn update_schedule(
         &self,
         id: Uuid,
@@ -2400,6 +3267,7 @@ impl Database {
         parameters: Option<&serde_json::Value>,
         tags: Option<&serde_json::Value>,
         enabled: Option<bool>,
+        retry_policy: Option<Option<&RetryPolicy>>,
     ) -> Result<Schedule> {
         // Get existing schedule to determine current values for next_run calculation
         let existing = self.get_schedule(id, org_id).await?;
@@ -2418,6 +3286,7 @@ impl Database {
                 tags = COALESCE($6, tags),
                 enabled = COALESCE($7, enabled),
                 next_run_at = $8,
+                retry_policy = CASE WHEN $9 THEN $10 ELSE retry_policy END,
                 updated_at = NOW()
             WHERE id = $1 AND org_id = $2
             RETURNING *
@@ -2431,6 +3300,8 @@ impl Database {
         .bind(tags)
         .bind(enabled)
         .bind(next_run_at)
+        .bind(retry_policy.is_some())
+        .bind(retry_policy.flatten().map(sqlx::types::Json))
         .fetch_optional(&self.pool)
         .await?
         .ok_or_else(|| Error::NotFound("Schedule not found".into()))?;
@@ -16,11 +16,40 @@ pub enum Error {
     // Server errors (hide details from client)
     Internal(String),
     Database(String),
-    Connection(String),
-    QueryExecution(String),
+    Connection(String, Option<ErrorCode>),
+    QueryExecution(String, Option<ErrorCode>),
     Timeout(String),
 }
 
+/// Machine-readable cause reported by a datasource, used to decide retries
+/// (see `connectors::is_retryable`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// Failed below the protocol (refused, reset, TLS, pool timed out), so
+    /// the server reported nothing
+    Network,
+    /// Postgres SQLSTATE, e.g. `40P01`
+    Postgres(String),
+    /// MySQL error number, e.g. `1213`
+    MySql(u16),
+    /// SQLite primary or extended result code
+    Sqlite(i32),
+    /// HTTP status from an HTTP or Prometheus datasource
+    Http(u16),
+    /// JSON-RPC error code from a connector plugin
+    Plugin(i64),
+}
+
+impl Error {
+    /// The datasource's code for a connection or query error, if it gave one
+    pub fn code(&self) -> Option<&ErrorCode> {
+        match self {
+            Error::Connection(_, code) | Error::QueryExecution(_, code) => code.as_ref(),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -31,8 +60,8 @@ impl fmt::Display for Error {
             Error::Conflict(msg) => write!(f, "Conflict: {}", msg),
             Error::Internal(msg) => write!(f, "Internal error: {}", msg),
             Error::Database(msg) => write!(f, "Database error: {}", msg),
-            Error::Connection(msg) => write!(f, "Connection error: {}", msg),
-            Error::QueryExecution(msg) => write!(f, "Query execution error: {}", msg),
+            Error::Connection(msg, _) => write!(f, "Connection error: {}", msg),
+            Error::QueryExecution(msg, _) => write!(f, "Query execution error: {}", msg),
             Error::Timeout(msg) => write!(f, "Timeout: {}", msg),
         }
     }
@@ -97,10 +126,11 @@ impl ResponseError for Error {
                     "A database error occurred. Please try again later.".to_string(),
                 )
             }
-            Error::Connection(msg) => {
+            Error::Connection(msg, code) => {
                 tracing::error!(
                     error_id = %error_id,
                     error = %msg,
+                    code = ?code,
                     "Connection error"
                 );
                 (
@@ -109,10 +139,11 @@ impl ResponseError for Error {
                     "Failed to connect to external service. Please try again later.".to_string(),
                 )
             }
-            Error::QueryExecution(msg) => {
+            Error::QueryExecution(msg, code) => {
                 tracing::error!(
                     error_id = %error_id,
                     error = %msg,
+                    code = ?code,
                     "Query execution error"
                 );
                 (
@@ -165,6 +196,22 @@ impl From<sqlx::Error> for Error {
     }
 }
 
+impl From<object_store::Error> for Error {
+    fn from(err: object_store::Error) -> Self {
+        match err {
+            object_store::Error::NotFound { .. } => {
+                tracing::error!(error = %err, "Result object missing from storage");
+                Error::NotFound("Result not found".to_string())
+            }
+            _ => {
+                // Log full error server-side; it can include bucket and endpoint details
+                tracing::error!(error = %err, "Result storage error");
+                Error::Internal("Result storage failed".to_string())
+            }
+        }
+    }
+}
+
 impl From<argon2::password_hash::Error> for Error {
     fn from(err: argon2::password_hash::Error) -> Self {
         // Never expose password hashing details
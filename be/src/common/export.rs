@@ -0,0 +1,879 @@
+//! Export of run results as downloadable files
+//!
+//! Each column's declared type decides how its values are written: numbers
+//! stay numbers, dates and timestamps become native spreadsheet and Parquet
+//! dates (timestamps in UTC), and nulls are left empty. Decimals are never
+//! rounded through floating point: Parquet gets a DECIMAL column when the
+//! precision and scale are declared (text otherwise) and XLSX gets their
+//! text. A value that doesn't
+//! fit its column's type is written as text rather than dropped, and in CSV
+//! and TSV text a spreadsheet would run as a formula is quoted. CSV, TSV and
+//! JSON Lines are produced a chunk of rows at a time so they can be streamed;
+//! XLSX and Parquet are built whole.
+
+use crate::error::{Error, Result};
+use crate::models::ColumnDef;
+use crate::uploads::{parse_timestamp, unique_column_names};
+use actix_web::http::header::{
+    Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue,
+};
+use bytes::Bytes;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat};
+use parquet::basic::{Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType};
+use parquet::data_type::{
+    BoolType, ByteArray, ByteArrayType, DoubleType, FixedLenByteArray, FixedLenByteArrayType,
+    Int32Type, Int64Type,
+};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use rust_xlsxwriter::{Format, Workbook};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Rows per streamed chunk of a text export
+const CHUNK_ROWS: usize = 1000;
+
+/// Rows per Parquet row group
+const ROW_GROUP_ROWS: usize = 100_000;
+
+/// Rows and columns a worksheet can hold, including the header row
+const XLSX_MAX_ROWS: usize = 1_048_576;
+const XLSX_MAX_COLUMNS: usize = 16_384;
+
+/// Longest string a worksheet cell can hold, in characters
+const XLSX_MAX_STRING_CHARS: usize = 32_767;
+
+/// Longest exported file name, before the extension
+const MAX_FILENAME_CHARS: usize = 100;
+
+/// Most digits a Parquet decimal held in `DECIMAL_BYTES` can have
+const MAX_DECIMAL_PRECISION: u8 = 38;
+const DECIMAL_BYTES: i32 = 16;
+
+/// Supported export file formats
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Tsv,
+    Xlsx,
+    Parquet,
+    /// One JSON object per row
+    Jsonl,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Tsv => "tsv",
+            Self::Xlsx => "xlsx",
+            Self::Parquet => "parquet",
+            Self::Jsonl => "jsonl",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Tsv => "text/tab-separated-values; charset=utf-8",
+            Self::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            Self::Parquet => "application/vnd.apache.parquet",
+            Self::Jsonl => "application/x-ndjson",
+        }
+    }
+
+    /// Whether the file can be written a chunk at a time with `text_chunks`
+    pub fn is_streamed(&self) -> bool {
+        matches!(self, Self::Csv | Self::Tsv | Self::Jsonl)
+    }
+}
+
+/// How a column's values are written, from its declared type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Integer,
+    Float,
+    /// Fixed-point number with its precision and scale, when declared
+    Decimal(Option<(u8, u8)>),
+    Boolean,
+    Date,
+    Timestamp,
+    Text,
+}
+
+impl ColumnKind {
+    fn of(column: &ColumnDef) -> Self {
+        let data_type = column.data_type.to_uppercase();
+        // Drop precision and modifiers, e.g. NUMERIC(10,2), BIGINT UNSIGNED
+        let base = data_type.split(['(', ' ']).next().unwrap_or("");
+        match base {
+            "INT" | "INT2" | "INT4" | "INT8" | "INTEGER" | "SMALLINT" | "BIGINT" | "TINYINT"
+            | "MEDIUMINT" => Self::Integer,
+            "FLOAT" | "FLOAT4" | "FLOAT8" | "REAL" | "DOUBLE" => Self::Float,
+            "NUMERIC" | "DECIMAL" => Self::Decimal(decimal_precision_scale(&data_type)),
+            "BOOL" | "BOOLEAN" => Self::Boolean,
+            "DATE" => Self::Date,
+            "TIMESTAMP" | "TIMESTAMPTZ" | "DATETIME" => Self::Timestamp,
+            _ => Self::Text,
+        }
+    }
+}
+
+/// Precision and scale declared in e.g. `NUMERIC(10,2)`; a missing scale is 0
+fn decimal_precision_scale(data_type: &str) -> Option<(u8, u8)> {
+    let args = data_type.split_once('(')?.1.split_once(')')?.0;
+    let (precision, scale) = match args.split_once(',') {
+        Some((precision, scale)) => (precision.trim().parse().ok()?, scale.trim().parse().ok()?),
+        None => (args.trim().parse().ok()?, 0),
+    };
+    let valid = (1..=MAX_DECIMAL_PRECISION).contains(&precision) && scale <= precision;
+    valid.then_some((precision, scale))
+}
+
+/// `Content-Disposition` for downloading a result of the query `name`
+///
+/// Characters that aren't safe in file names are replaced, and non-ASCII
+/// names also get an ASCII fallback for older clients.
+pub fn content_disposition(name: &str, format: ExportFormat) -> ContentDisposition {
+    let stem: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .take(MAX_FILENAME_CHARS)
+        .collect();
+    let stem = stem.trim().trim_matches('.');
+    let stem = if stem.is_empty() { "result" } else { stem };
+
+    let filename = format!("{}.{}", stem, format.extension());
+    let ascii: String = filename
+        .chars()
+        .map(|c| if c.is_ascii() { c } else { '_' })
+        .collect();
+
+    let mut parameters = vec![DispositionParam::Filename(ascii.clone())];
+    if ascii != filename {
+        parameters.push(DispositionParam::FilenameExt(ExtendedValue {
+            charset: Charset::Ext("UTF-8".to_string()),
+            language_tag: None,
+            value: filename.into_bytes(),
+        }));
+    }
+
+    ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters,
+    }
+}
+
+/// Encode a whole result as `format`
+pub fn encode(format: ExportFormat, columns: &[ColumnDef], rows: Vec<Vec<Value>>) -> Result<Vec<u8>> {
+    match format {
+        ExportFormat::Xlsx => encode_xlsx(columns, &rows),
+        ExportFormat::Parquet => encode_parquet(columns, &rows),
+        _ => {
+            let mut out = Vec::new();
+            for chunk in text_chunks(format, columns, rows) {
+                out.extend_from_slice(&chunk?);
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// Encode a result as CSV, TSV or JSON Lines a chunk of rows at a time
+///
+/// The first chunk starts with the header (for CSV and TSV). Other formats
+/// produce nothing.
+pub fn text_chunks(format: ExportFormat, columns: &[ColumnDef], rows: Vec<Vec<Value>>) -> TextChunks {
+    let names: Vec<String> = columns.iter().map(|c| c.name.clone()).collect();
+    TextChunks {
+        format,
+        kinds: columns.iter().map(ColumnKind::of).collect(),
+        names: unique_column_names(&names),
+        header: matches!(format, ExportFormat::Csv | ExportFormat::Tsv),
+        rows: if format.is_streamed() { rows } else { Vec::new() }.into_iter(),
+    }
+}
+
+/// Iterator over the chunks of a text export; see `text_chunks`
+pub struct TextChunks {
+    format: ExportFormat,
+    kinds: Vec<ColumnKind>,
+    names: Vec<String>,
+    header: bool,
+    rows: std::vec::IntoIter<Vec<Value>>,
+}
+
+impl Iterator for TextChunks {
+    type Item = Result<Bytes>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let header = std::mem::take(&mut self.header);
+        let rows: Vec<Vec<Value>> = self.rows.by_ref().take(CHUNK_ROWS).collect();
+        if rows.is_empty() && !header {
+            return None;
+        }
+
+        let chunk = match self.format {
+            ExportFormat::Jsonl => self.jsonl_chunk(&rows),
+            _ => self.delimited_chunk(header, &rows),
+        };
+        Some(chunk.map(Bytes::from))
+    }
+}
+
+impl TextChunks {
+    fn delimited_chunk(&self, header: bool, rows: &[Vec<Value>]) -> Result<Vec<u8>> {
+        let delimiter = if self.format == ExportFormat::Tsv { b'\t' } else { b',' };
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter)
+            .from_writer(Vec::new());
+
+        if header {
+            writer.write_record(&self.names).map_err(export_error)?;
+        }
+        for row in rows {
+            let record = self
+                .kinds
+                .iter()
+                .enumerate()
+                .map(|(i, kind)| text_cell(row.get(i).unwrap_or(&Value::Null), *kind));
+            writer.write_record(record).map_err(export_error)?;
+        }
+
+        writer
+            .into_inner()
+            .map_err(|e| Error::Internal(format!("Failed to write export: {}", e)))
+    }
+
+    fn jsonl_chunk(&self, rows: &[Vec<Value>]) -> Result<Vec<u8>> {
+        let mut out = Vec::new();
+        for row in rows {
+            let object: serde_json::Map<String, Value> = self
+                .names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| (name.clone(), row.get(i).cloned().unwrap_or(Value::Null)))
+                .collect();
+            serde_json::to_writer(&mut out, &object).map_err(export_error)?;
+            out.push(b'\n');
+        }
+        Ok(out)
+    }
+}
+
+/// A cell of a CSV or TSV file; nulls are empty and timestamps are UTC
+/// ISO 8601
+fn text_cell(value: &Value, kind: ColumnKind) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) if kind == ColumnKind::Timestamp => parse_timestamp(s)
+            .map(format_timestamp)
+            .unwrap_or_else(|| escape_formula(s)),
+        // Numbers sent as strings keep their sign
+        Value::String(s)
+            if matches!(kind, ColumnKind::Integer | ColumnKind::Float | ColumnKind::Decimal(_))
+                && s.trim().parse::<f64>().is_ok() =>
+        {
+            s.clone()
+        }
+        Value::String(s) => escape_formula(s),
+        other => other.to_string(),
+    }
+}
+
+/// Quote text a spreadsheet opening the file would run as a formula
+fn escape_formula(s: &str) -> String {
+    if s.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", s)
+    } else {
+        s.to_string()
+    }
+}
+
+fn format_timestamp(ts: NaiveDateTime) -> String {
+    ts.and_utc().to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// A value converted to its column's type, if it fits
+enum Cell {
+    Null,
+    Integer(i64),
+    Float(f64),
+    /// Value times 10^scale
+    Decimal { unscaled: i128, scale: u8 },
+    Boolean(bool),
+    Date(NaiveDate),
+    Timestamp(NaiveDateTime),
+    Text(String),
+}
+
+impl Cell {
+    /// Convert `value` for a column of `kind`, or `None` if it doesn't fit
+    fn typed(value: &Value, kind: ColumnKind) -> Option<Self> {
+        let cell = match (kind, value) {
+            (_, Value::Null) => Self::Null,
+            (ColumnKind::Integer, Value::Number(n)) => Self::Integer(n.as_i64()?),
+            (ColumnKind::Integer, Value::String(s)) => Self::Integer(s.trim().parse().ok()?),
+            (ColumnKind::Float, Value::Number(n)) => Self::Float(n.as_f64()?),
+            (ColumnKind::Float, Value::String(s)) => Self::Float(s.trim().parse().ok()?),
+            (ColumnKind::Decimal(Some((precision, scale))), Value::Number(n)) => Self::Decimal {
+                unscaled: parse_decimal(&n.to_string(), precision, scale)?,
+                scale,
+            },
+            (ColumnKind::Decimal(Some((precision, scale))), Value::String(s)) => Self::Decimal {
+                unscaled: parse_decimal(s, precision, scale)?,
+                scale,
+            },
+            // Without a declared scale, keep the digits as they came
+            (ColumnKind::Decimal(None), Value::Number(n)) => Self::Text(n.to_string()),
+            (ColumnKind::Decimal(None), Value::String(s)) => {
+                s.trim().parse::<f64>().ok()?;
+                Self::Text(s.clone())
+            }
+            (ColumnKind::Boolean, Value::Bool(b)) => Self::Boolean(*b),
+            (ColumnKind::Date, Value::String(s)) => {
+                Self::Date(NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok()?)
+            }
+            (ColumnKind::Timestamp, Value::String(s)) => Self::Timestamp(parse_timestamp(s.trim())?),
+            (ColumnKind::Text, value) => Self::text(value),
+            _ => return None,
+        };
+        Some(cell)
+    }
+
+    /// Convert `value` for a column of `kind`, falling back to its own JSON
+    /// type (or text, for decimals)
+    fn of(value: &Value, kind: ColumnKind) -> Self {
+        Self::typed(value, kind).unwrap_or_else(|| match value {
+            _ if matches!(kind, ColumnKind::Decimal(_)) => Self::text(value),
+            Value::Number(n) => n.as_f64().map(Self::Float).unwrap_or_else(|| Self::text(value)),
+            Value::Bool(b) => Self::Boolean(*b),
+            _ => Self::text(value),
+        })
+    }
+
+    fn text(value: &Value) -> Self {
+        match value {
+            Value::String(s) => Self::Text(s.clone()),
+            other => Self::Text(other.to_string()),
+        }
+    }
+}
+
+/// A decimal string as an integer of `10^-scale` units, if it has at most
+/// `scale` fractional digits and `precision` digits in all
+fn parse_decimal(s: &str, precision: u8, scale: u8) -> Option<i128> {
+    let s = s.trim();
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let integer = integer.trim_start_matches('0');
+    let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if digits.is_empty()
+        || digits == "."
+        || !all_digits(integer)
+        || !all_digits(fraction)
+        || fraction.len() > scale as usize
+        || integer.len() + scale as usize > precision as usize
+    {
+        return None;
+    }
+
+    let padding = std::iter::repeat_n(b'0', scale as usize - fraction.len());
+    let unscaled = integer
+        .bytes()
+        .chain(fraction.bytes())
+        .chain(padding)
+        .fold(0i128, |n, digit| n * 10 + (digit - b'0') as i128);
+    Some(if negative { -unscaled } else { unscaled })
+}
+
+/// An unscaled decimal written out with `scale` fractional digits
+fn format_decimal(unscaled: i128, scale: u8) -> String {
+    let digits = format!("{:0>width$}", unscaled.unsigned_abs(), width = scale as usize + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale as usize);
+    let sign = if unscaled < 0 { "-" } else { "" };
+    if fraction.is_empty() {
+        format!("{}{}", sign, integer)
+    } else {
+        format!("{}{}.{}", sign, integer, fraction)
+    }
+}
+
+fn encode_xlsx(columns: &[ColumnDef], rows: &[Vec<Value>]) -> Result<Vec<u8>> {
+    if rows.len() >= XLSX_MAX_ROWS || columns.len() > XLSX_MAX_COLUMNS {
+        return Err(Error::BadRequest(format!(
+            "Result is too large for XLSX (at most {} rows and {} columns); export it as CSV or Parquet",
+            XLSX_MAX_ROWS - 1,
+            XLSX_MAX_COLUMNS
+        )));
+    }
+
+    let header = Format::new().set_bold();
+    let date = Format::new().set_num_format("yyyy-mm-dd");
+    let timestamp = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss");
+    let kinds: Vec<ColumnKind> = columns.iter().map(ColumnKind::of).collect();
+
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    for (c, column) in columns.iter().enumerate() {
+        sheet
+            .write_string_with_format(0, c as u16, &column.name, &header)
+            .map_err(export_error)?;
+    }
+
+    for (r, row) in rows.iter().enumerate() {
+        let r = r as u32 + 1;
+        for (c, kind) in kinds.iter().enumerate() {
+            let c = c as u16;
+            match Cell::of(row.get(c as usize).unwrap_or(&Value::Null), *kind) {
+                Cell::Null => continue,
+                Cell::Integer(n) => sheet.write_number(r, c, n as f64),
+                Cell::Float(n) => sheet.write_number(r, c, n),
+                // As text, since a worksheet number is a float
+                Cell::Decimal { unscaled, scale } => {
+                    sheet.write_string(r, c, format_decimal(unscaled, scale))
+                }
+                Cell::Boolean(b) => sheet.write_boolean(r, c, b),
+                Cell::Date(d) => sheet.write_date_with_format(r, c, d, &date),
+                Cell::Timestamp(ts) => sheet.write_datetime_with_format(r, c, ts, &timestamp),
+                Cell::Text(s) => {
+                    let s: String = s.chars().take(XLSX_MAX_STRING_CHARS).collect();
+                    sheet.write_string(r, c, s)
+                }
+            }
+            .map_err(export_error)?;
+        }
+    }
+    sheet.set_freeze_panes(1, 0).map_err(export_error)?;
+
+    workbook.save_to_buffer().map_err(export_error)
+}
+
+fn encode_parquet(columns: &[ColumnDef], rows: &[Vec<Value>]) -> Result<Vec<u8>> {
+    // A column whose values don't all fit its declared type is written as
+    // text, as are decimals of unknown precision
+    let kinds: Vec<ColumnKind> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let kind = match ColumnKind::of(column) {
+                ColumnKind::Decimal(None) => ColumnKind::Text,
+                kind => kind,
+            };
+            let fits = rows
+                .iter()
+                .all(|row| Cell::typed(row.get(i).unwrap_or(&Value::Null), kind).is_some());
+            if fits { kind } else { ColumnKind::Text }
+        })
+        .collect();
+
+    let names: Vec<String> = columns.iter().map(|c| c.name.clone()).collect();
+    let fields = unique_column_names(&names)
+        .into_iter()
+        .zip(&kinds)
+        .map(|(name, kind)| {
+            if let ColumnKind::Decimal(Some((precision, scale))) = *kind {
+                let (precision, scale) = (precision as i32, scale as i32);
+                return Type::primitive_type_builder(&name, PhysicalType::FIXED_LEN_BYTE_ARRAY)
+                    .with_repetition(Repetition::OPTIONAL)
+                    .with_length(DECIMAL_BYTES)
+                    .with_precision(precision)
+                    .with_scale(scale)
+                    .with_logical_type(Some(LogicalType::Decimal { scale, precision }))
+                    .build()
+                    .map(Arc::new);
+            }
+            let (physical, logical) = match kind {
+                ColumnKind::Integer => (PhysicalType::INT64, None),
+                ColumnKind::Float => (PhysicalType::DOUBLE, None),
+                ColumnKind::Decimal(_) => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+                ColumnKind::Boolean => (PhysicalType::BOOLEAN, None),
+                ColumnKind::Date => (PhysicalType::INT32, Some(LogicalType::Date)),
+                ColumnKind::Timestamp => (
+                    PhysicalType::INT64,
+                    Some(LogicalType::Timestamp {
+                        is_adjusted_to_u_t_c: true,
+                        unit: TimeUnit::MICROS(Default::default()),
+                    }),
+                ),
+                ColumnKind::Text => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+            };
+            Type::primitive_type_builder(&name, physical)
+                .with_repetition(Repetition::OPTIONAL)
+                .with_logical_type(logical)
+                .build()
+                .map(Arc::new)
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(export_error)?;
+    let schema = Type::group_type_builder("schema")
+        .with_fields(fields)
+        .build()
+        .map_err(export_error)?;
+
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = SerializedFileWriter::new(Vec::new(), Arc::new(schema), Arc::new(properties))
+        .map_err(export_error)?;
+
+    for chunk in rows.chunks(ROW_GROUP_ROWS) {
+        let mut row_group = writer.next_row_group().map_err(export_error)?;
+        for (i, kind) in kinds.iter().enumerate() {
+            let cells: Vec<Cell> = chunk
+                .iter()
+                .map(|row| Cell::of(row.get(i).unwrap_or(&Value::Null), *kind))
+                .collect();
+            let levels: Vec<i16> = cells
+                .iter()
+                .map(|cell| if matches!(cell, Cell::Null) { 0 } else { 1 })
+                .collect();
+
+            let mut column = row_group
+                .next_column()
+                .map_err(export_error)?
+                .ok_or_else(|| Error::Internal("Parquet schema is missing a column".to_string()))?;
+            match kind {
+                ColumnKind::Integer => {
+                    let values: Vec<i64> = cells
+                        .iter()
+                        .filter_map(|cell| match cell {
+                            Cell::Integer(n) => Some(*n),
+                            _ => None,
+                        })
+                        .collect();
+                    column.typed::<Int64Type>().write_batch(&values, Some(&levels), None)
+                }
+                ColumnKind::Float => {
+                    let values: Vec<f64> = cells
+                        .iter()
+                        .filter_map(|cell| match cell {
+                            Cell::Float(n) => Some(*n),
+                            _ => None,
+                        })
+                        .collect();
+                    column.typed::<DoubleType>().write_batch(&values, Some(&levels), None)
+                }
+                ColumnKind::Decimal(_) => {
+                    let values: Vec<FixedLenByteArray> = cells
+                        .iter()
+                        .filter_map(|cell| match cell {
+                            Cell::Decimal { unscaled, .. } => {
+                                Some(FixedLenByteArray::from(unscaled.to_be_bytes().to_vec()))
+                            }
+                            _ => None,
+                        })
+                        .collect();
+                    column
+                        .typed::<FixedLenByteArrayType>()
+                        .write_batch(&values, Some(&levels), None)
+                }
+                ColumnKind::Boolean => {
+                    let values: Vec<bool> = cells
+                        .iter()
+                        .filter_map(|cell| match cell {
+                            Cell::Boolean(b) => Some(*b),
+                            _ => None,
+                        })
+                        .collect();
+                    column.typed::<BoolType>().write_batch(&values, Some(&levels), None)
+                }
+                ColumnKind::Date => {
+                    let values: Vec<i32> = cells
+                        .iter()
+                        .filter_map(|cell| match cell {
+                            Cell::Date(d) => Some((*d - DateTime::UNIX_EPOCH.date_naive()).num_days() as i32),
+                            _ => None,
+                        })
+                        .collect();
+                    column.typed::<Int32Type>().write_batch(&values, Some(&levels), None)
+                }
+                ColumnKind::Timestamp => {
+                    let values: Vec<i64> = cells
+                        .iter()
+                        .filter_map(|cell| match cell {
+                            Cell::Timestamp(ts) => Some(ts.and_utc().timestamp_micros()),
+                            _ => None,
+                        })
+                        .collect();
+                    column.typed::<Int64Type>().write_batch(&values, Some(&levels), None)
+                }
+                ColumnKind::Text => {
+                    let values: Vec<ByteArray> = cells
+                        .into_iter()
+                        .filter_map(|cell| match cell {
+                            Cell::Text(s) => Some(ByteArray::from(s.into_bytes())),
+                            _ => None,
+                        })
+                        .collect();
+                    column.typed::<ByteArrayType>().write_batch(&values, Some(&levels), None)
+                }
+            }
+            .map_err(export_error)?;
+            column.close().map_err(export_error)?;
+        }
+        row_group.close().map_err(export_error)?;
+    }
+
+    writer.into_inner().map_err(export_error)
+}
+
+fn export_error(e: impl std::fmt::Display) -> Error {
+    Error::Internal(format!("Failed to write export: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field;
+    use serde_json::json;
+
+    fn columns() -> Vec<ColumnDef> {
+        [
+            ("id", "INT8"),
+            ("name", "TEXT"),
+            ("amount", "NUMERIC(10,2)"),
+            ("active", "BOOL"),
+            ("day", "DATE"),
+            ("at", "TIMESTAMPTZ"),
+            ("name", "VARCHAR"),
+        ]
+        .into_iter()
+        .map(|(name, data_type)| ColumnDef {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+        })
+        .collect()
+    }
+
+    fn rows() -> Vec<Vec<Value>> {
+        vec![
+            vec![
+                json!(1),
+                json!("Alice, \"Al\""),
+                json!(10.5),
+                json!(true),
+                json!("2024-03-01"),
+                json!("2024-03-01T12:30:00+00:00"),
+                json!("x"),
+            ],
+            vec![
+                json!(2),
+                Value::Null,
+                json!("3"),
+                json!(false),
+                Value::Null,
+                json!("2024-03-02 08:00:00.250"),
+                json!({"a": 1}),
+            ],
+        ]
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let csv = String::from_utf8(encode(ExportFormat::Csv, &columns(), rows()).unwrap()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "id,name,amount,active,day,at,name_2");
+        assert_eq!(
+            lines[1],
+            r#"1,"Alice, ""Al""",10.5,true,2024-03-01,2024-03-01T12:30:00Z,x"#
+        );
+        assert_eq!(lines[2], r#"2,,3,false,,2024-03-02T08:00:00.250Z,"{""a"":1}""#);
+    }
+
+    #[test]
+    fn test_csv_export_escapes_formulas() {
+        let columns = &columns()[..3];
+        let rows = vec![
+            vec![json!(1), json!("=HYPERLINK(\"http://x\")"), json!("-3.5")],
+            vec![json!(-2), json!("@SUM(A1)"), json!(-4)],
+            vec![json!(3), json!("\t+1"), json!("-1+1")],
+            vec![json!(4), json!("a=b"), Value::Null],
+        ];
+        let csv = String::from_utf8(encode(ExportFormat::Csv, columns, rows).unwrap()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[1], r#"1,"'=HYPERLINK(""http://x"")",-3.5"#);
+        assert_eq!(lines[2], "-2,'@SUM(A1),-4");
+        assert_eq!(lines[3], "3,'\t+1,'-1+1");
+        assert_eq!(lines[4], "4,a=b,");
+    }
+
+    #[test]
+    fn test_tsv_export() {
+        let tsv = String::from_utf8(encode(ExportFormat::Tsv, &columns(), rows()).unwrap()).unwrap();
+        assert!(tsv.starts_with("id\tname\tamount\t"));
+        assert_eq!(tsv.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_jsonl_export() {
+        let jsonl = String::from_utf8(encode(ExportFormat::Jsonl, &columns(), rows()).unwrap()).unwrap();
+        let lines: Vec<Value> = jsonl.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["id"], json!(1));
+        assert_eq!(lines[0]["name_2"], json!("x"));
+        assert_eq!(lines[1]["name"], Value::Null);
+    }
+
+    #[test]
+    fn test_text_chunks_stream_rows() {
+        let columns = &columns()[..1];
+        let rows: Vec<Vec<Value>> = (0..CHUNK_ROWS as i64 + 1).map(|i| vec![json!(i)]).collect();
+        let chunks: Vec<Bytes> = text_chunks(ExportFormat::Csv, columns, rows)
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].starts_with(b"id\n0\n"));
+        assert_eq!(&chunks[1][..], format!("{}\n", CHUNK_ROWS).as_bytes());
+
+        // Header only for an empty result
+        let chunks: Vec<Bytes> = text_chunks(ExportFormat::Csv, columns, Vec::new())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(chunks, vec![Bytes::from_static(b"id\n")]);
+        assert_eq!(text_chunks(ExportFormat::Jsonl, columns, Vec::new()).count(), 0);
+        assert_eq!(text_chunks(ExportFormat::Xlsx, columns, vec![vec![json!(1)]]).count(), 0);
+    }
+
+    #[test]
+    fn test_xlsx_export() {
+        let xlsx = encode(ExportFormat::Xlsx, &columns(), rows()).unwrap();
+        // XLSX files are zip archives
+        assert!(xlsx.starts_with(b"PK"));
+    }
+
+    #[test]
+    fn test_parquet_export() {
+        let parquet = encode(ExportFormat::Parquet, &columns(), rows()).unwrap();
+        let reader = SerializedFileReader::new(Bytes::from(parquet)).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+
+        let rows: Vec<Vec<(String, Field)>> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().into_columns())
+            .collect();
+        assert_eq!(rows[0][0], ("id".to_string(), Field::Long(1)));
+        let decimal = |field: &Field| match field {
+            Field::Decimal(d) => (d.data().to_vec(), d.precision(), d.scale()),
+            other => panic!("not a decimal: {:?}", other),
+        };
+        assert_eq!(decimal(&rows[0][2].1), (1050i128.to_be_bytes().to_vec(), 10, 2));
+        assert_eq!(decimal(&rows[1][2].1), (300i128.to_be_bytes().to_vec(), 10, 2));
+        assert_eq!(rows[0][3].1, Field::Bool(true));
+        assert_eq!(rows[0][4].1, Field::Date(19783));
+        assert_eq!(rows[1][4].1, Field::Null);
+        assert_eq!(rows[0][5].1, Field::TimestampMicros(1_709_296_200_000_000));
+        // Mixed values fall back to text
+        assert_eq!(rows[1][6], ("name_2".to_string(), Field::Str(r#"{"a":1}"#.to_string())));
+    }
+
+    #[test]
+    fn test_parquet_decimals_keep_their_digits() {
+        let columns = vec![
+            ColumnDef {
+                name: "exact".to_string(),
+                data_type: "NUMERIC(38,4)".to_string(),
+            },
+            ColumnDef {
+                name: "unknown".to_string(),
+                data_type: "NUMERIC".to_string(),
+            },
+        ];
+        let rows = vec![
+            vec![json!("12345678901234567890.1234"), json!("0.10000000000000000001")],
+            vec![json!("-0.5"), json!(2)],
+        ];
+        let parquet = encode(ExportFormat::Parquet, &columns, rows).unwrap();
+        let reader = SerializedFileReader::new(Bytes::from(parquet)).unwrap();
+        let rows: Vec<Vec<(String, Field)>> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().into_columns())
+            .collect();
+
+        let unscaled = |field: &Field| match field {
+            Field::Decimal(d) => i128::from_be_bytes(d.data().try_into().unwrap()),
+            other => panic!("not a decimal: {:?}", other),
+        };
+        assert_eq!(unscaled(&rows[0][0].1), 123_456_789_012_345_678_901_234);
+        assert_eq!(unscaled(&rows[1][0].1), -5000);
+        assert_eq!(rows[0][1].1, Field::Str("0.10000000000000000001".to_string()));
+        assert_eq!(rows[1][1].1, Field::Str("2".to_string()));
+    }
+
+    #[test]
+    fn test_decimal_parsing() {
+        assert_eq!(parse_decimal("10.5", 10, 2), Some(1050));
+        assert_eq!(parse_decimal("-0.05", 10, 2), Some(-5));
+        assert_eq!(parse_decimal("007", 3, 0), Some(7));
+        assert_eq!(parse_decimal("1.234", 10, 2), None);
+        assert_eq!(parse_decimal("1000", 5, 2), None);
+        assert_eq!(parse_decimal("1e3", 10, 2), None);
+        assert_eq!(parse_decimal(".", 10, 2), None);
+
+        assert_eq!(format_decimal(1050, 2), "10.50");
+        assert_eq!(format_decimal(-5, 2), "-0.05");
+        assert_eq!(format_decimal(7, 0), "7");
+    }
+
+    #[test]
+    fn test_xlsx_rejects_too_many_rows() {
+        let rows = vec![vec![json!(1)]; XLSX_MAX_ROWS];
+        let err = encode(ExportFormat::Xlsx, &columns()[..1], rows).unwrap_err();
+        assert!(matches!(err, Error::BadRequest(_)));
+    }
+
+    #[test]
+    fn test_column_kind() {
+        let kind = |data_type: &str| {
+            ColumnKind::of(&ColumnDef {
+                name: "c".to_string(),
+                data_type: data_type.to_string(),
+            })
+        };
+        assert_eq!(kind("int4"), ColumnKind::Integer);
+        assert_eq!(kind("BIGINT UNSIGNED"), ColumnKind::Integer);
+        assert_eq!(kind("DOUBLE PRECISION"), ColumnKind::Float);
+        assert_eq!(kind("numeric(10, 2)"), ColumnKind::Decimal(Some((10, 2))));
+        assert_eq!(kind("DECIMAL(12)"), ColumnKind::Decimal(Some((12, 0))));
+        assert_eq!(kind("NUMERIC"), ColumnKind::Decimal(None));
+        assert_eq!(kind("NUMERIC(50,2)"), ColumnKind::Decimal(None));
+        assert_eq!(kind("DATETIME"), ColumnKind::Timestamp);
+        assert_eq!(kind("JSONB"), ColumnKind::Text);
+    }
+
+    #[test]
+    fn test_content_disposition() {
+        let header = |name: &str, format| content_disposition(name, format).to_string();
+        assert_eq!(
+            header("Q3 revenue / region", ExportFormat::Csv),
+            r#"attachment; filename="Q3 revenue _ region.csv""#
+        );
+        assert_eq!(header("  ..  ", ExportFormat::Xlsx), r#"attachment; filename="result.xlsx""#);
+
+        let disposition = content_disposition("Ventes été", ExportFormat::Parquet);
+        assert_eq!(disposition.get_filename(), Some("Ventes _t_.parquet"));
+        assert!(disposition.get_filename_ext().is_some());
+    }
+
+    #[test]
+    fn test_format_deserialization() {
+        let format: ExportFormat = serde_json::from_str(r#""jsonl""#).unwrap();
+        assert_eq!(format, ExportFormat::Jsonl);
+        assert!(serde_json::from_str::<ExportFormat>(r#""xls""#).is_err());
+        assert_eq!(ExportFormat::default(), ExportFormat::Csv);
+    }
+}
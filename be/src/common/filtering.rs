@@ -96,6 +96,9 @@ impl SortableColumns {
     pub const RUNS: &'static [&'static str] =
         &["created_at", "started_at", "completed_at"];
 
+    pub const RUN_FAILURES: &'static [&'static str] =
+        &["created_at", "first_failed_at", "last_failed_at"];
+
     pub const VISUALIZATIONS: &'static [&'static str] =
         &["name", "created_at", "updated_at"];
 
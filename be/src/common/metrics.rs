@@ -1,8 +1,9 @@
 use prometheus::{
-    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry,
-    TextEncoder,
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
 };
 use std::sync::Arc;
+use uuid::Uuid;
 
 /// Application metrics registry
 #[derive(Clone)]
@@ -21,6 +22,11 @@ pub struct Metrics {
     pub db_pool_acquire_duration_seconds: HistogramVec,
     pub db_pool_acquire_timeout_total: IntCounter,
 
+    // Datasource connector cache metrics
+    pub connector_cache_entries: IntGauge,
+    pub connector_cache_events_total: IntCounterVec,
+    pub connector_pool_connections: IntGaugeVec,
+
     // Query execution metrics
     pub query_executions_total: IntCounterVec,
     pub query_execution_duration_seconds: HistogramVec,
@@ -32,8 +38,16 @@ pub struct Metrics {
     pub jobs_claimed_total: IntCounterVec,
     pub job_processing_duration_seconds: HistogramVec,
     pub job_queue_depth: IntGauge,
+    pub job_queue_depth_by_priority: IntGaugeVec,
     pub job_retry_queue_depth: IntGauge,
     pub job_dead_letter_queue_size: IntGauge,
+    pub runs_reclaimed_total: IntCounterVec,
+    pub runs_throttled_total: IntCounter,
+    pub runners_active: IntGauge,
+
+    // Retention cleanup metrics
+    pub retention_deleted_total: IntCounterVec,
+    pub retention_last_cleanup_timestamp_seconds: IntGauge,
 
     // Cache metrics
     pub cache_requests_total: IntCounterVec,
@@ -110,6 +124,28 @@ impl Metrics {
             "Total number of database connection acquisition timeouts",
         )?;
 
+        // Datasource connector cache metrics
+        let connector_cache_entries = IntGauge::new(
+            "loupe_connector_cache_entries",
+            "Number of datasource connectors held in the connector cache",
+        )?;
+
+        let connector_cache_events_total = IntCounterVec::new(
+            Opts::new(
+                "loupe_connector_cache_events_total",
+                "Connector cache lookups and removals",
+            ),
+            &["event"], // hit, miss, evicted, invalidated
+        )?;
+
+        let connector_pool_connections = IntGaugeVec::new(
+            Opts::new(
+                "loupe_connector_pool_connections",
+                "Connections in each cached datasource pool",
+            ),
+            &["datasource_id", "state"], // active, idle, max
+        )?;
+
         // Query execution metrics
         let query_executions_total = IntCounterVec::new(
             Opts::new("loupe_query_executions_total", "Total number of query executions")
@@ -177,6 +213,16 @@ impl Metrics {
             "Number of jobs waiting in queue (status=queued)",
         )?;
 
+        let job_queue_depth_by_priority = IntGaugeVec::new(
+            Opts::new(
+                "loupe_job_queue_depth_by_priority",
+                "Number of jobs waiting in queue per priority lane",
+            )
+            .namespace("loupe")
+            .subsystem("runner"),
+            &["priority"], // interactive, dashboard, scheduled, backfill
+        )?;
+
         let job_retry_queue_depth = IntGauge::new(
             "loupe_job_retry_queue_depth",
             "Number of jobs waiting for retry (status=failed with next_retry_at)",
@@ -187,6 +233,42 @@ impl Metrics {
             "Number of permanently failed jobs in dead letter queue",
         )?;
 
+        let runs_reclaimed_total = IntCounterVec::new(
+            Opts::new(
+                "loupe_runs_reclaimed_total",
+                "Total number of runs reclaimed after their runner stopped heartbeating",
+            )
+            .namespace("loupe")
+            .subsystem("runner"),
+            &["outcome"], // "requeued" or "failed"
+        )?;
+
+        let runs_throttled_total = IntCounter::new(
+            "loupe_runs_throttled_total",
+            "Total number of times a run was requeued because the query limiter was full",
+        )?;
+
+        let runners_active = IntGauge::new(
+            "loupe_runners_active",
+            "Number of runners with a heartbeat within the lease period",
+        )?;
+
+        // Retention cleanup metrics
+        let retention_deleted_total = IntCounterVec::new(
+            Opts::new(
+                "loupe_retention_deleted_total",
+                "Total number of records deleted by retention cleanup",
+            )
+            .namespace("loupe")
+            .subsystem("scheduler"),
+            &["kind"], // "run_results", "result_objects", "runs" or "run_failures"
+        )?;
+
+        let retention_last_cleanup_timestamp_seconds = IntGauge::new(
+            "loupe_retention_last_cleanup_timestamp_seconds",
+            "Unix time the last retention cleanup finished without errors",
+        )?;
+
         // Cache metrics
         let cache_requests_total = IntCounterVec::new(
             Opts::new("loupe_cache_requests_total", "Total number of cache requests")
@@ -210,6 +292,9 @@ impl Metrics {
         registry.register(Box::new(db_pool_connections_max.clone()))?;
         registry.register(Box::new(db_pool_acquire_duration_seconds.clone()))?;
         registry.register(Box::new(db_pool_acquire_timeout_total.clone()))?;
+        registry.register(Box::new(connector_cache_entries.clone()))?;
+        registry.register(Box::new(connector_cache_events_total.clone()))?;
+        registry.register(Box::new(connector_pool_connections.clone()))?;
         registry.register(Box::new(query_executions_total.clone()))?;
         registry.register(Box::new(query_execution_duration_seconds.clone()))?;
         registry.register(Box::new(query_rows_returned.clone()))?;
@@ -218,8 +303,14 @@ impl Metrics {
         registry.register(Box::new(jobs_claimed_total.clone()))?;
         registry.register(Box::new(job_processing_duration_seconds.clone()))?;
         registry.register(Box::new(job_queue_depth.clone()))?;
+        registry.register(Box::new(job_queue_depth_by_priority.clone()))?;
         registry.register(Box::new(job_retry_queue_depth.clone()))?;
         registry.register(Box::new(job_dead_letter_queue_size.clone()))?;
+        registry.register(Box::new(runs_reclaimed_total.clone()))?;
+        registry.register(Box::new(runs_throttled_total.clone()))?;
+        registry.register(Box::new(runners_active.clone()))?;
+        registry.register(Box::new(retention_deleted_total.clone()))?;
+        registry.register(Box::new(retention_last_cleanup_timestamp_seconds.clone()))?;
         registry.register(Box::new(cache_requests_total.clone()))?;
         registry.register(Box::new(cache_hit_rate.clone()))?;
 
@@ -233,6 +324,9 @@ impl Metrics {
             db_pool_connections_max,
             db_pool_acquire_duration_seconds,
             db_pool_acquire_timeout_total,
+            connector_cache_entries,
+            connector_cache_events_total,
+            connector_pool_connections,
             query_executions_total,
             query_execution_duration_seconds,
             query_rows_returned,
@@ -241,8 +335,14 @@ impl Metrics {
             jobs_claimed_total,
             job_processing_duration_seconds,
             job_queue_depth,
+            job_queue_depth_by_priority,
             job_retry_queue_depth,
             job_dead_letter_queue_size,
+            runs_reclaimed_total,
+            runs_throttled_total,
+            runners_active,
+            retention_deleted_total,
+            retention_last_cleanup_timestamp_seconds,
             cache_requests_total,
             cache_hit_rate,
         })
@@ -269,6 +369,31 @@ impl Metrics {
         self.db_pool_connections_max.set(stats.connections_max as i64);
     }
 
+    /// Update the pool gauges for a cached datasource connector
+    pub fn update_connector_pool_metrics(&self, datasource_id: Uuid, stats: &crate::db::PoolStats) {
+        let id = datasource_id.to_string();
+        for (state, value) in [
+            ("active", stats.connections_active),
+            ("idle", stats.connections_idle),
+            ("max", stats.connections_max),
+        ] {
+            self.connector_pool_connections
+                .with_label_values(&[id.as_str(), state])
+                .set(value as i64);
+        }
+    }
+
+    /// Drop the pool gauges for a datasource connector that left the cache
+    pub fn remove_connector_pool_metrics(&self, datasource_id: Uuid) {
+        let id = datasource_id.to_string();
+        for state in ["active", "idle", "max"] {
+            // Label set is absent for connectors without a pool
+            let _ = self
+                .connector_pool_connections
+                .remove_label_values(&[id.as_str(), state]);
+        }
+    }
+
     /// Normalize endpoint path for metrics (remove IDs and params)
     /// Examples:
     /// - /api/v1/dashboards/123 -> /api/v1/dashboards/:id
@@ -1,9 +1,11 @@
 pub mod cache;
 pub mod config;
+pub mod connector_cache;
 pub mod connectors;
 pub mod db;
 pub mod encryption;
 pub mod error;
+pub mod export;
 pub mod filtering;
 pub mod jwt;
 pub mod metrics;
@@ -11,16 +13,20 @@ pub mod models;
 pub mod pagination;
 pub mod params;
 pub mod query_limiter;
+pub mod result_store;
+pub mod result_view;
 pub mod secrets;
 pub mod sql_validator;
 pub mod tracing;
+pub mod uploads;
 pub mod validation;
 
 pub use cache::{CacheManager, CacheStats};
 pub use config::{init_tracing, load_env, Config, AdminConfig, ObservabilityConfig};
-pub use db::{Database, DatabaseConfig, PoolStats};
+pub use connector_cache::{ConnectorCache, ConnectorCacheConfig};
+pub use db::{Database, DatabaseConfig, PoolStats, QuerySlotAttempt, RUN_CANCELLED_CHANNEL, RUN_LEASE, RUNS_CHANNEL};
 pub use encryption::{mask_sensitive, EncryptionManager};
-pub use error::{Error, Result};
+pub use error::{Error, ErrorCode, Result};
 pub use filtering::{
     parse_tags, DateRangeParams, ListParams, SearchParams, SortParams, SortableColumns,
 };
@@ -29,8 +35,11 @@ pub use metrics::Metrics;
 pub use pagination::{PaginatedResponse, PaginationParams, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
 pub use params::{
     BoundParams, ParamSchema, TypedValue, bind_params, extract_params, substitute_params,
+    to_question_placeholders,
 };
-pub use query_limiter::{LimitError, QueryGuard, QueryLimiter, QueryLimits};
+pub use query_limiter::{LimitError, LimiterBackend, QueryGuard, QueryLimiter, QueryLimits};
+pub use result_store::{ResultBackend, ResultStore};
+pub use result_view::{FilterOp, ResultFilter, ResultView};
 pub use secrets::{redact_secret, SecretSource, SecretsManager};
 pub use sql_validator::SqlValidator;
 pub use validation::{
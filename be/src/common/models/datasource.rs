@@ -1,3 +1,5 @@
+use super::ColumnDef;
+use crate::uploads::UploadFormat;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
@@ -9,6 +11,15 @@ use validator::Validate;
 #[serde(rename_all = "lowercase")]
 pub enum DatasourceType {
     Postgres,
+    Mysql,
+    /// File-backed database, restricted to `SQLITE_ALLOWED_DIRS`
+    Sqlite,
+    /// REST/JSON endpoint; queries are JSON request specs
+    Http,
+    /// Prometheus HTTP API; queries are PromQL
+    Prometheus,
+    /// Out-of-process connector registered in `CONNECTOR_PLUGINS`
+    Plugin,
 }
 
 /// A datasource connection definition
@@ -21,6 +32,11 @@ pub struct Datasource {
     /// Encrypted connection string
     #[serde(skip_serializing)]
     pub connection_string_encrypted: String,
+    /// Bumped when the connection string changes
+    pub config_version: i32,
+    /// Maximum runs executing against this datasource at once, across all
+    /// runners (None for no limit)
+    pub max_concurrent_runs: Option<i32>,
     pub created_by: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -38,6 +54,9 @@ pub struct CreateDatasourceRequest {
 
     #[validate(custom(function = "crate::validation::validate_connection_string", message = "Invalid connection string"))]
     pub connection_string: String,
+
+    #[validate(range(min = 1, max = 1000, message = "max_concurrent_runs must be between 1 and 1000"))]
+    pub max_concurrent_runs: Option<i32>,
 }
 
 fn default_ds_type() -> DatasourceType {
@@ -52,6 +71,44 @@ pub struct UpdateDatasourceRequest {
 
     #[validate(custom(function = "crate::validation::validate_connection_string", message = "Invalid connection string"))]
     pub connection_string: Option<String>,
+
+    /// Omit to leave unchanged; `null` removes the limit
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[validate(range(min = 1, max = 1000, message = "max_concurrent_runs must be between 1 and 1000"))]
+    pub max_concurrent_runs: Option<Option<i32>>,
+}
+
+/// Distinguish an explicit `null` (Some(None)) from a missing field (None)
+pub(super) fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Query parameters for `POST /datasources/upload` (the file is the request body)
+#[derive(Debug, Deserialize, Validate)]
+pub struct UploadDatasourceParams {
+    #[validate(length(min = 1, max = 255, message = "Name must be between 1 and 255 characters"))]
+    #[validate(custom(function = "crate::validation::validate_name", message = "Name contains invalid characters"))]
+    pub name: String,
+
+    /// File format; inferred from Content-Type when omitted
+    pub format: Option<UploadFormat>,
+
+    /// Table name for the uploaded rows; derived from `name` when omitted
+    #[validate(length(min = 1, max = 63, message = "Table name must be between 1 and 63 characters"))]
+    pub table: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadDatasourceResponse {
+    #[serde(flatten)]
+    pub datasource: DatasourceResponse,
+    pub table: String,
+    pub columns: Vec<ColumnDef>,
+    pub row_count: usize,
 }
 
 #[derive(Debug, Serialize)]
@@ -60,6 +117,7 @@ pub struct DatasourceResponse {
     pub org_id: Uuid,
     pub name: String,
     pub ds_type: DatasourceType,
+    pub max_concurrent_runs: Option<i32>,
     pub created_by: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -72,6 +130,7 @@ impl From<Datasource> for DatasourceResponse {
             org_id: ds.org_id,
             name: ds.name,
             ds_type: ds.ds_type,
+            max_concurrent_runs: ds.max_concurrent_runs,
             created_by: ds.created_by,
             created_at: ds.created_at,
             updated_at: ds.updated_at,
@@ -1,6 +1,9 @@
+use super::{RetentionPolicy, RetryPolicy};
+use super::datasource::deserialize_some;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use sqlx::types::Json;
 use uuid::Uuid;
 use validator::Validate;
 
@@ -41,6 +44,12 @@ pub struct Query {
     pub max_rows: i32,
     /// JSON array of tag strings
     pub tags: serde_json::Value,
+    /// Retry policy for its runs; unset uses the default
+    pub retry_policy: Option<Json<RetryPolicy>>,
+    /// Default serialized result size limit; unset uses the org ceiling
+    pub max_bytes: Option<i64>,
+    /// Retention of its run history; unset uses the org's policy
+    pub retention_policy: Option<Json<RetentionPolicy>>,
     pub created_by: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -75,6 +84,18 @@ pub struct CreateQueryRequest {
     #[serde(default)]
     #[validate(length(max = 50, message = "Maximum 50 tags allowed"))]
     pub tags: Vec<String>,
+
+    /// Unset uses the default policy
+    #[validate(nested)]
+    pub retry_policy: Option<RetryPolicy>,
+
+    /// Unset uses the org ceiling
+    #[validate(range(min = 1, message = "Max bytes must be positive"))]
+    pub max_bytes: Option<i64>,
+
+    /// Unset uses the org's policy
+    #[validate(nested)]
+    pub retention_policy: Option<RetentionPolicy>,
 }
 
 fn default_timeout() -> i32 {
@@ -107,6 +128,21 @@ pub struct UpdateQueryRequest {
 
     #[validate(length(max = 50, message = "Maximum 50 tags allowed"))]
     pub tags: Option<Vec<String>>,
+
+    /// Omit to leave unchanged; `null` goes back to the default policy
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[validate(nested)]
+    pub retry_policy: Option<Option<RetryPolicy>>,
+
+    /// Omit to leave unchanged; `null` goes back to the org ceiling
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[validate(range(min = 1, message = "Max bytes must be positive"))]
+    pub max_bytes: Option<Option<i64>>,
+
+    /// Omit to leave unchanged; `null` goes back to the org's policy
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[validate(nested)]
+    pub retention_policy: Option<Option<RetentionPolicy>>,
 }
 
 /// Export format for a query (excludes org-specific IDs)
@@ -158,6 +194,9 @@ pub struct QueryResponse {
     pub timeout_seconds: i32,
     pub max_rows: i32,
     pub tags: Vec<String>,
+    pub retry_policy: Option<RetryPolicy>,
+    pub max_bytes: Option<i64>,
+    pub retention_policy: Option<RetentionPolicy>,
     pub created_by: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -178,6 +217,9 @@ impl From<Query> for QueryResponse {
             timeout_seconds: q.timeout_seconds,
             max_rows: q.max_rows,
             tags,
+            retry_policy: q.retry_policy.map(|policy| policy.0),
+            max_bytes: q.max_bytes,
+            retention_policy: q.retention_policy.map(|policy| policy.0),
             created_by: q.created_by,
             created_at: q.created_at,
             updated_at: q.updated_at,
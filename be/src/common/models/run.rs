@@ -1,7 +1,12 @@
+use super::{Query, Schedule};
+use crate::pagination::PaginatedResponse;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use sqlx::types::Json;
+use std::time::Duration;
 use uuid::Uuid;
+use validator::Validate;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "text", rename_all = "lowercase")]
@@ -15,6 +20,141 @@ pub enum RunStatus {
     Timeout,
 }
 
+/// Scheduling lane of a run; runners claim higher lanes first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum RunPriority {
+    /// Someone is waiting on it in the editor
+    Interactive,
+    /// Dashboard tile refresh
+    Dashboard,
+    /// Triggered by a schedule
+    Scheduled,
+    /// Bulk reprocessing that can wait for everything else
+    Backfill,
+}
+
+impl RunPriority {
+    pub const ALL: [RunPriority; 4] = [
+        RunPriority::Interactive,
+        RunPriority::Dashboard,
+        RunPriority::Scheduled,
+        RunPriority::Backfill,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RunPriority::Interactive => "interactive",
+            RunPriority::Dashboard => "dashboard",
+            RunPriority::Scheduled => "scheduled",
+            RunPriority::Backfill => "backfill",
+        }
+    }
+}
+
+/// Kind of failure a retry policy can retry (see `connectors::retry_class`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RetryClass {
+    /// The query ran past its timeout
+    Timeout,
+    /// The datasource couldn't be reached or dropped the connection
+    Connection,
+    /// Deadlocks, lock and serialization conflicts, overload
+    Transient,
+}
+
+/// How failed runs are retried
+///
+/// Set on a query, optionally overridden by a schedule, and copied onto
+/// each run when it's created. Missing fields take their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt
+    #[validate(range(min = 0, max = 10, message = "max_retries must be between 0 and 10"))]
+    pub max_retries: i32,
+    /// Delay before the first retry; doubles for each one after
+    #[validate(range(min = 1, max = 3600, message = "base_delay_seconds must be between 1 and 3600"))]
+    pub base_delay_seconds: i32,
+    /// Longest delay between retries
+    #[validate(range(min = 1, max = 86400, message = "max_delay_seconds must be between 1 and 86400"))]
+    pub max_delay_seconds: i32,
+    /// Fraction of each delay taken off at random, so runs that failed
+    /// together don't all retry together
+    #[validate(range(min = 0.0, max = 1.0, message = "jitter must be between 0 and 1"))]
+    pub jitter: f64,
+    /// Failures that are retried; others fail the run straight away
+    pub retry_on: Vec<RetryClass>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay_seconds: 30,
+            max_delay_seconds: 3600,
+            jitter: 0.0,
+            retry_on: vec![RetryClass::Timeout, RetryClass::Connection, RetryClass::Transient],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The policy for a run of `query`, or of `schedule` if it has its own
+    pub fn resolve(query: &Query, schedule: Option<&Schedule>) -> Self {
+        schedule
+            .and_then(|s| s.retry_policy.as_ref())
+            .or(query.retry_policy.as_ref())
+            .map(|policy| policy.0.clone())
+            .unwrap_or_default()
+    }
+
+    /// Delay before the next retry, before jitter, after `retry_count`
+    /// retries so far
+    pub fn backoff(&self, retry_count: i32) -> Duration {
+        let base = f64::from(self.base_delay_seconds) * 2f64.powi(retry_count.clamp(0, 30));
+        Duration::from_secs_f64(base.min(f64::from(self.max_delay_seconds)))
+    }
+
+    pub fn retries(&self, class: RetryClass) -> bool {
+        self.retry_on.contains(&class)
+    }
+}
+
+/// Days run history is kept when no policy says otherwise
+pub const DEFAULT_RETENTION_DAYS: i32 = 7;
+
+/// How long a query's results, runs and dead-lettered failures are kept
+///
+/// Set on an org, optionally overridden by a query. Each result is given its
+/// `expires_at` from the policy when it's stored; the scheduler's cleanup
+/// deletes what has expired. Missing fields take their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Days results, finished runs and run failures are kept
+    #[validate(range(min = 1, max = 3650, message = "keep_days must be between 1 and 3650"))]
+    pub keep_days: i32,
+    /// Most results kept per query; older ones go before they expire.
+    /// Unset keeps every result until it expires
+    #[validate(range(min = 1, max = 10_000, message = "keep_last must be between 1 and 10,000"))]
+    pub keep_last: Option<i32>,
+    /// Never delete the latest result of a query shown on a dashboard
+    pub keep_dashboard_results: bool,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_days: DEFAULT_RETENTION_DAYS,
+            keep_last: None,
+            keep_dashboard_results: true,
+        }
+    }
+}
+
 /// An execution instance of a query
 #[derive(Debug, Clone, Serialize, FromRow)]
 pub struct Run {
@@ -42,6 +182,17 @@ pub struct Run {
     pub max_retries: i32,
     /// Timestamp when this run is eligible for retry
     pub next_retry_at: Option<DateTime<Utc>>,
+    /// When the claiming runner's lease lapses unless renewed by a heartbeat
+    pub lease_expires_at: Option<DateTime<Utc>>,
+    /// Queued run is not claimed before this time (query limiter was full)
+    pub throttled_until: Option<DateTime<Utc>>,
+    /// When the query limiter first turned this run away
+    pub first_throttled_at: Option<DateTime<Utc>>,
+    pub priority: RunPriority,
+    /// Retry policy in effect for this run
+    pub retry_policy: Json<RetryPolicy>,
+    /// Serialized result size limit; unset uses the runner-wide limit
+    pub max_bytes: Option<i64>,
 }
 
 /// A run that exceeded max retries and was moved to the dead letter queue
@@ -61,6 +212,13 @@ pub struct RunFailure {
     pub last_failed_at: DateTime<Utc>,
     pub created_by: Uuid,
     pub created_at: DateTime<Utc>,
+    /// Limits of the failed run (unset for failures recorded before these
+    /// were kept)
+    pub timeout_seconds: Option<i32>,
+    pub max_rows: Option<i32>,
+    /// Run created by the latest replay
+    pub replayed_run_id: Option<Uuid>,
+    pub replayed_at: Option<DateTime<Utc>>,
 }
 
 /// The result of a completed run
@@ -70,14 +228,48 @@ pub struct RunResult {
     pub run_id: Uuid,
     /// JSON array of column definitions
     pub columns: serde_json::Value,
-    /// JSON array of row arrays
-    pub rows: serde_json::Value,
+    /// JSON array of row arrays; unset when the rows are in object storage
+    /// (read results through `ResultStore` to get them either way)
+    pub rows: Option<serde_json::Value>,
     pub row_count: i64,
     pub byte_count: i64,
     pub execution_time_ms: i64,
     pub created_at: DateTime<Utc>,
     /// TTL for cleanup
     pub expires_at: Option<DateTime<Utc>>,
+    /// Whether rows were left out because a limit was reached
+    pub truncated: bool,
+    pub truncation_reason: Option<TruncationReason>,
+    /// Object storage key of the rows, when they aren't stored inline
+    pub storage_key: Option<String>,
+    /// How the rows are serialized, inline in `data` or in object storage
+    pub encoding: ResultEncoding,
+    /// Encoded rows stored inline, for encodings other than JSON
+    #[serde(skip)]
+    pub data: Option<Vec<u8>>,
+}
+
+/// Limit that cut a run's result short
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationReason {
+    /// The query returned more than the run's `max_rows`
+    MaxRows,
+    /// The serialized rows outgrew the run's `max_bytes`
+    MaxBytes,
+}
+
+/// How a result's rows are serialized for storage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ResultEncoding {
+    /// JSON array of row arrays, stored as JSONB
+    #[default]
+    Json,
+    /// JSON array of column arrays, zstd-compressed
+    ColumnarZstd,
 }
 
 // DTOs
@@ -88,6 +280,10 @@ pub struct CreateRunRequest {
     pub parameters: serde_json::Value,
     pub timeout_seconds: Option<i32>,
     pub max_rows: Option<i32>,
+    /// Defaults to the query's limit, capped by the org ceiling
+    pub max_bytes: Option<i64>,
+    /// Defaults to interactive
+    pub priority: Option<RunPriority>,
 }
 
 /// Request to execute ad-hoc SQL (creates ephemeral query + run)
@@ -101,6 +297,8 @@ pub struct ExecuteAdHocRequest {
     pub timeout_seconds: i32,
     #[serde(default = "default_max_rows")]
     pub max_rows: i32,
+    /// Capped by the org ceiling
+    pub max_bytes: Option<i64>,
 }
 
 fn default_timeout() -> i32 {
@@ -116,9 +314,13 @@ pub struct RunResponse {
     pub id: Uuid,
     pub query_id: Uuid,
     pub status: RunStatus,
+    pub priority: RunPriority,
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub error_message: Option<String>,
+    pub retry_count: i32,
+    pub next_retry_at: Option<DateTime<Utc>>,
+    pub retry_policy: RetryPolicy,
     pub created_at: DateTime<Utc>,
 }
 
@@ -128,15 +330,84 @@ impl From<Run> for RunResponse {
             id: r.id,
             query_id: r.query_id,
             status: r.status,
+            priority: r.priority,
             started_at: r.started_at,
             completed_at: r.completed_at,
             error_message: r.error_message,
+            retry_count: r.retry_count,
+            next_retry_at: r.next_retry_at,
+            retry_policy: r.retry_policy.0,
             created_at: r.created_at,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize)]
+pub struct RunFailureResponse {
+    pub id: Uuid,
+    pub run_id: Uuid,
+    pub query_id: Uuid,
+    pub datasource_id: Uuid,
+    pub executed_sql: String,
+    pub parameters: serde_json::Value,
+    pub error_message: String,
+    pub retry_count: i32,
+    pub max_retries: i32,
+    pub first_failed_at: DateTime<Utc>,
+    pub last_failed_at: DateTime<Utc>,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub replayed_run_id: Option<Uuid>,
+    pub replayed_at: Option<DateTime<Utc>>,
+}
+
+impl From<RunFailure> for RunFailureResponse {
+    fn from(f: RunFailure) -> Self {
+        Self {
+            id: f.id,
+            run_id: f.run_id,
+            query_id: f.query_id,
+            datasource_id: f.datasource_id,
+            executed_sql: f.executed_sql,
+            parameters: f.parameters,
+            error_message: f.error_message,
+            retry_count: f.retry_count,
+            max_retries: f.max_retries,
+            first_failed_at: f.first_failed_at,
+            last_failed_at: f.last_failed_at,
+            created_by: f.created_by,
+            created_at: f.created_at,
+            replayed_run_id: f.replayed_run_id,
+            replayed_at: f.replayed_at,
+        }
+    }
+}
+
+/// Which dead-lettered runs to purge; every given criterion must match
+#[derive(Debug, Default, Deserialize)]
+pub struct PurgeRunFailuresRequest {
+    pub ids: Option<Vec<Uuid>>,
+    pub query_id: Option<Uuid>,
+    pub datasource_id: Option<Uuid>,
+    /// Only failures dead-lettered before this time
+    pub before: Option<DateTime<Utc>>,
+}
+
+impl PurgeRunFailuresRequest {
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_none()
+            && self.query_id.is_none()
+            && self.datasource_id.is_none()
+            && self.before.is_none()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PurgeRunFailuresResponse {
+    pub purged: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColumnDef {
     pub name: String,
     pub data_type: String,
@@ -149,6 +420,8 @@ pub struct RunResultResponse {
     pub rows: Vec<Vec<serde_json::Value>>,
     pub row_count: i64,
     pub execution_time_ms: i64,
+    pub truncated: bool,
+    pub truncation_reason: Option<TruncationReason>,
 }
 
 impl From<RunResult> for RunResultResponse {
@@ -157,16 +430,115 @@ impl From<RunResult> for RunResultResponse {
             tracing::error!("Failed to deserialize columns for run {}: {}", r.run_id, e);
             vec![]
         });
-        let rows = serde_json::from_value(r.rows.clone()).unwrap_or_else(|e| {
-            tracing::error!("Failed to deserialize rows for run {}: {}", r.run_id, e);
+        let rows = match r.rows {
+            Some(rows) => serde_json::from_value(rows).unwrap_or_else(|e| {
+                tracing::error!("Failed to deserialize rows for run {}: {}", r.run_id, e);
+                vec![]
+            }),
+            None => {
+                tracing::error!("Rows for run {} were not loaded from object storage", r.run_id);
+                vec![]
+            }
+        };
+        Self {
+            run_id: r.run_id,
+            columns,
+            rows,
+            row_count: r.row_count,
+            execution_time_ms: r.execution_time_ms,
+            truncated: r.truncated,
+            truncation_reason: r.truncation_reason,
+        }
+    }
+}
+
+/// Query parameters for `GET /runs/{id}/result`
+///
+/// Any of the paging, sorting, filtering or projection parameters turns the
+/// response into a `RunResultPageResponse`; see `ResultView`.
+#[derive(Debug, Default, Deserialize)]
+pub struct RunResultParams {
+    #[serde(default)]
+    pub format: ResultLayout,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    /// Result column to sort rows by
+    pub sort_by: Option<String>,
+    /// "asc" (default) or "desc"
+    pub sort_direction: Option<String>,
+    /// Comma-separated result columns to return, in order
+    pub columns: Option<String>,
+    /// JSON array of `ResultFilter`s, all of which a row must match
+    pub filters: Option<String>,
+}
+
+impl RunResultParams {
+    /// Whether a page of the result was asked for rather than all of it
+    pub fn is_paged(&self) -> bool {
+        self.limit.is_some()
+            || self.offset.is_some()
+            || self.sort_by.is_some()
+            || self.sort_direction.is_some()
+            || self.columns.is_some()
+            || self.filters.is_some()
+    }
+}
+
+/// Shape of a result response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultLayout {
+    /// `RunResultResponse`: one array per row
+    #[default]
+    Rows,
+    /// `ColumnarRunResultResponse`: one array per column
+    Columnar,
+}
+
+/// A result with its values grouped by column, which is smaller and faster
+/// to chart for wide time-series than `RunResultResponse`
+#[derive(Debug, Serialize)]
+pub struct ColumnarRunResultResponse {
+    pub run_id: Uuid,
+    pub columns: Vec<ColumnDef>,
+    /// One array per column, each `row_count` long
+    pub data: Vec<Vec<serde_json::Value>>,
+    pub row_count: i64,
+    pub execution_time_ms: i64,
+    pub truncated: bool,
+    pub truncation_reason: Option<TruncationReason>,
+}
+
+impl ColumnarRunResultResponse {
+    pub fn new(r: RunResult, data: Vec<Vec<serde_json::Value>>) -> Self {
+        let columns = serde_json::from_value(r.columns).unwrap_or_else(|e| {
+            tracing::error!("Failed to deserialize columns for run {}: {}", r.run_id, e);
             vec![]
         });
         Self {
             run_id: r.run_id,
             columns,
-            rows,
+            data,
             row_count: r.row_count,
             execution_time_ms: r.execution_time_ms,
+            truncated: r.truncated,
+            truncation_reason: r.truncation_reason,
         }
     }
 }
+
+/// One page of a result's rows, after filtering and sorting
+#[derive(Debug, Serialize)]
+pub struct RunResultPageResponse {
+    pub run_id: Uuid,
+    /// The returned columns, in order
+    pub columns: Vec<ColumnDef>,
+    /// Rows on this page; `total` counts the rows matching the filters
+    #[serde(flatten)]
+    pub rows: PaginatedResponse<Vec<serde_json::Value>>,
+    /// Rows in the whole stored result
+    pub row_count: i64,
+    pub execution_time_ms: i64,
+    pub truncated: bool,
+    pub truncation_reason: Option<TruncationReason>,
+}
@@ -1,6 +1,9 @@
+use super::RetryPolicy;
+use super::datasource::deserialize_some;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use sqlx::types::Json;
 use uuid::Uuid;
 use validator::Validate;
 
@@ -20,6 +23,8 @@ pub struct Schedule {
     pub enabled: bool,
     pub last_run_at: Option<DateTime<Utc>>,
     pub next_run_at: Option<DateTime<Utc>>,
+    /// Retry policy for its runs; unset uses the default
+    pub retry_policy: Option<Json<RetryPolicy>>,
     pub created_by: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -47,6 +52,10 @@ pub struct CreateScheduleRequest {
 
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+
+    /// Unset uses the query's policy
+    #[validate(nested)]
+    pub retry_policy: Option<RetryPolicy>,
 }
 
 fn default_enabled() -> bool {
@@ -69,6 +78,11 @@ pub struct UpdateScheduleRequest {
     pub tags: Option<Vec<String>>,
 
     pub enabled: Option<bool>,
+
+    /// Omit to leave unchanged; `null` goes back to the query's policy
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[validate(nested)]
+    pub retry_policy: Option<Option<RetryPolicy>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -83,6 +97,7 @@ pub struct ScheduleResponse {
     pub enabled: bool,
     pub last_run_at: Option<DateTime<Utc>>,
     pub next_run_at: Option<DateTime<Utc>>,
+    pub retry_policy: Option<RetryPolicy>,
     pub created_by: Uuid,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -102,6 +117,7 @@ impl From<Schedule> for ScheduleResponse {
             enabled: s.enabled,
             last_run_at: s.last_run_at,
             next_run_at: s.next_run_at,
+            retry_policy: s.retry_policy.map(|policy| policy.0),
             created_by: s.created_by,
             created_at: s.created_at,
             updated_at: s.updated_at,
@@ -1,9 +1,12 @@
 //! Unit tests for models
 
 mod user_tests {
-    use crate::models::{OrgRole, User, UserResponse};
+    use crate::models::{
+        OrgRole, Organization, UpdateOrganizationSettingsRequest, User, UserResponse,
+    };
     use chrono::Utc;
     use uuid::Uuid;
+    use validator::Validate;
 
     #[test]
     fn test_org_role_default() {
@@ -65,10 +68,51 @@ mod user_tests {
             OrgRole::Viewer
         );
     }
+
+    #[test]
+    fn test_org_caps_result_bytes() {
+        let mut org = Organization {
+            id: Uuid::new_v4(),
+            name: "Org".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            max_result_bytes: None,
+            retention_policy: None,
+        };
+        assert_eq!(org.cap_result_bytes(None), None);
+        assert_eq!(org.cap_result_bytes(Some(5000)), Some(5000));
+
+        org.max_result_bytes = Some(1000);
+        assert_eq!(org.cap_result_bytes(None), Some(1000));
+        assert_eq!(org.cap_result_bytes(Some(500)), Some(500));
+        assert_eq!(org.cap_result_bytes(Some(5000)), Some(1000));
+    }
+
+    #[test]
+    fn test_update_organization_settings_request() {
+        let req: UpdateOrganizationSettingsRequest = serde_json::from_str("{}").unwrap();
+        assert_eq!(req.max_result_bytes, None);
+
+        let req: UpdateOrganizationSettingsRequest =
+            serde_json::from_str(r#"{"max_result_bytes": null}"#).unwrap();
+        assert_eq!(req.max_result_bytes, Some(None));
+        assert!(req.validate().is_ok());
+
+        let req: UpdateOrganizationSettingsRequest =
+            serde_json::from_str(r#"{"max_result_bytes": 0}"#).unwrap();
+        assert!(req.validate().is_err());
+
+        let req: UpdateOrganizationSettingsRequest =
+            serde_json::from_str(r#"{"retention_policy": {"keep_days": 5000}}"#).unwrap();
+        assert!(req.validate().is_err());
+    }
 }
 
 mod datasource_tests {
-    use crate::models::{CreateDatasourceRequest, Datasource, DatasourceResponse, DatasourceType};
+    use crate::models::{
+        CreateDatasourceRequest, Datasource, DatasourceResponse, DatasourceType,
+        UpdateDatasourceRequest,
+    };
     use chrono::Utc;
     use uuid::Uuid;
 
@@ -78,6 +122,26 @@ mod datasource_tests {
             serde_json::to_string(&DatasourceType::Postgres).unwrap(),
             r#""postgres""#
         );
+        assert_eq!(
+            serde_json::to_string(&DatasourceType::Mysql).unwrap(),
+            r#""mysql""#
+        );
+        assert_eq!(
+            serde_json::to_string(&DatasourceType::Sqlite).unwrap(),
+            r#""sqlite""#
+        );
+        assert_eq!(
+            serde_json::to_string(&DatasourceType::Http).unwrap(),
+            r#""http""#
+        );
+        assert_eq!(
+            serde_json::to_string(&DatasourceType::Prometheus).unwrap(),
+            r#""prometheus""#
+        );
+        assert_eq!(
+            serde_json::to_string(&DatasourceType::Plugin).unwrap(),
+            r#""plugin""#
+        );
     }
 
     #[test]
@@ -91,6 +155,15 @@ mod datasource_tests {
         assert_eq!(req.ds_type, DatasourceType::Postgres);
     }
 
+    #[test]
+    fn test_update_datasource_request_concurrency_limit() {
+        let parse = |json: &str| -> UpdateDatasourceRequest { serde_json::from_str(json).unwrap() };
+
+        assert_eq!(parse(r#"{}"#).max_concurrent_runs, None);
+        assert_eq!(parse(r#"{"max_concurrent_runs": null}"#).max_concurrent_runs, Some(None));
+        assert_eq!(parse(r#"{"max_concurrent_runs": 4}"#).max_concurrent_runs, Some(Some(4)));
+    }
+
     #[test]
     fn test_datasource_response_excludes_connection_string() {
         let ds = Datasource {
@@ -99,6 +172,8 @@ mod datasource_tests {
             name: "Test DS".to_string(),
             ds_type: DatasourceType::Postgres,
             connection_string_encrypted: "secret_connection".to_string(),
+            config_version: 1,
+            max_concurrent_runs: None,
             created_by: Uuid::new_v4(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -185,6 +260,9 @@ mod query_tests {
             tags: serde_json::json!([]),
             timeout_seconds: 30,
             max_rows: 1000,
+            retry_policy: None,
+            max_bytes: None,
+            retention_policy: None,
             created_by: Uuid::new_v4(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -197,9 +275,17 @@ mod query_tests {
 }
 
 mod run_tests {
-    use crate::models::{ColumnDef, ExecuteAdHocRequest, RunResult, RunResultResponse, RunStatus};
+    use crate::models::{
+        ColumnDef, ColumnarRunResultResponse, CreateQueryRequest, CreateRunRequest,
+        ExecuteAdHocRequest, PurgeRunFailuresRequest, ResultEncoding, ResultLayout,
+        RetentionPolicy, RetryClass, RetryPolicy, RunPriority, RunResult, RunResultParams,
+        RunResultPageResponse, RunResultResponse, RunStatus, TruncationReason, UpdateQueryRequest,
+    };
+    use crate::pagination::{PaginatedResponse, PaginationParams};
     use chrono::Utc;
+    use std::time::Duration;
     use uuid::Uuid;
+    use validator::Validate;
 
     #[test]
     fn test_run_status_serialization() {
@@ -229,6 +315,27 @@ mod run_tests {
         );
     }
 
+    #[test]
+    fn test_run_priority_serialization() {
+        for priority in RunPriority::ALL {
+            assert_eq!(
+                serde_json::to_string(&priority).unwrap(),
+                format!(r#""{}""#, priority.as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn test_create_run_request_priority() {
+        let json = r#"{"query_id": "00000000-0000-0000-0000-000000000001"}"#;
+        let req: CreateRunRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(req.priority, None);
+
+        let json = r#"{"query_id": "00000000-0000-0000-0000-000000000001", "priority": "dashboard"}"#;
+        let req: CreateRunRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(req.priority, Some(RunPriority::Dashboard));
+    }
+
     #[test]
     fn test_execute_adhoc_request_defaults() {
         let json = r#"{
@@ -262,12 +369,17 @@ mod run_tests {
                 {"name": "id", "data_type": "INT8"},
                 {"name": "name", "data_type": "TEXT"}
             ]),
-            rows: serde_json::json!([[1, "Alice"], [2, "Bob"]]),
+            rows: Some(serde_json::json!([[1, "Alice"], [2, "Bob"]])),
             row_count: 2,
             byte_count: 100,
             execution_time_ms: 50,
             created_at: Utc::now(),
             expires_at: None,
+            truncated: true,
+            truncation_reason: Some(TruncationReason::MaxRows),
+            storage_key: None,
+            encoding: ResultEncoding::Json,
+            data: None,
         };
 
         let response = RunResultResponse::from(result);
@@ -275,6 +387,224 @@ mod run_tests {
         assert_eq!(response.columns.len(), 2);
         assert_eq!(response.rows.len(), 2);
         assert_eq!(response.execution_time_ms, 50);
+        assert!(response.truncated);
+        assert_eq!(response.truncation_reason, Some(TruncationReason::MaxRows));
+    }
+
+    #[test]
+    fn test_columnar_run_result_response() {
+        let result = RunResult {
+            id: Uuid::new_v4(),
+            run_id: Uuid::new_v4(),
+            columns: serde_json::json!([
+                {"name": "id", "data_type": "INT8"},
+                {"name": "name", "data_type": "TEXT"}
+            ]),
+            rows: None,
+            row_count: 2,
+            byte_count: 40,
+            execution_time_ms: 50,
+            created_at: Utc::now(),
+            expires_at: None,
+            truncated: false,
+            truncation_reason: None,
+            storage_key: None,
+            encoding: ResultEncoding::ColumnarZstd,
+            data: Some(vec![0; 40]),
+        };
+        let data = vec![
+            vec![serde_json::json!(1), serde_json::json!(2)],
+            vec![serde_json::json!("Alice"), serde_json::json!("Bob")],
+        ];
+
+        let response = ColumnarRunResultResponse::new(result, data);
+        assert_eq!(response.columns.len(), 2);
+        assert_eq!(response.data[1], vec![serde_json::json!("Alice"), serde_json::json!("Bob")]);
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert!(json.get("rows").is_none());
+        assert_eq!(json["data"][0], serde_json::json!([1, 2]));
+    }
+
+    #[test]
+    fn test_run_result_params_default_to_rows() {
+        let params: RunResultParams = serde_json::from_str("{}").unwrap();
+        assert_eq!(params.format, ResultLayout::Rows);
+
+        let params: RunResultParams = serde_json::from_str(r#"{"format": "columnar"}"#).unwrap();
+        assert_eq!(params.format, ResultLayout::Columnar);
+        assert!(!params.is_paged());
+    }
+
+    #[test]
+    fn test_run_result_params_is_paged() {
+        for json in [
+            r#"{"limit": 10}"#,
+            r#"{"offset": 10}"#,
+            r#"{"sort_by": "id"}"#,
+            r#"{"columns": "id,name"}"#,
+            r#"{"filters": "[]"}"#,
+        ] {
+            let params: RunResultParams = serde_json::from_str(json).unwrap();
+            assert!(params.is_paged(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_run_result_page_response_flattens_pagination() {
+        let response = RunResultPageResponse {
+            run_id: Uuid::new_v4(),
+            columns: vec![ColumnDef { name: "id".to_string(), data_type: "INT4".to_string() }],
+            rows: PaginatedResponse::new(
+                vec![vec![serde_json::json!(3)]],
+                5,
+                &PaginationParams { limit: 2, offset: 2 },
+            ),
+            row_count: 5,
+            execution_time_ms: 12,
+            truncated: false,
+            truncation_reason: None,
+        };
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["data"], serde_json::json!([[3]]));
+        assert_eq!(json["total"], 5);
+        assert_eq!(json["page"], 2);
+        assert_eq!(json["has_next"], true);
+        assert_eq!(json["row_count"], 5);
+        assert!(json.get("rows").is_none());
+    }
+
+    #[test]
+    fn test_result_encoding_serialization() {
+        assert_eq!(serde_json::to_string(&ResultEncoding::Json).unwrap(), r#""json""#);
+        assert_eq!(
+            serde_json::to_string(&ResultEncoding::ColumnarZstd).unwrap(),
+            r#""columnar_zstd""#
+        );
+    }
+
+    #[test]
+    fn test_truncation_reason_serialization() {
+        assert_eq!(serde_json::to_string(&TruncationReason::MaxRows).unwrap(), r#""max_rows""#);
+        assert_eq!(serde_json::to_string(&TruncationReason::MaxBytes).unwrap(), r#""max_bytes""#);
+    }
+
+    #[test]
+    fn test_query_max_bytes_validation() {
+        let json = r#"{
+            "datasource_id": "550e8400-e29b-41d4-a716-446655440000",
+            "name": "Q",
+            "sql": "SELECT 1",
+            "max_bytes": 0
+        }"#;
+        let req: CreateQueryRequest = serde_json::from_str(json).unwrap();
+        assert!(req.validate().unwrap_err().to_string().contains("max_bytes"));
+
+        let req: UpdateQueryRequest = serde_json::from_str(r#"{"max_bytes": null}"#).unwrap();
+        assert_eq!(req.max_bytes, Some(None));
+        let req: UpdateQueryRequest = serde_json::from_str(r#"{"max_bytes": 1048576}"#).unwrap();
+        assert_eq!(req.max_bytes, Some(Some(1_048_576)));
+        assert!(req.validate().is_ok());
+    }
+
+    #[test]
+    fn test_retry_policy_fills_missing_fields() {
+        let policy: RetryPolicy = serde_json::from_str("{}").unwrap();
+        assert_eq!(policy, RetryPolicy::default());
+
+        let policy: RetryPolicy =
+            serde_json::from_str(r#"{"max_retries": 1, "retry_on": ["timeout"]}"#).unwrap();
+        assert_eq!(policy.max_retries, 1);
+        assert_eq!(policy.base_delay_seconds, 30);
+        assert!(policy.retries(RetryClass::Timeout));
+        assert!(!policy.retries(RetryClass::Connection));
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy {
+            base_delay_seconds: 10,
+            max_delay_seconds: 60,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.backoff(0), Duration::from_secs(10));
+        assert_eq!(policy.backoff(1), Duration::from_secs(20));
+        assert_eq!(policy.backoff(2), Duration::from_secs(40));
+        assert_eq!(policy.backoff(3), Duration::from_secs(60));
+        assert_eq!(policy.backoff(100), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_retry_policy_validation() {
+        let json = r#"{
+            "datasource_id": "00000000-0000-0000-0000-000000000001",
+            "name": "Q",
+            "sql": "SELECT 1",
+            "retry_policy": {"max_retries": 50, "jitter": 2.0}
+        }"#;
+        let req: CreateQueryRequest = serde_json::from_str(json).unwrap();
+        let errors = req.validate().unwrap_err().to_string();
+        assert!(errors.contains("max_retries"));
+        assert!(errors.contains("jitter"));
+    }
+
+    #[test]
+    fn test_update_retry_policy_null_vs_missing() {
+        let req: UpdateQueryRequest = serde_json::from_str("{}").unwrap();
+        assert_eq!(req.retry_policy, None);
+
+        let req: UpdateQueryRequest = serde_json::from_str(r#"{"retry_policy": null}"#).unwrap();
+        assert_eq!(req.retry_policy, Some(None));
+
+        let req: UpdateQueryRequest =
+            serde_json::from_str(r#"{"retry_policy": {"max_retries": 0}}"#).unwrap();
+        assert_eq!(req.retry_policy.unwrap().unwrap().max_retries, 0);
+    }
+
+    #[test]
+    fn test_retention_policy_fills_missing_fields() {
+        let policy: RetentionPolicy = serde_json::from_str("{}").unwrap();
+        assert_eq!(policy, RetentionPolicy::default());
+        assert_eq!(policy.keep_days, 7);
+        assert!(policy.keep_dashboard_results);
+
+        let policy: RetentionPolicy = serde_json::from_str(r#"{"keep_last": 5}"#).unwrap();
+        assert_eq!(policy.keep_last, Some(5));
+        assert_eq!(policy.keep_days, 7);
+    }
+
+    #[test]
+    fn test_retention_policy_validation() {
+        let json = r#"{
+            "datasource_id": "00000000-0000-0000-0000-000000000001",
+            "name": "Q",
+            "sql": "SELECT 1",
+            "retention_policy": {"keep_days": 0, "keep_last": 0}
+        }"#;
+        let req: CreateQueryRequest = serde_json::from_str(json).unwrap();
+        let errors = req.validate().unwrap_err().to_string();
+        assert!(errors.contains("keep_days"));
+        assert!(errors.contains("keep_last"));
+
+        let req: UpdateQueryRequest = serde_json::from_str(r#"{"retention_policy": null}"#).unwrap();
+        assert_eq!(req.retention_policy, Some(None));
+    }
+
+    #[test]
+    fn test_purge_run_failures_requires_criteria() {
+        let req: PurgeRunFailuresRequest = serde_json::from_str("{}").unwrap();
+        assert!(req.is_empty());
+
+        let req: PurgeRunFailuresRequest =
+            serde_json::from_str(r#"{"before": "2026-01-01T00:00:00Z"}"#).unwrap();
+        assert!(!req.is_empty());
+
+        let req: PurgeRunFailuresRequest = serde_json::from_str(
+            r#"{"ids": ["550e8400-e29b-41d4-a716-446655440000"]}"#,
+        )
+        .unwrap();
+        assert!(!req.is_empty());
     }
 }
 
@@ -354,8 +684,9 @@ mod dashboard_tests {
 }
 
 mod schedule_tests {
-    use crate::models::{CreateScheduleRequest, Schedule, ScheduleResponse};
+    use crate::models::{CreateScheduleRequest, Query, RetryPolicy, Schedule, ScheduleResponse};
     use chrono::Utc;
+    use sqlx::types::Json;
     use uuid::Uuid;
 
     #[test]
@@ -384,6 +715,7 @@ mod schedule_tests {
             enabled: true,
             last_run_at: None,
             next_run_at: Some(Utc::now()),
+            retry_policy: None,
             created_by: Uuid::new_v4(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -393,4 +725,56 @@ mod schedule_tests {
         assert_eq!(response.cron_expression, "0 * * * *");
         assert!(response.enabled);
     }
+
+    #[test]
+    fn test_schedule_retry_policy_overrides_query() {
+        let query_policy = RetryPolicy {
+            max_retries: 5,
+            ..RetryPolicy::default()
+        };
+        let query = Query {
+            id: Uuid::new_v4(),
+            org_id: Uuid::new_v4(),
+            datasource_id: Uuid::new_v4(),
+            name: "Q".to_string(),
+            description: None,
+            sql: "SELECT 1".to_string(),
+            parameters: serde_json::json!([]),
+            tags: serde_json::json!([]),
+            timeout_seconds: 30,
+            max_rows: 1000,
+            retry_policy: Some(Json(query_policy.clone())),
+            max_bytes: None,
+            retention_policy: None,
+            created_by: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let mut schedule = Schedule {
+            id: Uuid::new_v4(),
+            org_id: query.org_id,
+            query_id: query.id,
+            name: "Hourly".to_string(),
+            cron_expression: "0 * * * *".to_string(),
+            parameters: serde_json::json!({}),
+            tags: serde_json::json!([]),
+            enabled: true,
+            last_run_at: None,
+            next_run_at: None,
+            retry_policy: None,
+            created_by: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        assert_eq!(RetryPolicy::resolve(&query, None), query_policy);
+        assert_eq!(RetryPolicy::resolve(&query, Some(&schedule)), query_policy);
+
+        let schedule_policy = RetryPolicy {
+            max_retries: 0,
+            ..RetryPolicy::default()
+        };
+        schedule.retry_policy = Some(Json(schedule_policy.clone()));
+        assert_eq!(RetryPolicy::resolve(&query, Some(&schedule)), schedule_policy);
+    }
 }
@@ -1,6 +1,9 @@
+use super::RetentionPolicy;
+use super::datasource::deserialize_some;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use sqlx::types::Json;
 use uuid::Uuid;
 use validator::Validate;
 
@@ -38,6 +41,49 @@ pub struct Organization {
     pub name: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Ceiling on the serialized size of any run result in the org
+    pub max_result_bytes: Option<i64>,
+    /// Retention of run history; unset uses the default
+    pub retention_policy: Option<Json<RetentionPolicy>>,
+}
+
+impl Organization {
+    /// Result size limit for a run, given the requested (or query default)
+    /// limit; the org ceiling wins when lower or when none was requested
+    pub fn cap_result_bytes(&self, requested: Option<i64>) -> Option<i64> {
+        match (requested, self.max_result_bytes) {
+            (Some(requested), Some(ceiling)) => Some(requested.min(ceiling)),
+            (requested, ceiling) => requested.or(ceiling),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrganizationSettingsResponse {
+    pub max_result_bytes: Option<i64>,
+    pub retention_policy: Option<RetentionPolicy>,
+}
+
+impl From<Organization> for OrganizationSettingsResponse {
+    fn from(o: Organization) -> Self {
+        Self {
+            max_result_bytes: o.max_result_bytes,
+            retention_policy: o.retention_policy.map(|policy| policy.0),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateOrganizationSettingsRequest {
+    /// Omit to leave unchanged; `null` removes the ceiling
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[validate(range(min = 1, message = "Max result bytes must be positive"))]
+    pub max_result_bytes: Option<Option<i64>>,
+
+    /// Omit to leave unchanged; `null` goes back to the default policy
+    #[serde(default, deserialize_with = "deserialize_some")]
+    #[validate(nested)]
+    pub retention_policy: Option<Option<RetentionPolicy>>,
 }
 
 // DTOs with validation
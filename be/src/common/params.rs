@@ -122,6 +122,48 @@ impl TypedValue {
             TypedValue::Null => "NULL".to_string(),
         }
     }
+
+    /// Format as unquoted text for non-SQL protocols (URLs, query strings).
+    /// Callers are responsible for any escaping the target format needs.
+    pub fn to_plain_string(&self) -> String {
+        match self {
+            TypedValue::String(s) => s.clone(),
+            TypedValue::Number(n) => n.to_string(),
+            TypedValue::Integer(i) => i.to_string(),
+            TypedValue::Boolean(b) => b.to_string(),
+            TypedValue::Date(d) => d.to_string(),
+            TypedValue::DateTime(dt) => dt.to_rfc3339(),
+            TypedValue::Null => String::new(),
+        }
+    }
+
+    /// Convert to the tagged `{"type": .., "value": ..}` form stored on runs
+    /// and sent to connector plugins.
+    pub fn to_tagged_json(&self) -> Value {
+        let type_name = match self {
+            TypedValue::String(_) => "string",
+            TypedValue::Number(_) => "number",
+            TypedValue::Integer(_) => "integer",
+            TypedValue::Boolean(_) => "boolean",
+            TypedValue::Date(_) => "date",
+            TypedValue::DateTime(_) => "datetime",
+            TypedValue::Null => "null",
+        };
+        serde_json::json!({"type": type_name, "value": self.to_json()})
+    }
+
+    /// Convert to a plain JSON value (no type tag).
+    pub fn to_json(&self) -> Value {
+        match self {
+            TypedValue::String(s) => Value::from(s.as_str()),
+            TypedValue::Number(n) => serde_json::json!(n),
+            TypedValue::Integer(i) => Value::from(*i),
+            TypedValue::Boolean(b) => Value::from(*b),
+            TypedValue::Date(d) => Value::from(d.to_string()),
+            TypedValue::DateTime(dt) => Value::from(dt.to_rfc3339()),
+            TypedValue::Null => Value::Null,
+        }
+    }
 }
 
 /// Parameter definitions from query metadata.
@@ -243,6 +285,112 @@ pub fn substitute_params(
     Ok(result)
 }
 
+/// Rewrite positional `$1, $2, ...` placeholders as `?` for drivers that bind by order
+/// of appearance (MySQL). Values are reordered/duplicated to match the rewritten SQL.
+///
+/// Placeholders are left untouched inside string literals (single- or double-quoted,
+/// with MySQL backslash escapes and doubled quotes), backtick-quoted identifiers and
+/// comments (`-- `, `#` and `/* */`).
+pub fn to_question_placeholders(sql: &str, values: &[TypedValue]) -> Result<(String, Vec<TypedValue>)> {
+    /// What the characters being read belong to
+    #[derive(Clone, Copy, PartialEq)]
+    enum Context {
+        Code,
+        /// Inside a literal or identifier opened by this quote
+        Quoted(char),
+        LineComment,
+        BlockComment,
+    }
+
+    let mut out = String::with_capacity(sql.len());
+    let mut ordered = Vec::new();
+    let mut chars = sql.chars().peekable();
+    let mut context = Context::Code;
+
+    while let Some(c) = chars.next() {
+        out.push(c);
+        match context {
+            Context::Quoted(quote) => {
+                if c == '\\' && quote != '`' {
+                    // The escaped character can't close the literal
+                    if let Some(escaped) = chars.next() {
+                        out.push(escaped);
+                    }
+                } else if c == quote {
+                    // A doubled quote stands for itself
+                    if chars.peek() == Some(&quote) {
+                        out.push(quote);
+                        chars.next();
+                    } else {
+                        context = Context::Code;
+                    }
+                }
+            }
+            Context::LineComment => {
+                if c == '\n' {
+                    context = Context::Code;
+                }
+            }
+            Context::BlockComment => {
+                if c == '*' && chars.peek() == Some(&'/') {
+                    out.push('/');
+                    chars.next();
+                    context = Context::Code;
+                }
+            }
+            Context::Code => match c {
+                '\'' | '"' | '`' => context = Context::Quoted(c),
+                '#' => context = Context::LineComment,
+                // MySQL needs whitespace after `--` for it to start a comment
+                '-' if chars.peek() == Some(&'-') => {
+                    out.push('-');
+                    chars.next();
+                    if chars.peek().is_none_or(|next| next.is_whitespace() || next.is_control()) {
+                        context = Context::LineComment;
+                    }
+                }
+                '/' if chars.peek() == Some(&'*') => {
+                    out.push('*');
+                    chars.next();
+                    context = Context::BlockComment;
+                }
+                '$' => {
+                    let mut digits = String::new();
+                    while let Some(&d) = chars.peek() {
+                        if d.is_ascii_digit() {
+                            digits.push(d);
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+
+                    if digits.is_empty() {
+                        continue;
+                    }
+
+                    let position: usize = digits
+                        .parse()
+                        .map_err(|_| Error::BadRequest(format!("Invalid placeholder ${}", digits)))?;
+                    let value = position
+                        .checked_sub(1)
+                        .and_then(|idx| values.get(idx))
+                        .ok_or_else(|| {
+                            Error::BadRequest(format!("No value bound for placeholder ${}", position))
+                        })?;
+
+                    ordered.push(value.clone());
+                    out.pop();
+                    out.push('?');
+                }
+                _ => {}
+            },
+        }
+    }
+
+    Ok((out, ordered))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -353,4 +501,56 @@ mod tests {
         let bound = bind_params(sql, &schema, &values).unwrap();
         assert!(matches!(bound.values[0], TypedValue::String(ref s) if s == "default_val"));
     }
+
+    #[test]
+    fn test_to_question_placeholders_reorders_values() {
+        let values = vec![TypedValue::Integer(1), TypedValue::String("a".into())];
+        let (sql, ordered) =
+            to_question_placeholders("SELECT * FROM t WHERE b = $2 AND a = $1 OR c = $2", &values)
+                .unwrap();
+
+        assert_eq!(sql, "SELECT * FROM t WHERE b = ? AND a = ? OR c = ?");
+        assert_eq!(ordered.len(), 3);
+        assert!(matches!(ordered[0], TypedValue::String(ref s) if s == "a"));
+        assert!(matches!(ordered[1], TypedValue::Integer(1)));
+        assert!(matches!(ordered[2], TypedValue::String(ref s) if s == "a"));
+    }
+
+    #[test]
+    fn test_to_question_placeholders_skips_literals() {
+        let values = vec![TypedValue::Integer(7)];
+        let (sql, ordered) =
+            to_question_placeholders("SELECT '$1 off' AS promo, $1 AS n", &values).unwrap();
+
+        assert_eq!(sql, "SELECT '$1 off' AS promo, ? AS n");
+        assert_eq!(ordered.len(), 1);
+    }
+
+    #[test]
+    fn test_to_question_placeholders_skips_quoted_text_and_comments() {
+        let values = vec![TypedValue::Integer(7)];
+        let rewrite = |sql: &str| to_question_placeholders(sql, &values).unwrap();
+
+        // A backslash-escaped quote doesn't end the literal
+        assert_eq!(rewrite(r"SELECT 'it\'s $1', $1").0, r"SELECT 'it\'s $1', ?");
+        assert_eq!(rewrite("SELECT 'it''s $1', $1").0, "SELECT 'it''s $1', ?");
+        assert_eq!(rewrite(r#"SELECT "say \"$1\"", $1"#).0, r#"SELECT "say \"$1\"", ?"#);
+        assert_eq!(rewrite("SELECT `col$1` FROM t WHERE a = $1").0, "SELECT `col$1` FROM t WHERE a = ?");
+
+        let (sql, ordered) = rewrite("SELECT $1 -- uses $1\n# and $1\n/* not $1 */ + $1");
+        assert_eq!(sql, "SELECT ? -- uses $1\n# and $1\n/* not $1 */ + ?");
+        assert_eq!(ordered.len(), 2);
+
+        // Without whitespace `--` is two minus signs
+        assert_eq!(rewrite("SELECT 5--$1").0, "SELECT 5--?");
+        assert_eq!(rewrite("SELECT 1 --").0, "SELECT 1 --");
+        // An unterminated literal swallows the rest
+        assert_eq!(rewrite("SELECT 'open $1").0, "SELECT 'open $1");
+    }
+
+    #[test]
+    fn test_to_question_placeholders_missing_value() {
+        let result = to_question_placeholders("SELECT $3", &[TypedValue::Null]);
+        assert!(result.is_err());
+    }
 }
@@ -1,10 +1,41 @@
 /// Query execution limiter to prevent resource exhaustion
 ///
 /// Tracks concurrent query executions per organization and enforces limits.
+/// The local backend counts within one process; the Postgres backend keeps
+/// slots in the `query_slots` table so the limits hold across every runner.
+/// Postgres slots are leased to their holder and renewed by its heartbeat,
+/// so a crashed runner's slots free up once the lease expires.
+use crate::db::Database;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+/// Where concurrent query counts are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimiterBackend {
+    /// In-process counts; limits apply per process
+    Local,
+    /// Shared slots in the metadata database; limits apply across processes
+    Postgres,
+}
+
+impl LimiterBackend {
+    /// Read `QUERY_LIMITER_BACKEND` (`postgres` or `local`), defaulting to Postgres
+    pub fn from_env() -> Self {
+        match std::env::var("QUERY_LIMITER_BACKEND").as_deref() {
+            Ok("local") => Self::Local,
+            Ok("postgres") | Err(_) => Self::Postgres,
+            Ok(other) => {
+                tracing::warn!(
+                    value = %other,
+                    "Invalid QUERY_LIMITER_BACKEND value. Falling back to postgres."
+                );
+                Self::Postgres
+            }
+        }
+    }
+}
+
 /// Configuration for query execution limits
 #[derive(Debug, Clone)]
 pub struct QueryLimits {
@@ -47,7 +78,13 @@ impl QueryLimits {
 #[derive(Clone)]
 pub struct QueryLimiter {
     limits: QueryLimits,
-    state: Arc<Mutex<LimiterState>>,
+    backend: Backend,
+}
+
+#[derive(Clone)]
+enum Backend {
+    Local(Arc<Mutex<LimiterState>>),
+    Postgres { db: Database, holder: String },
 }
 
 struct LimiterState {
@@ -58,86 +95,168 @@ struct LimiterState {
 }
 
 impl QueryLimiter {
-    /// Create a new query limiter with default limits
+    /// Create an in-process query limiter
     pub fn new(limits: QueryLimits) -> Self {
         Self {
             limits,
-            state: Arc::new(Mutex::new(LimiterState {
+            backend: Backend::Local(Arc::new(Mutex::new(LimiterState {
                 org_queries: HashMap::new(),
                 total_queries: 0,
-            })),
+            }))),
+        }
+    }
+
+    /// Create a limiter sharing slots through the metadata database
+    ///
+    /// `holder` identifies this process (the runner id); its heartbeats keep
+    /// the slots it holds alive.
+    pub fn postgres(limits: QueryLimits, db: Database, holder: impl Into<String>) -> Self {
+        Self {
+            limits,
+            backend: Backend::Postgres {
+                db,
+                holder: holder.into(),
+            },
         }
     }
 
     /// Attempt to acquire a slot for query execution
     ///
     /// Returns Ok(QueryGuard) if the query can proceed, or Err if limit is reached.
-    pub fn try_acquire(&self, org_id: Uuid) -> Result<QueryGuard, LimitError> {
-        let mut state = self.state.lock().unwrap();
+    pub async fn try_acquire(&self, org_id: Uuid) -> Result<QueryGuard, LimitError> {
+        let slot_id = match &self.backend {
+            Backend::Local(state) => {
+                self.acquire_local(state, org_id)?;
+                None
+            }
+            Backend::Postgres { db, holder } => {
+                let attempt = db
+                    .acquire_query_slot(
+                        org_id,
+                        holder,
+                        self.limits.max_concurrent_per_org as i64,
+                        self.limits.max_concurrent_global as i64,
+                    )
+                    .await
+                    .map_err(|e| LimitError::Unavailable(e.to_string()))?;
+
+                if attempt.slot_id.is_none() {
+                    return Err(self.limit_error(
+                        org_id,
+                        attempt.global_count as usize,
+                        attempt.org_count as usize,
+                    ));
+                }
+                attempt.slot_id
+            }
+        };
 
-        // Check global limit
-        if state.total_queries >= self.limits.max_concurrent_global {
-            return Err(LimitError::GlobalLimitReached {
-                current: state.total_queries,
-                max: self.limits.max_concurrent_global,
-            });
-        }
+        Ok(QueryGuard {
+            org_id,
+            slot_id,
+            limiter: self.clone(),
+        })
+    }
+
+    fn acquire_local(&self, state: &Mutex<LimiterState>, org_id: Uuid) -> Result<(), LimitError> {
+        let mut state = state.lock().unwrap();
 
-        // Check per-org limit
         let org_count = state.org_queries.get(&org_id).copied().unwrap_or(0);
-        if org_count >= self.limits.max_concurrent_per_org {
-            return Err(LimitError::OrgLimitReached {
-                org_id,
-                current: org_count,
-                max: self.limits.max_concurrent_per_org,
-            });
+        if state.total_queries >= self.limits.max_concurrent_global
+            || org_count >= self.limits.max_concurrent_per_org
+        {
+            return Err(self.limit_error(org_id, state.total_queries, org_count));
         }
 
         // Increment counters
         state.total_queries += 1;
         *state.org_queries.entry(org_id).or_insert(0) += 1;
 
-        Ok(QueryGuard {
-            org_id,
-            limiter: self.clone(),
-        })
+        Ok(())
     }
 
-    /// Release a query slot (called automatically when QueryGuard is dropped)
-    fn release(&self, org_id: Uuid) {
-        let mut state = self.state.lock().unwrap();
-
-        if let Some(count) = state.org_queries.get_mut(&org_id) {
-            *count = count.saturating_sub(1);
-            if *count == 0 {
-                state.org_queries.remove(&org_id);
+    /// The error for a rejected acquisition; the global limit is reported first
+    fn limit_error(&self, org_id: Uuid, total: usize, org_count: usize) -> LimitError {
+        if total >= self.limits.max_concurrent_global {
+            LimitError::GlobalLimitReached {
+                current: total,
+                max: self.limits.max_concurrent_global,
+            }
+        } else {
+            LimitError::OrgLimitReached {
+                org_id,
+                current: org_count,
+                max: self.limits.max_concurrent_per_org,
             }
         }
+    }
 
-        state.total_queries = state.total_queries.saturating_sub(1);
+    /// Release a query slot (called automatically when QueryGuard is dropped)
+    fn release(&self, org_id: Uuid, slot_id: Option<Uuid>) {
+        match &self.backend {
+            Backend::Local(state) => {
+                let mut state = state.lock().unwrap();
+
+                if let Some(count) = state.org_queries.get_mut(&org_id) {
+                    *count = count.saturating_sub(1);
+                    if *count == 0 {
+                        state.org_queries.remove(&org_id);
+                    }
+                }
+
+                state.total_queries = state.total_queries.saturating_sub(1);
+            }
+            Backend::Postgres { db, .. } => {
+                let Some(slot_id) = slot_id else { return };
+                // Drop can't await; if this doesn't run, the slot's lease expires
+                if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                    let db = db.clone();
+                    handle.spawn(async move {
+                        if let Err(e) = db.release_query_slot(slot_id).await {
+                            tracing::warn!(slot_id = %slot_id, "Failed to release query slot: {}", e);
+                        }
+                    });
+                }
+            }
+        }
     }
 
     /// Get current statistics
-    pub fn stats(&self) -> LimiterStats {
-        let state = self.state.lock().unwrap();
-        LimiterStats {
-            total_queries: state.total_queries,
-            org_count: state.org_queries.len(),
+    pub async fn stats(&self) -> Result<LimiterStats, LimitError> {
+        let (total_queries, org_count) = match &self.backend {
+            Backend::Local(state) => {
+                let state = state.lock().unwrap();
+                (state.total_queries, state.org_queries.len())
+            }
+            Backend::Postgres { db, .. } => {
+                let (total, orgs) = db
+                    .query_slot_counts()
+                    .await
+                    .map_err(|e| LimitError::Unavailable(e.to_string()))?;
+                (total as usize, orgs as usize)
+            }
+        };
+
+        Ok(LimiterStats {
+            total_queries,
+            org_count,
             max_concurrent_global: self.limits.max_concurrent_global,
             max_concurrent_per_org: self.limits.max_concurrent_per_org,
-        }
+        })
     }
 }
 
 /// Guard that automatically releases a query slot when dropped
 pub struct QueryGuard {
     org_id: Uuid,
+    /// Shared slot held, for the Postgres backend
+    slot_id: Option<Uuid>,
     limiter: QueryLimiter,
 }
 
 impl Drop for QueryGuard {
     fn drop(&mut self) {
-        self.limiter.release(self.org_id);
+        self.limiter.release(self.org_id, self.slot_id);
     }
 }
 
@@ -162,14 +281,17 @@ pub enum LimitError {
         current: usize,
         max: usize,
     },
+
+    #[error("Query limiter unavailable: {0}")]
+    Unavailable(String),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_limiter_basic() {
+    #[tokio::test]
+    async fn test_limiter_basic() {
         let limits = QueryLimits {
             max_concurrent_per_org: 2,
             max_concurrent_global: 5,
@@ -178,22 +300,22 @@ mod tests {
         let org_id = Uuid::new_v4();
 
         // Should be able to acquire up to the limit
-        let _guard1 = limiter.try_acquire(org_id).unwrap();
-        let _guard2 = limiter.try_acquire(org_id).unwrap();
+        let _guard1 = limiter.try_acquire(org_id).await.unwrap();
+        let _guard2 = limiter.try_acquire(org_id).await.unwrap();
 
         // Third should fail
         assert!(matches!(
-            limiter.try_acquire(org_id),
+            limiter.try_acquire(org_id).await,
             Err(LimitError::OrgLimitReached { .. })
         ));
 
         // Stats should reflect current state
-        let stats = limiter.stats();
+        let stats = limiter.stats().await.unwrap();
         assert_eq!(stats.total_queries, 2);
     }
 
-    #[test]
-    fn test_limiter_release() {
+    #[tokio::test]
+    async fn test_limiter_release() {
         let limits = QueryLimits {
             max_concurrent_per_org: 2,
             max_concurrent_global: 5,
@@ -202,16 +324,16 @@ mod tests {
         let org_id = Uuid::new_v4();
 
         {
-            let _guard = limiter.try_acquire(org_id).unwrap();
-            assert_eq!(limiter.stats().total_queries, 1);
+            let _guard = limiter.try_acquire(org_id).await.unwrap();
+            assert_eq!(limiter.stats().await.unwrap().total_queries, 1);
         } // Guard dropped here
 
         // Should be released
-        assert_eq!(limiter.stats().total_queries, 0);
+        assert_eq!(limiter.stats().await.unwrap().total_queries, 0);
     }
 
-    #[test]
-    fn test_global_limit() {
+    #[tokio::test]
+    async fn test_global_limit() {
         let limits = QueryLimits {
             max_concurrent_per_org: 10,
             max_concurrent_global: 2,
@@ -221,12 +343,12 @@ mod tests {
         let org1 = Uuid::new_v4();
         let org2 = Uuid::new_v4();
 
-        let _guard1 = limiter.try_acquire(org1).unwrap();
-        let _guard2 = limiter.try_acquire(org2).unwrap();
+        let _guard1 = limiter.try_acquire(org1).await.unwrap();
+        let _guard2 = limiter.try_acquire(org2).await.unwrap();
 
         // Third should fail due to global limit
         assert!(matches!(
-            limiter.try_acquire(org1),
+            limiter.try_acquire(org1).await,
             Err(LimitError::GlobalLimitReached { .. })
         ));
     }
@@ -0,0 +1,530 @@
+/// Storage of run result rows
+///
+/// Result metadata (columns, counts, timings, truncation) always lives in
+/// `run_results`. The rows are stored inline unless an object store is
+/// configured and their stored size is over the offload threshold; then they
+/// are written to the object store (S3, R2, MinIO, or a local directory) and
+/// `run_results.storage_key` points at them. Reading through `get_run_result`
+/// fills the rows in from wherever they are.
+///
+/// Rows are serialized with the store's `ResultEncoding`: a JSON array of row
+/// arrays (inline as JSONB), or a zstd-compressed JSON array of column arrays
+/// (inline as bytes). Wide time-series compress several times over in the
+/// columnar form. Either can be read back row- or column-major.
+use crate::db::Database;
+use crate::error::{Error, Result};
+use crate::models::{ResultEncoding, RunResult, TruncationReason};
+use object_store::ObjectStore;
+use object_store::aws::AmazonS3Builder;
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use serde_json::value::RawValue;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Default size above which rows are offloaded (1 MiB)
+pub const DEFAULT_OFFLOAD_THRESHOLD_BYTES: usize = 1024 * 1024;
+
+/// zstd level for columnar results; favours write speed, since the runner
+/// compresses every result
+const ZSTD_LEVEL: i32 = 3;
+
+/// Where offloaded result rows are kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResultBackend {
+    /// Every result inline in Postgres
+    Postgres,
+    /// S3-compatible bucket; `endpoint` is set for R2, MinIO and the like
+    S3 {
+        bucket: String,
+        endpoint: Option<String>,
+    },
+    /// Local directory, for single-host deployments and tests
+    Filesystem { path: String },
+}
+
+impl ResultBackend {
+    /// Read `RESULT_STORE` (`postgres`, `s3` or `filesystem`), defaulting to Postgres
+    ///
+    /// `s3` needs `RESULT_STORE_S3_BUCKET` and takes `RESULT_STORE_S3_ENDPOINT`;
+    /// credentials and region come from the usual `AWS_*` variables.
+    /// `filesystem` needs `RESULT_STORE_PATH`.
+    pub fn from_env() -> Result<Self> {
+        let required = |name: &str| {
+            std::env::var(name)
+                .map_err(|_| Error::Internal(format!("{} must be set for RESULT_STORE", name)))
+        };
+
+        match std::env::var("RESULT_STORE").as_deref() {
+            Ok("postgres") | Err(_) => Ok(Self::Postgres),
+            Ok("s3") => Ok(Self::S3 {
+                bucket: required("RESULT_STORE_S3_BUCKET")?,
+                endpoint: std::env::var("RESULT_STORE_S3_ENDPOINT").ok(),
+            }),
+            Ok("filesystem") => Ok(Self::Filesystem {
+                path: required("RESULT_STORE_PATH")?,
+            }),
+            Ok(other) => {
+                tracing::warn!(
+                    value = %other,
+                    "Invalid RESULT_STORE value. Falling back to postgres."
+                );
+                Ok(Self::Postgres)
+            }
+        }
+    }
+
+    fn build(&self) -> Result<Option<Arc<dyn ObjectStore>>> {
+        let store: Arc<dyn ObjectStore> = match self {
+            Self::Postgres => return Ok(None),
+            Self::S3 { bucket, endpoint } => {
+                let mut builder = AmazonS3Builder::from_env().with_bucket_name(bucket);
+                if let Some(endpoint) = endpoint {
+                    builder = builder
+                        .with_endpoint(endpoint)
+                        .with_allow_http(endpoint.starts_with("http://"));
+                }
+                Arc::new(builder.build()?)
+            }
+            Self::Filesystem { path } => {
+                std::fs::create_dir_all(path).map_err(|e| {
+                    Error::Internal(format!("Failed to create result directory {}: {}", path, e))
+                })?;
+                Arc::new(LocalFileSystem::new_with_prefix(path)?)
+            }
+        };
+        Ok(Some(store))
+    }
+}
+
+/// Writes and reads run results, choosing inline or object storage by size
+#[derive(Clone)]
+pub struct ResultStore {
+    db: Database,
+    objects: Option<Arc<dyn ObjectStore>>,
+    offload_threshold: usize,
+    encoding: ResultEncoding,
+}
+
+impl ResultStore {
+    /// Keep every result inline in Postgres
+    pub fn inline(db: Database) -> Self {
+        Self {
+            db,
+            objects: None,
+            offload_threshold: usize::MAX,
+            encoding: ResultEncoding::Json,
+        }
+    }
+
+    /// Offload rows larger than `offload_threshold` bytes to `objects`
+    pub fn with_object_store(
+        db: Database,
+        objects: Arc<dyn ObjectStore>,
+        offload_threshold: usize,
+    ) -> Self {
+        Self {
+            db,
+            objects: Some(objects),
+            offload_threshold,
+            encoding: ResultEncoding::Json,
+        }
+    }
+
+    /// Write new results with `encoding`; results already stored keep theirs
+    pub fn with_encoding(mut self, encoding: ResultEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Create a store from `RESULT_STORE` (see `ResultBackend::from_env`),
+    /// `RESULT_OFFLOAD_THRESHOLD_BYTES` and `RESULT_ENCODING` (`json` or
+    /// `columnar_zstd`, defaulting to `json`)
+    pub fn from_env(db: Database) -> Result<Self> {
+        let backend = ResultBackend::from_env()?;
+        let offload_threshold = std::env::var("RESULT_OFFLOAD_THRESHOLD_BYTES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_OFFLOAD_THRESHOLD_BYTES);
+        let encoding = match std::env::var("RESULT_ENCODING").as_deref() {
+            Ok("json") | Err(_) => ResultEncoding::Json,
+            Ok("columnar_zstd") => ResultEncoding::ColumnarZstd,
+            Ok(other) => {
+                tracing::warn!(
+                    value = %other,
+                    "Invalid RESULT_ENCODING value. Falling back to json."
+                );
+                ResultEncoding::Json
+            }
+        };
+
+        let store = match backend.build()? {
+            Some(objects) => Self::with_object_store(db, objects, offload_threshold),
+            None => Self::inline(db),
+        };
+        Ok(store.with_encoding(encoding))
+    }
+
+    /// Whether large results go to object storage
+    pub fn offloads(&self) -> bool {
+        self.objects.is_some()
+    }
+
+    /// Store a run's result; `rows` is the serialized JSON array of row arrays
+    ///
+    /// The stored `byte_count` is the size of the rows as encoded, which for
+    /// columnar results is their compressed size.
+    pub async fn put(
+        &self,
+        run_id: Uuid,
+        columns: &serde_json::Value,
+        rows: &RawValue,
+        row_count: i64,
+        execution_time_ms: i64,
+        truncation_reason: Option<TruncationReason>,
+    ) -> Result<RunResult> {
+        let encoded = match self.encoding {
+            ResultEncoding::Json => None,
+            ResultEncoding::ColumnarZstd => Some(encode_columnar(rows, column_count(columns))?),
+        };
+        let stored = encoded.as_deref().unwrap_or(rows.get().as_bytes());
+        let byte_count = stored.len() as i64;
+
+        let objects = match &self.objects {
+            Some(objects) if stored.len() > self.offload_threshold => objects,
+            _ => {
+                return match &encoded {
+                    None => {
+                        self.db
+                            .create_run_result(
+                                run_id,
+                                columns,
+                                rows,
+                                row_count,
+                                byte_count,
+                                execution_time_ms,
+                                truncation_reason,
+                            )
+                            .await
+                    }
+                    Some(data) => {
+                        self.db
+                            .create_encoded_run_result(
+                                run_id,
+                                columns,
+                                self.encoding,
+                                data,
+                                row_count,
+                                byte_count,
+                                execution_time_ms,
+                                truncation_reason,
+                            )
+                            .await
+                    }
+                };
+            }
+        };
+
+        let key = object_key(run_id, self.encoding);
+        objects
+            .put(&key, bytes::Bytes::copy_from_slice(stored).into())
+            .await?;
+
+        let result = self
+            .db
+            .create_offloaded_run_result(
+                run_id,
+                columns,
+                key.as_ref(),
+                self.encoding,
+                row_count,
+                byte_count,
+                execution_time_ms,
+                truncation_reason,
+            )
+            .await;
+
+        // Don't leave an object nothing points at
+        if result.is_err()
+            && let Err(e) = objects.delete(&key).await
+        {
+            tracing::warn!(run_id = %run_id, key = %key, "Failed to remove orphaned result object: {}", e);
+        }
+        result
+    }
+
+    /// A run's result with its rows, wherever and however they are stored
+    pub async fn get_run_result(&self, run_id: Uuid) -> Result<RunResult> {
+        let (mut result, rows) = self.load(run_id).await?;
+        result.rows = Some(match rows {
+            StoredRows::Rows(rows) => rows,
+            StoredRows::Columns(data) => serde_json::Value::Array(
+                columns_to_rows(data, result.row_count)
+                    .into_iter()
+                    .map(serde_json::Value::Array)
+                    .collect(),
+            ),
+        });
+        Ok(result)
+    }
+
+    /// A run's result with its values grouped by column, one array per column
+    ///
+    /// The result's `rows` are left unset.
+    pub async fn get_columnar_result(
+        &self,
+        run_id: Uuid,
+    ) -> Result<(RunResult, Vec<Vec<serde_json::Value>>)> {
+        let (result, rows) = self.load(run_id).await?;
+        let data = match rows {
+            StoredRows::Columns(data) => data,
+            StoredRows::Rows(rows) => {
+                let rows: Vec<Vec<serde_json::Value>> = serde_json::from_value(rows)
+                    .map_err(|e| corrupt(run_id, "rows", e))?;
+                rows_to_columns(rows, column_count(&result.columns))
+            }
+        };
+        Ok((result, data))
+    }
+
+    /// Fetch a result and decode its rows, taking them out of the `RunResult`
+    async fn load(&self, run_id: Uuid) -> Result<(RunResult, StoredRows)> {
+        let mut result = self.db.get_run_result(run_id).await?;
+
+        let offloaded = match &result.storage_key {
+            Some(key) => {
+                let objects = self.objects.as_ref().ok_or_else(|| {
+                    tracing::error!(run_id = %run_id, key = %key, "Result is offloaded but no object store is configured");
+                    Error::Internal("Result storage is not configured".to_string())
+                })?;
+                Some(objects.get(&Path::from(key.as_str())).await?.bytes().await?)
+            }
+            None => None,
+        };
+
+        let rows = match result.encoding {
+            ResultEncoding::Json => match (result.rows.take(), offloaded) {
+                (Some(rows), _) => StoredRows::Rows(rows),
+                (None, Some(data)) => StoredRows::Rows(
+                    serde_json::from_slice(&data).map_err(|e| corrupt(run_id, "rows", e))?,
+                ),
+                (None, None) => return Err(missing(run_id)),
+            },
+            ResultEncoding::ColumnarZstd => {
+                let data = match (result.data.take(), offloaded) {
+                    (Some(data), _) => bytes::Bytes::from(data),
+                    (None, Some(data)) => data,
+                    (None, None) => return Err(missing(run_id)),
+                };
+                StoredRows::Columns(decode_columnar(run_id, &data)?)
+            }
+        };
+
+        Ok((result, rows))
+    }
+
+    /// Delete offloaded rows by their `storage_key` (the `run_results` row is
+    /// removed separately); already missing objects are fine
+    pub async fn delete_object(&self, storage_key: &str) -> Result<()> {
+        let objects = self.objects.as_ref().ok_or_else(|| {
+            tracing::error!(key = %storage_key, "Result is offloaded but no object store is configured");
+            Error::Internal("Result storage is not configured".to_string())
+        })?;
+        match objects.delete(&Path::from(storage_key)).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Delete the objects of results removed along with their query or
+    /// datasource; one left behind only costs storage, so failures are logged
+    pub async fn delete_orphaned_objects(&self, storage_keys: &[String]) {
+        for key in storage_keys {
+            if let Err(e) = self.delete_object(key).await {
+                tracing::warn!(key = %key, "Failed to delete orphaned result object: {}", e);
+            }
+        }
+    }
+}
+
+/// Rows as decoded from storage
+enum StoredRows {
+    /// JSON array of row arrays
+    Rows(serde_json::Value),
+    /// One array per column
+    Columns(Vec<Vec<serde_json::Value>>),
+}
+
+/// Object key for a run's rows
+fn object_key(run_id: Uuid, encoding: ResultEncoding) -> Path {
+    match encoding {
+        ResultEncoding::Json => Path::from(format!("results/{}.json", run_id)),
+        ResultEncoding::ColumnarZstd => Path::from(format!("results/{}.columns.json.zst", run_id)),
+    }
+}
+
+/// Number of column definitions in a result's `columns`
+fn column_count(columns: &serde_json::Value) -> usize {
+    columns.as_array().map_or(0, Vec::len)
+}
+
+/// Transpose serialized row arrays into compressed column arrays
+///
+/// Values are copied as raw JSON, so nothing is parsed beyond the array
+/// structure.
+fn encode_columnar(rows: &RawValue, column_count: usize) -> Result<Vec<u8>> {
+    let rows: Vec<Vec<&RawValue>> = serde_json::from_str(rows.get())
+        .map_err(|e| Error::Internal(format!("Result rows are not arrays: {}", e)))?;
+
+    let mut data: Vec<Vec<&RawValue>> = vec![Vec::with_capacity(rows.len()); column_count];
+    for row in rows {
+        if row.len() != column_count {
+            return Err(Error::Internal(format!(
+                "Result row has {} values but there are {} columns",
+                row.len(),
+                column_count
+            )));
+        }
+        for (column, value) in data.iter_mut().zip(row) {
+            column.push(value);
+        }
+    }
+
+    let compress = |data: &Vec<Vec<&RawValue>>| -> std::io::Result<Vec<u8>> {
+        let mut encoder = zstd::Encoder::new(Vec::new(), ZSTD_LEVEL)?;
+        serde_json::to_writer(&mut encoder, data)?;
+        encoder.finish()
+    };
+    compress(&data).map_err(|e| Error::Internal(format!("Failed to compress result: {}", e)))
+}
+
+fn decode_columnar(run_id: Uuid, data: &[u8]) -> Result<Vec<Vec<serde_json::Value>>> {
+    let decoder = zstd::Decoder::new(data).map_err(|e| corrupt(run_id, "columns", e))?;
+    serde_json::from_reader(decoder).map_err(|e| corrupt(run_id, "columns", e))
+}
+
+fn columns_to_rows(data: Vec<Vec<serde_json::Value>>, row_count: i64) -> Vec<Vec<serde_json::Value>> {
+    let mut columns: Vec<_> = data.into_iter().map(Vec::into_iter).collect();
+    (0..row_count)
+        .map(|_| {
+            columns
+                .iter_mut()
+                .map(|column| column.next().unwrap_or(serde_json::Value::Null))
+                .collect()
+        })
+        .collect()
+}
+
+fn rows_to_columns(
+    rows: Vec<Vec<serde_json::Value>>,
+    column_count: usize,
+) -> Vec<Vec<serde_json::Value>> {
+    let mut data = vec![Vec::with_capacity(rows.len()); column_count];
+    for row in rows {
+        for (column, value) in data.iter_mut().zip(row) {
+            column.push(value);
+        }
+    }
+    data
+}
+
+fn corrupt(run_id: Uuid, what: &str, e: impl std::fmt::Display) -> Error {
+    tracing::error!(run_id = %run_id, "Stored result {} could not be decoded: {}", what, e);
+    Error::Internal(format!("Stored result {} could not be decoded", what))
+}
+
+fn missing(run_id: Uuid) -> Error {
+    tracing::error!(run_id = %run_id, "Result has no stored rows");
+    Error::Internal("Stored result has no rows".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_key() {
+        let run_id = Uuid::parse_str("550e8400-e29b-41d4-a716-446655440000").unwrap();
+        assert_eq!(
+            object_key(run_id, ResultEncoding::Json).as_ref(),
+            "results/550e8400-e29b-41d4-a716-446655440000.json"
+        );
+        assert_eq!(
+            object_key(run_id, ResultEncoding::ColumnarZstd).as_ref(),
+            "results/550e8400-e29b-41d4-a716-446655440000.columns.json.zst"
+        );
+    }
+
+    #[test]
+    fn test_columnar_round_trip() {
+        let run_id = Uuid::new_v4();
+        let rows = RawValue::from_string(r#"[[1,"a",null],[2,"b",{"x":1.5}]]"#.to_string()).unwrap();
+
+        let encoded = encode_columnar(&rows, 3).unwrap();
+        let data = decode_columnar(run_id, &encoded).unwrap();
+        assert_eq!(
+            data,
+            vec![
+                vec![serde_json::json!(1), serde_json::json!(2)],
+                vec![serde_json::json!("a"), serde_json::json!("b")],
+                vec![serde_json::Value::Null, serde_json::json!({"x": 1.5})],
+            ]
+        );
+
+        let back = columns_to_rows(data, 2);
+        assert_eq!(serde_json::to_string(&back).unwrap(), rows.get());
+    }
+
+    #[test]
+    fn test_columnar_compresses_repetitive_rows() {
+        let rows: Vec<_> = (0..1000)
+            .map(|i| serde_json::json!(["2026-01-01T00:00:00Z", "cpu", i % 10]))
+            .collect();
+        let rows = serde_json::value::to_raw_value(&rows).unwrap();
+
+        let encoded = encode_columnar(&rows, 3).unwrap();
+        assert!(encoded.len() * 10 < rows.get().len());
+    }
+
+    #[test]
+    fn test_columnar_rejects_ragged_rows() {
+        let rows = RawValue::from_string("[[1,2],[3]]".to_string()).unwrap();
+        assert!(encode_columnar(&rows, 2).is_err());
+    }
+
+    #[test]
+    fn test_rows_to_columns() {
+        let rows = vec![
+            vec![serde_json::json!(1), serde_json::json!("a")],
+            vec![serde_json::json!(2), serde_json::json!("b")],
+        ];
+        let data = rows_to_columns(rows.clone(), 2);
+        assert_eq!(data[0], vec![serde_json::json!(1), serde_json::json!(2)]);
+        assert_eq!(columns_to_rows(data, 2), rows);
+    }
+
+    #[test]
+    fn test_empty_result_has_empty_columns() {
+        let rows = RawValue::from_string("[]".to_string()).unwrap();
+        let data = decode_columnar(Uuid::new_v4(), &encode_columnar(&rows, 2).unwrap()).unwrap();
+        assert_eq!(data, vec![Vec::<serde_json::Value>::new(); 2]);
+        assert!(columns_to_rows(data, 0).is_empty());
+    }
+
+    #[test]
+    fn test_backend_defaults_to_postgres() {
+        assert!(ResultBackend::Postgres.build().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_filesystem_backend_creates_directory() {
+        let dir = std::env::temp_dir().join(format!("loupe-results-{}", Uuid::new_v4()));
+        let backend = ResultBackend::Filesystem {
+            path: dir.to_string_lossy().into_owned(),
+        };
+
+        assert!(backend.build().unwrap().is_some());
+        assert!(dir.is_dir());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
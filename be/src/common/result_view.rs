@@ -0,0 +1,449 @@
+/// Paging, sorting, filtering and projection over a stored result
+///
+/// Applied to the rows as stored, so a large result can be browsed a page at
+/// a time without re-running its query. For sorting, values order by JSON
+/// type first (null, booleans, numbers, strings, then arrays and objects) and
+/// then by value; in a column of a numeric type, numeric strings sort as
+/// numbers (decimals are often stored as strings). Filters compare like values
+/// only, except that a number also compares with a numeric string; a cell that
+/// can't be compared never matches.
+use crate::error::{Error, Result};
+use crate::models::{ColumnDef, RunResultPageResponse, RunResultParams, RunResultResponse};
+use crate::pagination::{PaginatedResponse, PaginationParams};
+use serde::Deserialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// Rows per page when `limit` isn't given
+pub const DEFAULT_RESULT_PAGE_SIZE: i64 = 1000;
+
+/// Most rows returned in one page
+pub const MAX_RESULT_PAGE_SIZE: i64 = 10_000;
+
+/// Most filters accepted in one request
+const MAX_FILTERS: usize = 20;
+
+/// Comparison applied by a `ResultFilter`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    /// Case-insensitive substring of a string cell
+    Contains,
+    IsNull,
+    NotNull,
+}
+
+/// A condition on one result column, e.g.
+/// `{"column": "status", "op": "eq", "value": "active"}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResultFilter {
+    pub column: String,
+    pub op: FilterOp,
+    /// Unused by `is_null` and `not_null`
+    #[serde(default)]
+    pub value: Value,
+}
+
+/// Column to sort by
+#[derive(Debug, Clone, Copy)]
+struct Sort {
+    column: usize,
+    descending: bool,
+    /// Whether the column's type is numeric, so numeric strings sort as numbers
+    numeric: bool,
+}
+
+/// Validated view parameters, with columns resolved to their positions
+#[derive(Debug, Clone)]
+pub struct ResultView {
+    pub pagination: PaginationParams,
+    sort: Option<Sort>,
+    filters: Vec<(usize, FilterOp, Value)>,
+    projection: Option<Vec<usize>>,
+}
+
+impl ResultView {
+    /// Check `params` against a result's columns; unknown columns and
+    /// malformed filters are rejected
+    pub fn parse(params: &RunResultParams, columns: &[ColumnDef]) -> Result<Self> {
+        let position = |name: &str| {
+            columns
+                .iter()
+                .position(|c| c.name == name)
+                .ok_or_else(|| Error::BadRequest(format!("Unknown result column: {}", name)))
+        };
+
+        let pagination = PaginationParams {
+            limit: params
+                .limit
+                .unwrap_or(DEFAULT_RESULT_PAGE_SIZE)
+                .clamp(1, MAX_RESULT_PAGE_SIZE),
+            offset: params.offset.unwrap_or(0).max(0),
+        };
+
+        let descending = match params.sort_direction.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(other) => {
+                return Err(Error::BadRequest(format!(
+                    "Invalid sort_direction: {} (expected asc or desc)",
+                    other
+                )));
+            }
+        };
+        let sort = match &params.sort_by {
+            Some(column) => {
+                let column = position(column)?;
+                Some(Sort {
+                    column,
+                    descending,
+                    numeric: is_numeric(&columns[column]),
+                })
+            }
+            None => None,
+        };
+
+        let filters = match &params.filters {
+            Some(json) => {
+                let filters: Vec<ResultFilter> = serde_json::from_str(json)
+                    .map_err(|e| Error::BadRequest(format!("Invalid filters: {}", e)))?;
+                if filters.len() > MAX_FILTERS {
+                    return Err(Error::BadRequest(format!(
+                        "At most {} filters are allowed",
+                        MAX_FILTERS
+                    )));
+                }
+                filters
+                    .into_iter()
+                    .map(|f| {
+                        let needs_value = !matches!(f.op, FilterOp::IsNull | FilterOp::NotNull);
+                        if needs_value && f.value.is_null() {
+                            return Err(Error::BadRequest(format!(
+                                "Filter on {} needs a value; use is_null or not_null for nulls",
+                                f.column
+                            )));
+                        }
+                        if f.op == FilterOp::Contains && !f.value.is_string() {
+                            return Err(Error::BadRequest(format!(
+                                "Filter on {}: contains needs a string value",
+                                f.column
+                            )));
+                        }
+                        Ok((position(&f.column)?, f.op, f.value))
+                    })
+                    .collect::<Result<_>>()?
+            }
+            None => Vec::new(),
+        };
+
+        let projection = match &params.columns {
+            Some(names) => {
+                let positions = names
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(position)
+                    .collect::<Result<Vec<_>>>()?;
+                if positions.is_empty() {
+                    return Err(Error::BadRequest("columns must name at least one column".to_string()));
+                }
+                Some(positions)
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            pagination,
+            sort,
+            filters,
+            projection,
+        })
+    }
+
+    /// Filter, sort, page and project a result's rows
+    pub fn page(&self, result: RunResultResponse) -> RunResultPageResponse {
+        let mut rows = result.rows;
+        rows.retain(|row| {
+            self.filters
+                .iter()
+                .all(|(column, op, value)| matches(row.get(*column).unwrap_or(&Value::Null), *op, value))
+        });
+        let total = rows.len() as i64;
+
+        if let Some(sort) = self.sort {
+            rows.sort_by(|a, b| {
+                let ordering = compare_values(
+                    a.get(sort.column).unwrap_or(&Value::Null),
+                    b.get(sort.column).unwrap_or(&Value::Null),
+                    sort.numeric,
+                );
+                if sort.descending { ordering.reverse() } else { ordering }
+            });
+        }
+
+        let page = rows
+            .into_iter()
+            .skip(self.pagination.offset as usize)
+            .take(self.pagination.limit as usize);
+
+        let (columns, page) = match &self.projection {
+            Some(positions) => (
+                positions.iter().map(|&i| result.columns[i].clone()).collect(),
+                page.map(|row| {
+                    positions
+                        .iter()
+                        .map(|&i| row.get(i).cloned().unwrap_or(Value::Null))
+                        .collect()
+                })
+                .collect(),
+            ),
+            None => (result.columns, page.collect()),
+        };
+
+        RunResultPageResponse {
+            run_id: result.run_id,
+            columns,
+            rows: PaginatedResponse::new(page, total, &self.pagination),
+            row_count: result.row_count,
+            execution_time_ms: result.execution_time_ms,
+            truncated: result.truncated,
+            truncation_reason: result.truncation_reason,
+        }
+    }
+}
+
+fn matches(cell: &Value, op: FilterOp, value: &Value) -> bool {
+    let ordering = || compare_filter(cell, value);
+    match op {
+        FilterOp::IsNull => cell.is_null(),
+        FilterOp::NotNull => !cell.is_null(),
+        FilterOp::Contains => match (cell, value) {
+            (Value::String(cell), Value::String(value)) => {
+                cell.to_lowercase().contains(&value.to_lowercase())
+            }
+            _ => false,
+        },
+        FilterOp::Eq => ordering() == Some(Ordering::Equal),
+        FilterOp::Ne => ordering().is_some_and(|o| o != Ordering::Equal),
+        FilterOp::Gt => ordering() == Some(Ordering::Greater),
+        FilterOp::Gte => ordering().is_some_and(|o| o != Ordering::Less),
+        FilterOp::Lt => ordering() == Some(Ordering::Less),
+        FilterOp::Lte => ordering().is_some_and(|o| o != Ordering::Greater),
+    }
+}
+
+/// How `cell` compares with a filter's `value`, if they're comparable
+fn compare_filter(cell: &Value, value: &Value) -> Option<Ordering> {
+    match (cell, value) {
+        (Value::Number(a), Value::Number(b)) => match (a.as_i64(), b.as_i64()) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        },
+        (Value::String(a), Value::Number(b)) => a.trim().parse::<f64>().ok()?.partial_cmp(&b.as_f64()?),
+        (Value::Number(a), Value::String(b)) => a.as_f64()?.partial_cmp(&b.trim().parse::<f64>().ok()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// Whether a column's declared type is numeric, e.g. INT8 or NUMERIC(10,2)
+fn is_numeric(column: &ColumnDef) -> bool {
+    let data_type = column.data_type.to_uppercase();
+    let base = data_type.split(['(', ' ']).next().unwrap_or("");
+    matches!(
+        base,
+        "INT" | "INT2" | "INT4" | "INT8" | "INTEGER" | "SMALLINT" | "BIGINT" | "TINYINT"
+            | "MEDIUMINT" | "FLOAT" | "FLOAT4" | "FLOAT8" | "REAL" | "DOUBLE" | "NUMERIC"
+            | "DECIMAL"
+    )
+}
+
+/// Total order over JSON values for sorting; with `numeric`, strings that
+/// parse as numbers order as numbers
+fn compare_values(a: &Value, b: &Value, numeric: bool) -> Ordering {
+    let number = |v: &Value| match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) if numeric => s.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    let rank = |v: &Value| match v {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        _ if number(v).is_some() => 2,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    };
+
+    rank(a).cmp(&rank(b)).then_with(|| match (a, b) {
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        _ if rank(a) == 2 => number(a)
+            .unwrap_or(0.0)
+            .total_cmp(&number(b).unwrap_or(0.0)),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        _ => a.to_string().cmp(&b.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn result() -> RunResultResponse {
+        RunResultResponse {
+            run_id: Uuid::new_v4(),
+            columns: vec![
+                ColumnDef { name: "id".to_string(), data_type: "INT8".to_string() },
+                ColumnDef { name: "name".to_string(), data_type: "TEXT".to_string() },
+                ColumnDef { name: "amount".to_string(), data_type: "NUMERIC".to_string() },
+            ],
+            rows: vec![
+                vec![json!(1), json!("Alice"), json!("10.50")],
+                vec![json!(2), json!("bob"), json!("3")],
+                vec![json!(3), json!("Carol"), Value::Null],
+                vec![json!(4), json!("Dave"), json!("250")],
+            ],
+            row_count: 4,
+            execution_time_ms: 5,
+            truncated: false,
+            truncation_reason: None,
+        }
+    }
+
+    fn params(query: &str) -> RunResultParams {
+        actix_web::web::Query::<RunResultParams>::from_query(query)
+            .unwrap()
+            .into_inner()
+    }
+
+    fn view(params: RunResultParams) -> Result<RunResultPageResponse> {
+        let result = result();
+        Ok(ResultView::parse(&params, &result.columns)?.page(result))
+    }
+
+    fn ids(page: &RunResultPageResponse) -> Vec<i64> {
+        page.rows.data.iter().map(|row| row[0].as_i64().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_pages_rows() {
+        let page = view(params("limit=2&offset=2")).unwrap();
+        assert_eq!(ids(&page), vec![3, 4]);
+        assert_eq!(page.rows.total, 4);
+        assert_eq!(page.rows.page, 2);
+        assert!(!page.rows.has_next);
+        assert!(page.rows.has_prev);
+        assert_eq!(page.row_count, 4);
+    }
+
+    #[test]
+    fn test_limit_is_clamped() {
+        let view = ResultView::parse(&params("limit=0"), &result().columns).unwrap();
+        assert_eq!(view.pagination.limit, 1);
+        let view = ResultView::parse(&params("limit=1000000&offset=-5"), &result().columns).unwrap();
+        assert_eq!(view.pagination.limit, MAX_RESULT_PAGE_SIZE);
+        assert_eq!(view.pagination.offset, 0);
+    }
+
+    #[test]
+    fn test_sorts_by_column() {
+        assert_eq!(ids(&view(params("sort_by=name")).unwrap()), vec![1, 3, 4, 2]);
+        assert_eq!(
+            ids(&view(params("sort_by=id&sort_direction=DESC")).unwrap()),
+            vec![4, 3, 2, 1]
+        );
+        // Nulls sort first, and decimal strings sort as numbers
+        assert_eq!(ids(&view(params("sort_by=amount")).unwrap()), vec![3, 2, 1, 4]);
+        assert_eq!(
+            ids(&view(params("sort_by=amount&sort_direction=desc")).unwrap()),
+            vec![4, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_numeric_columns_sort_strings_as_numbers() {
+        let column = ColumnDef {
+            name: "code".to_string(),
+            data_type: "TEXT".to_string(),
+        };
+        assert!(!is_numeric(&column));
+        assert_eq!(compare_values(&json!("10"), &json!("9"), false), Ordering::Less);
+        assert_eq!(compare_values(&json!("10"), &json!("9"), true), Ordering::Greater);
+        // Non-numeric strings still follow the numbers
+        assert_eq!(compare_values(&json!("n/a"), &json!("9"), true), Ordering::Greater);
+        assert_eq!(compare_values(&json!("n/a"), &json!(9), true), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_filters_rows() {
+        let filters = r#"[{"column":"amount","op":"gt","value":5}]"#;
+        let page = view(RunResultParams {
+            filters: Some(filters.to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(ids(&page), vec![1, 4]);
+        assert_eq!(page.rows.total, 2);
+
+        let filters = r#"[{"column":"name","op":"contains","value":"O"},{"column":"id","op":"ne","value":2}]"#;
+        let page = view(RunResultParams {
+            filters: Some(filters.to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(ids(&page), vec![3]);
+
+        let filters = r#"[{"column":"amount","op":"is_null"}]"#;
+        let page = view(RunResultParams {
+            filters: Some(filters.to_string()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(ids(&page), vec![3]);
+    }
+
+    #[test]
+    fn test_projects_columns() {
+        let page = view(params("columns=name, id")).unwrap();
+        assert_eq!(page.columns.len(), 2);
+        assert_eq!(page.columns[0].name, "name");
+        assert_eq!(page.rows.data[0], vec![json!("Alice"), json!(1)]);
+    }
+
+    #[test]
+    fn test_rejects_bad_parameters() {
+        for query in ["sort_by=missing", "sort_direction=up", "columns=,", "columns=nope"] {
+            assert!(view(params(query)).is_err(), "{}", query);
+        }
+        for filters in [
+            "not json",
+            r#"[{"column":"nope","op":"eq","value":1}]"#,
+            r#"[{"column":"id","op":"eq"}]"#,
+            r#"[{"column":"id","op":"contains","value":1}]"#,
+            r#"[{"column":"id","op":"like","value":1}]"#,
+        ] {
+            let params = RunResultParams {
+                filters: Some(filters.to_string()),
+                ..Default::default()
+            };
+            assert!(view(params).is_err(), "{}", filters);
+        }
+    }
+
+    #[test]
+    fn test_compare_values_orders_by_type_then_value() {
+        let mut values = vec![json!("b"), json!(10), Value::Null, json!(2.5), json!(true), json!("a")];
+        values.sort_by(|a, b| compare_values(a, b, false));
+        assert_eq!(values, vec![Value::Null, json!(true), json!(2.5), json!(10), json!("a"), json!("b")]);
+    }
+}
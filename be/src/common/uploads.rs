@@ -0,0 +1,500 @@
+//! Uploaded CSV / Parquet files as datasources
+//!
+//! An upload is parsed, its column types inferred, and the rows loaded into a
+//! single-table SQLite database under `UPLOAD_DIR`. The resulting file is then
+//! registered as a regular SQLite datasource, so schema browsing, saved queries
+//! and visualizations work the same as for any other connector.
+
+use crate::error::{Error, Result};
+use crate::models::ColumnDef;
+use bytes::Bytes;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::Field;
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Environment variable naming the directory uploaded files are stored in
+pub const UPLOAD_DIR_ENV: &str = "UPLOAD_DIR";
+
+/// Default maximum upload size (50 MiB)
+pub const DEFAULT_MAX_UPLOAD_BYTES: usize = 50 * 1024 * 1024;
+
+/// Directory uploads are stored in, if uploads are enabled
+pub fn upload_dir() -> Option<PathBuf> {
+    std::env::var(UPLOAD_DIR_ENV)
+        .ok()
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Maximum accepted upload size in bytes (`UPLOAD_MAX_BYTES`)
+pub fn max_upload_bytes() -> usize {
+    std::env::var("UPLOAD_MAX_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_UPLOAD_BYTES)
+}
+
+/// Supported upload file formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadFormat {
+    Csv,
+    Parquet,
+}
+
+impl UploadFormat {
+    /// Guess the format from a request Content-Type header
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or("").trim();
+        match mime {
+            "text/csv" | "application/csv" => Some(Self::Csv),
+            "application/vnd.apache.parquet" | "application/x-parquet" => Some(Self::Parquet),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+/// A parsed upload with inferred column types
+#[derive(Debug)]
+pub struct UploadedTable {
+    pub columns: Vec<ColumnDef>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// Parse an uploaded file and infer its column types
+pub fn parse_upload(format: UploadFormat, data: Bytes) -> Result<UploadedTable> {
+    let table = match format {
+        UploadFormat::Csv => parse_csv(&data)?,
+        UploadFormat::Parquet => parse_parquet(data)?,
+    };
+
+    if table.columns.is_empty() {
+        return Err(Error::BadRequest("Uploaded file has no columns".to_string()));
+    }
+
+    Ok(table)
+}
+
+fn parse_csv(data: &[u8]) -> Result<UploadedTable> {
+    let mut reader = csv::ReaderBuilder::new().flexible(false).from_reader(data);
+
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| Error::BadRequest(format!("Invalid CSV header: {}", e)))?
+        .iter()
+        .map(str::to_string)
+        .collect();
+
+    let mut records: Vec<Vec<String>> = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record =
+            record.map_err(|e| Error::BadRequest(format!("Invalid CSV row {}: {}", i + 1, e)))?;
+        records.push(record.iter().map(str::to_string).collect());
+    }
+
+    let names = unique_column_names(&headers);
+    let types: Vec<&'static str> = (0..names.len())
+        .map(|i| infer_text_type(records.iter().map(|r| r[i].as_str())))
+        .collect();
+
+    let columns = names
+        .into_iter()
+        .zip(&types)
+        .map(|(name, ty)| ColumnDef {
+            name,
+            data_type: ty.to_string(),
+        })
+        .collect();
+
+    let rows = records
+        .iter()
+        .map(|r| r.iter().zip(&types).map(|(v, ty)| coerce_text(v, ty)).collect())
+        .collect();
+
+    Ok(UploadedTable { columns, rows })
+}
+
+/// Pick the narrowest type every non-empty value parses as
+fn infer_text_type<'a>(values: impl Iterator<Item = &'a str> + Clone) -> &'static str {
+    let mut non_empty = values.filter(|v| !v.trim().is_empty()).peekable();
+    if non_empty.peek().is_none() {
+        return "TEXT";
+    }
+    let candidates = ["BOOLEAN", "INTEGER", "REAL", "DATE", "TIMESTAMP"];
+    candidates
+        .into_iter()
+        .find(|ty| non_empty.clone().all(|v| !coerce_text(v, ty).is_null()))
+        .unwrap_or("TEXT")
+}
+
+fn coerce_text(value: &str, data_type: &str) -> Value {
+    let v = value.trim();
+    if v.is_empty() {
+        return Value::Null;
+    }
+    match data_type {
+        "BOOLEAN" => match v.to_ascii_lowercase().as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            _ => Value::Null,
+        },
+        "INTEGER" => v.parse::<i64>().map(Value::from).unwrap_or(Value::Null),
+        "REAL" => v
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        "DATE" => NaiveDate::parse_from_str(v, "%Y-%m-%d")
+            .map(|d| Value::from(d.to_string()))
+            .unwrap_or(Value::Null),
+        "TIMESTAMP" => parse_timestamp(v)
+            .map(|dt| Value::from(dt.and_utc().to_rfc3339()))
+            .unwrap_or(Value::Null),
+        _ => Value::from(value),
+    }
+}
+
+/// Parse an RFC 3339 or `YYYY-MM-DD HH:MM:SS[.fff]` timestamp as UTC
+pub(crate) fn parse_timestamp(v: &str) -> Option<NaiveDateTime> {
+    DateTime::parse_from_rfc3339(v)
+        .map(|dt| dt.naive_utc())
+        .ok()
+        .or_else(|| NaiveDateTime::parse_from_str(v, "%Y-%m-%d %H:%M:%S%.f").ok())
+        .or_else(|| NaiveDateTime::parse_from_str(v, "%Y-%m-%dT%H:%M:%S%.f").ok())
+}
+
+fn parse_parquet(data: Bytes) -> Result<UploadedTable> {
+    let reader = SerializedFileReader::new(data)
+        .map_err(|e| Error::BadRequest(format!("Invalid Parquet file: {}", e)))?;
+
+    let headers: Vec<String> = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .root_schema()
+        .get_fields()
+        .iter()
+        .map(|f| f.name().to_string())
+        .collect();
+    let names = unique_column_names(&headers);
+
+    let mut types: Vec<Option<&'static str>> = vec![None; names.len()];
+    let mut rows = Vec::new();
+
+    let iter = reader
+        .get_row_iter(None)
+        .map_err(|e| Error::BadRequest(format!("Invalid Parquet file: {}", e)))?;
+    for row in iter {
+        let row = row.map_err(|e| Error::BadRequest(format!("Invalid Parquet row: {}", e)))?;
+        let values: Vec<Value> = row
+            .get_column_iter()
+            .enumerate()
+            .map(|(i, (_, field))| {
+                if let (Some(slot), Some(ty)) = (types.get_mut(i), parquet_type(field)) {
+                    slot.get_or_insert(ty);
+                }
+                parquet_value(field)
+            })
+            .collect();
+        rows.push(values);
+    }
+
+    let columns = names
+        .into_iter()
+        .zip(types)
+        .map(|(name, ty)| ColumnDef {
+            name,
+            data_type: ty.unwrap_or("TEXT").to_string(),
+        })
+        .collect();
+
+    Ok(UploadedTable { columns, rows })
+}
+
+fn parquet_type(field: &Field) -> Option<&'static str> {
+    let ty = match field {
+        Field::Null => return None,
+        Field::Bool(_) => "BOOLEAN",
+        Field::Byte(_)
+        | Field::Short(_)
+        | Field::Int(_)
+        | Field::Long(_)
+        | Field::UByte(_)
+        | Field::UShort(_)
+        | Field::UInt(_)
+        | Field::ULong(_) => "INTEGER",
+        Field::Float16(_) | Field::Float(_) | Field::Double(_) | Field::Decimal(_) => "REAL",
+        Field::Date(_) => "DATE",
+        Field::TimeMillis(_) | Field::TimeMicros(_) => "TIME",
+        Field::TimestampMillis(_) | Field::TimestampMicros(_) => "TIMESTAMP",
+        Field::Str(_) | Field::Bytes(_) => "TEXT",
+        Field::Group(_) | Field::ListInternal(_) | Field::MapInternal(_) => "JSON",
+    };
+    Some(ty)
+}
+
+fn parquet_value(field: &Field) -> Value {
+    match field {
+        Field::TimestampMillis(ms) => DateTime::from_timestamp_millis(*ms)
+            .map(|dt| Value::from(dt.to_rfc3339()))
+            .unwrap_or(Value::Null),
+        Field::TimestampMicros(us) => DateTime::from_timestamp_micros(*us)
+            .map(|dt| Value::from(dt.to_rfc3339()))
+            .unwrap_or(Value::Null),
+        Field::Decimal(_) => field
+            .to_json_value()
+            .as_str()
+            .and_then(|s| s.parse::<f64>().ok())
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        _ => field.to_json_value(),
+    }
+}
+
+/// Fill in blank headers and de-duplicate repeated ones
+pub(crate) fn unique_column_names(headers: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(headers.len());
+    for (i, header) in headers.iter().enumerate() {
+        let base = match header.trim() {
+            "" => format!("column_{}", i + 1),
+            h => h.to_string(),
+        };
+        let mut name = base.clone();
+        let mut n = 2;
+        while names.contains(&name) {
+            name = format!("{}_{}", base, n);
+            n += 1;
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// Derive a SQL-friendly table name (lowercase, alphanumeric and underscores)
+pub fn table_name(name: &str) -> String {
+    let mut table: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    table = table.trim_matches('_').to_string();
+    if table.is_empty() || table.starts_with(|c: char| c.is_ascii_digit()) {
+        table = format!("t_{}", table);
+    }
+    table.truncate(63);
+    table
+}
+
+fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// Store an upload for an org and load it into a new SQLite database
+///
+/// Returns the path of the database file. The original file is kept next to it.
+pub async fn store_upload(
+    org_id: Uuid,
+    format: UploadFormat,
+    data: &[u8],
+    table: &str,
+    parsed: &UploadedTable,
+) -> Result<PathBuf> {
+    let root = upload_dir().ok_or_else(|| {
+        Error::BadRequest(format!("File uploads are disabled ({} is not set)", UPLOAD_DIR_ENV))
+    })?;
+    let dir = root.join(org_id.to_string());
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| Error::Internal(format!("Failed to create upload directory: {}", e)))?;
+    // Datasource paths must be absolute
+    let dir = dir
+        .canonicalize()
+        .map_err(|e| Error::Internal(format!("Failed to resolve upload directory: {}", e)))?;
+
+    let id = Uuid::new_v4();
+    let original = dir.join(format!("{}.{}", id, format.extension()));
+    let db_path = dir.join(format!("{}.db", id));
+
+    tokio::fs::write(&original, data)
+        .await
+        .map_err(|e| Error::Internal(format!("Failed to store upload: {}", e)))?;
+
+    if let Err(e) = write_sqlite(&db_path, table, parsed).await {
+        remove_upload_files(&db_path).await;
+        return Err(e);
+    }
+
+    Ok(db_path)
+}
+
+async fn write_sqlite(path: &Path, table: &str, parsed: &UploadedTable) -> Result<()> {
+    let mut conn = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .connect()
+        .await
+        .map_err(|e| Error::Internal(format!("Failed to create upload database: {}", e)))?;
+
+    let column_sql: Vec<String> = parsed
+        .columns
+        .iter()
+        .map(|c| format!("{} {}", quote_ident(&c.name), c.data_type))
+        .collect();
+    let placeholders = vec!["?"; parsed.columns.len()].join(", ");
+    let insert_sql = format!("INSERT INTO {} VALUES ({})", quote_ident(table), placeholders);
+
+    let mut tx = conn.begin().await?;
+
+    sqlx::query(&format!("CREATE TABLE {} ({})", quote_ident(table), column_sql.join(", ")))
+        .execute(&mut *tx)
+        .await?;
+
+    for row in &parsed.rows {
+        let mut query = sqlx::query(&insert_sql);
+        for value in row {
+            query = match value {
+                Value::Null => query.bind(None::<String>),
+                Value::Bool(b) => query.bind(*b),
+                Value::Number(n) => match n.as_i64() {
+                    Some(i) => query.bind(i),
+                    None => query.bind(n.as_f64()),
+                },
+                Value::String(s) => query.bind(s.as_str()),
+                other => query.bind(other.to_string()),
+            };
+        }
+        query.execute(&mut *tx).await?;
+    }
+
+    tx.commit().await?;
+    conn.close().await?;
+
+    Ok(())
+}
+
+/// Whether a SQLite datasource path points into the upload directory
+pub fn is_upload_path(path: &Path) -> bool {
+    upload_dir()
+        .and_then(|dir| dir.canonicalize().ok())
+        .is_some_and(|dir| path.starts_with(dir))
+}
+
+/// Remove a stored upload's database and original file
+///
+/// Paths outside the upload directory are left untouched.
+pub async fn remove_upload_files(db_path: &Path) {
+    let Ok(db_path) = db_path.canonicalize() else {
+        return;
+    };
+    if !is_upload_path(&db_path) {
+        return;
+    }
+    for ext in ["db", "csv", "parquet"] {
+        let _ = tokio::fs::remove_file(db_path.with_extension(ext)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_type_inference() {
+        let data = b"id,name,score,active,day,seen_at\n\
+            1,Alice,9.5,true,2024-01-15,2024-01-15T10:00:00Z\n\
+            2,Bob,,FALSE,2024-02-01,2024-02-01 08:30:00\n";
+
+        let table = parse_upload(UploadFormat::Csv, Bytes::from_static(data)).unwrap();
+        let types: Vec<&str> = table.columns.iter().map(|c| c.data_type.as_str()).collect();
+        assert_eq!(types, vec!["INTEGER", "TEXT", "REAL", "BOOLEAN", "DATE", "TIMESTAMP"]);
+
+        assert_eq!(table.rows[0][0], serde_json::json!(1));
+        assert_eq!(table.rows[0][3], serde_json::json!(true));
+        assert_eq!(table.rows[1][2], Value::Null);
+        assert_eq!(table.rows[1][5], serde_json::json!("2024-02-01T08:30:00+00:00"));
+    }
+
+    #[test]
+    fn test_csv_mixed_column_falls_back_to_text() {
+        let table = parse_upload(UploadFormat::Csv, Bytes::from_static(b"code\n1\nA2\n")).unwrap();
+        assert_eq!(table.columns[0].data_type, "TEXT");
+        assert_eq!(table.rows[0][0], serde_json::json!("1"));
+    }
+
+    #[test]
+    fn test_csv_ragged_rows_rejected() {
+        let result = parse_upload(UploadFormat::Csv, Bytes::from_static(b"a,b\n1,2\n3\n"));
+        assert!(matches!(result, Err(Error::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn test_write_sqlite_is_queryable() {
+        use crate::connectors::{Connector, SqliteConnector};
+
+        let dir = std::env::temp_dir().join(format!("loupe-upload-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("upload.db");
+
+        let parsed = parse_upload(UploadFormat::Csv, Bytes::from_static(b"id,active\n1,true\n2,false\n")).unwrap();
+        write_sqlite(&path, "flags", &parsed).await.unwrap();
+
+        let connector = SqliteConnector::with_allowed_dirs(
+            &format!("sqlite://{}", path.display()),
+            std::slice::from_ref(&dir),
+        )
+        .await
+        .unwrap();
+
+        let schema = connector.get_schema().await.unwrap();
+        assert_eq!(schema[0].name, "flags");
+        assert_eq!(schema[0].columns[1].data_type, "BOOLEAN");
+
+        let output = connector
+            .execute("SELECT * FROM flags ORDER BY id", std::time::Duration::from_secs(5), 10)
+            .await
+            .unwrap();
+        assert_eq!(output.rows[1], vec![serde_json::json!(2), serde_json::json!(false)]);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_unique_column_names() {
+        let headers = vec!["id".to_string(), "".to_string(), "id".to_string()];
+        assert_eq!(unique_column_names(&headers), vec!["id", "column_2", "id_2"]);
+    }
+
+    #[test]
+    fn test_table_name() {
+        assert_eq!(table_name("Q3 Sales (EU).csv"), "q3_sales__eu__csv");
+        assert_eq!(table_name("2024 targets"), "t_2024_targets");
+        assert_eq!(table_name("!!!"), "t_");
+    }
+
+    #[test]
+    fn test_format_from_content_type() {
+        assert_eq!(UploadFormat::from_content_type("text/csv; charset=utf-8"), Some(UploadFormat::Csv));
+        assert_eq!(
+            UploadFormat::from_content_type("application/vnd.apache.parquet"),
+            Some(UploadFormat::Parquet)
+        );
+        assert_eq!(UploadFormat::from_content_type("application/json"), None);
+    }
+}
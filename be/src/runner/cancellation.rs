@@ -0,0 +1,155 @@
+//! Cancellation of in-flight runs
+//!
+//! `POST /runs/{id}/cancel` marks the run cancelled and sends a NOTIFY on
+//! `RUN_CANCELLED_CHANNEL`. The notification listener hands those to the
+//! watcher, which signals the task executing the run; that task drops its
+//! result stream and the connector cancels the query on the datasource.
+//! Notifications can be missed (e.g. while the listener reconnects), so
+//! in-flight runs are also polled for cancellation every `POLL_INTERVAL`.
+
+use loupe::Database;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// How often in-flight runs are checked in case a notification was missed
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Tracks the runs executing on this runner and signals their cancellation
+pub struct CancellationWatcher {
+    db: Database,
+    runs: Mutex<HashMap<Uuid, watch::Sender<bool>>>,
+}
+
+impl CancellationWatcher {
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            runs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start watching a run; it stops being watched when the handle drops
+    pub fn watch(self: &Arc<Self>, run_id: Uuid) -> RunCancellation {
+        let (tx, rx) = watch::channel(false);
+        self.runs.lock().unwrap().insert(run_id, tx);
+        RunCancellation {
+            watcher: Arc::clone(self),
+            run_id,
+            rx,
+        }
+    }
+
+    /// Signal a run's cancellation; returns false if it isn't running here
+    pub fn cancel(&self, run_id: Uuid) -> bool {
+        match self.runs.lock().unwrap().get(&run_id) {
+            Some(tx) => {
+                tx.send_replace(true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Runs currently executing on this runner
+    pub fn watched_ids(&self) -> Vec<Uuid> {
+        self.runs.lock().unwrap().keys().copied().collect()
+    }
+
+    /// Periodically check in-flight runs for missed cancellations
+    pub fn spawn_poller(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let watcher = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                watcher.poll().await;
+            }
+        })
+    }
+
+    async fn poll(&self) {
+        let ids = self.watched_ids();
+        if ids.is_empty() {
+            return;
+        }
+        match self.db.cancelled_run_ids(&ids).await {
+            Ok(cancelled) => {
+                for run_id in cancelled {
+                    if self.cancel(run_id) {
+                        tracing::info!(run_id = %run_id, "Run cancellation found by poll");
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Failed to poll for cancelled runs: {}", e),
+        }
+    }
+}
+
+/// Cancellation handle for one executing run
+pub struct RunCancellation {
+    watcher: Arc<CancellationWatcher>,
+    run_id: Uuid,
+    rx: watch::Receiver<bool>,
+}
+
+impl RunCancellation {
+    pub fn is_cancelled(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    /// Resolve once the run is cancelled
+    pub async fn cancelled(&mut self) {
+        // The sender lives until this handle drops, so this only errors if
+        // the entry was replaced; treat that as never cancelled
+        if self.rx.wait_for(|cancelled| *cancelled).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+impl Drop for RunCancellation {
+    fn drop(&mut self) {
+        self.watcher.runs.lock().unwrap().remove(&self.run_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    fn watcher() -> Arc<CancellationWatcher> {
+        // Never connects; these tests only exercise the in-memory signalling
+        let pool = PgPool::connect_lazy("postgres://localhost/loupe").unwrap();
+        Arc::new(CancellationWatcher::new(Database { pool }))
+    }
+
+    #[tokio::test]
+    async fn test_cancel_signals_watched_run() {
+        let watcher = watcher();
+        let run_id = Uuid::new_v4();
+        let mut cancellation = watcher.watch(run_id);
+        assert!(!cancellation.is_cancelled());
+
+        assert!(watcher.cancel(run_id));
+        tokio::time::timeout(Duration::from_secs(1), cancellation.cancelled())
+            .await
+            .unwrap();
+        assert!(cancellation.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_handle_stops_watching() {
+        let watcher = watcher();
+        let run_id = Uuid::new_v4();
+        let cancellation = watcher.watch(run_id);
+        assert_eq!(watcher.watched_ids(), vec![run_id]);
+
+        drop(cancellation);
+        assert!(watcher.watched_ids().is_empty());
+        assert!(!watcher.cancel(run_id));
+    }
+}
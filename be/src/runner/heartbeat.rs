@@ -0,0 +1,69 @@
+//! Runner heartbeats and reclaiming of abandoned runs
+//!
+//! Each claimed run is leased to its runner for `RUN_LEASE`. The runner
+//! heartbeats every `HEARTBEAT_INTERVAL`, renewing the leases on the runs it
+//! is executing (those the `CancellationWatcher` is watching). A runner that
+//! crashes stops renewing, as does one whose task for a run ended without
+//! finishing it, and whichever runner reaps next puts those runs back in the
+//! queue (or fails them once out of retries).
+
+use crate::cancellation::CancellationWatcher;
+use loupe::models::RunStatus;
+use loupe::{Database, Metrics, RUN_LEASE};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often leases are renewed; several heartbeats fit in one lease so a
+/// slow or failed heartbeat doesn't lose the runner its runs
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(RUN_LEASE.as_secs() / 6);
+
+/// Heartbeat and reap expired leases until the runner shuts down
+pub fn spawn(
+    db: Database,
+    metrics: Arc<Metrics>,
+    runner_id: String,
+    runs: Arc<CancellationWatcher>,
+) -> tokio::task::JoinHandle<()> {
+    let hostname = std::env::var("HOSTNAME").ok();
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let run_ids = runs.watched_ids();
+            if let Err(e) = db
+                .runner_heartbeat(&runner_id, hostname.as_deref(), &run_ids)
+                .await
+            {
+                tracing::warn!("Runner heartbeat failed: {}", e);
+            }
+
+            match db.reclaim_expired_runs().await {
+                Ok(reclaimed) => record_reclaimed(&metrics, &reclaimed),
+                Err(e) => tracing::warn!("Failed to reclaim expired runs: {}", e),
+            }
+        }
+    })
+}
+
+fn record_reclaimed(metrics: &Metrics, runs: &[loupe::models::Run]) {
+    for run in runs {
+        let outcome = if run.status == RunStatus::Queued {
+            "requeued"
+        } else {
+            "failed"
+        };
+        metrics
+            .runs_reclaimed_total
+            .with_label_values(&[outcome])
+            .inc();
+        tracing::warn!(
+            run_id = %run.id,
+            query_id = %run.query_id,
+            retry_count = run.retry_count,
+            outcome,
+            "Reclaimed run from unresponsive runner"
+        );
+    }
+}
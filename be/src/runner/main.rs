@@ -1,19 +1,28 @@
-use loupe::connectors::{Connector, PostgresConnector};
-use loupe::models::DatasourceType;
+mod cancellation;
+mod heartbeat;
+mod notifications;
+
+use cancellation::{CancellationWatcher, RunCancellation};
+use loupe::connectors::{DEFAULT_BATCH_SIZE, PluginRegistry, collect_rows, retry_class};
+use loupe::models::RetryClass;
 use loupe::params::TypedValue;
 use loupe::{
-    Database, Metrics, ObservabilityConfig, QueryLimiter, QueryLimits, init_tracing, load_env,
+    ConnectorCache, ConnectorCacheConfig, Database, Error, LimiterBackend, Metrics,
+    ObservabilityConfig, QueryLimiter, QueryLimits, ResultStore, init_tracing, load_env,
 };
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::signal;
-use tokio::sync::broadcast;
+use tokio::sync::{Notify, broadcast};
 use tokio::task::JoinSet;
 
-const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(5); // Fallback when no notification arrives
 const MAX_CONCURRENT_RUNS: usize = 4;
 const SLOW_QUERY_THRESHOLD_MS: i64 = 1000; // Log queries slower than 1 second
 const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30); // Grace period for in-flight tasks
+const DEFAULT_MAX_RESULT_BYTES: usize = 64 * 1024 * 1024; // Serialized result size cap
+const THROTTLE_BACKOFF: Duration = Duration::from_secs(2); // Requeue delay when the query limiter is full
+const DEFAULT_THROTTLE_MAX_WAIT: Duration = Duration::from_secs(300); // Throttled runs fail after this long
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -39,13 +48,58 @@ async fn main() -> anyhow::Result<()> {
 
     // Initialize query limiter
     let query_limits = QueryLimits::from_env();
-    let query_limiter = Arc::new(QueryLimiter::new(query_limits.clone()));
+    // Shared slots make the limits hold across all runners, not per process
+    let limiter_backend = LimiterBackend::from_env();
+    let query_limiter = Arc::new(match limiter_backend {
+        LimiterBackend::Postgres => {
+            QueryLimiter::postgres(query_limits.clone(), db.clone(), runner_id.clone())
+        }
+        LimiterBackend::Local => QueryLimiter::new(query_limits.clone()),
+    });
     tracing::info!(
-        "Query limiter initialized: max {} per org, {} global",
+        "Query limiter initialized ({:?}): max {} per org, {} global",
+        limiter_backend,
         query_limits.max_concurrent_per_org,
         query_limits.max_concurrent_global
     );
 
+    // Load connector plugins up front so registry problems surface at startup
+    PluginRegistry::global();
+
+    // Keep datasource connection pools warm between runs
+    let connector_config = ConnectorCacheConfig::from_env();
+    tracing::info!(
+        "Connector cache initialized: max {} datasources, {}s idle timeout",
+        connector_config.max_entries,
+        connector_config.idle_timeout.as_secs()
+    );
+    let connectors = Arc::new(ConnectorCache::new(connector_config, metrics.clone()));
+    connectors.spawn_sweeper();
+
+    // Large results go to object storage when one is configured
+    let results = Arc::new(ResultStore::from_env(db.clone())?);
+    tracing::info!(
+        "Result store initialized (offloading large results: {})",
+        results.offloads()
+    );
+
+    // Abort runs cancelled through the API while they execute here
+    let cancellations = Arc::new(CancellationWatcher::new(db.clone()));
+    cancellations.spawn_poller();
+
+    // Keep leases on the runs executing here alive and reclaim runs from
+    // dead runners
+    heartbeat::spawn(
+        db.clone(),
+        metrics.clone(),
+        runner_id.clone(),
+        cancellations.clone(),
+    );
+
+    // Wake up as soon as a run is queued instead of polling the queue
+    let work_available = Arc::new(Notify::new());
+    notifications::spawn_listener(db.clone(), work_available.clone(), cancellations.clone());
+
     // Create shutdown channel
     let (shutdown_tx, _) = broadcast::channel(1);
     let mut shutdown_rx = shutdown_tx.subscribe();
@@ -63,6 +117,10 @@ async fn main() -> anyhow::Result<()> {
     // Task set for tracking spawned jobs
     let mut tasks = JoinSet::new();
 
+    // Whether the last claim found the queue empty; while it has work the
+    // runner keeps claiming, otherwise it waits for a notification
+    let mut idle = false;
+
     // Main polling loop
     loop {
         tokio::select! {
@@ -92,6 +150,11 @@ async fn main() -> anyhow::Result<()> {
                     }
                 }
 
+                // In-flight runs still leased to us are reclaimed once the lease lapses
+                if let Err(e) = db.deregister_runner(&runner_id).await {
+                    tracing::warn!("Failed to deregister runner: {}", e);
+                }
+
                 tracing::info!("Graceful shutdown complete");
                 return Ok(());
             }
@@ -101,10 +164,12 @@ async fn main() -> anyhow::Result<()> {
                 if let Err(e) = result {
                     tracing::error!("Task panicked: {}", e);
                 }
+                // A finished run may free a datasource slot that queued runs wait on
+                idle = false;
             }
 
             // Claim new work if under concurrency limit
-            _ = tokio::time::sleep(POLL_INTERVAL), if tasks.len() < MAX_CONCURRENT_RUNS => {
+            _ = wait_for_work(&work_available, idle), if tasks.len() < MAX_CONCURRENT_RUNS => {
                 // First try to claim a retry run (prioritize retries)
                 let run_result = db.claim_retry_run(&runner_id).await;
                 let (run, _run_type) = match run_result {
@@ -139,15 +204,20 @@ async fn main() -> anyhow::Result<()> {
                 };
 
                 // If we got a run (either retry or new), execute it
+                idle = run.is_none();
                 if let Some(run) = run {
                     let db_clone = db.clone();
                     let metrics_clone = metrics.clone();
                     let limiter_clone = query_limiter.clone();
+                    let connectors_clone = connectors.clone();
+                    let results_clone = results.clone();
+                    let cancellation = cancellations.watch(run.id);
+                    let runner_id = runner_id.clone();
                     let run_id = run.id;
 
                     // Spawn task to execute the run
                     tasks.spawn(async move {
-                        let result = execute_run(&db_clone, &metrics_clone, &limiter_clone, &run).await;
+                        let result = execute_run(&db_clone, &metrics_clone, &limiter_clone, &connectors_clone, &results_clone, &runner_id, cancellation, &run).await;
                         if let Err(e) = result {
                             tracing::error!("Run {} failed: {}", run_id, e);
                         }
@@ -158,6 +228,18 @@ async fn main() -> anyhow::Result<()> {
     }
 }
 
+/// Wait until the queue may have work: immediately while it's busy, else
+/// until a run is queued or the idle poll interval passes
+async fn wait_for_work(work_available: &Notify, idle: bool) {
+    if !idle {
+        return;
+    }
+    tokio::select! {
+        _ = work_available.notified() => {}
+        _ = tokio::time::sleep(IDLE_POLL_INTERVAL) => {}
+    }
+}
+
 /// Wait for SIGTERM or SIGINT (Ctrl+C)
 async fn shutdown_signal() {
     let ctrl_c = async {
@@ -183,22 +265,70 @@ async fn shutdown_signal() {
     }
 }
 
+/// How long a run may keep being throttled before it fails, from
+/// `THROTTLE_MAX_WAIT_SECS`
+fn throttle_max_wait() -> Duration {
+    std::env::var("THROTTLE_MAX_WAIT_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_THROTTLE_MAX_WAIT)
+}
+
+/// Maximum serialized size of a stored result, from `MAX_RESULT_BYTES`
+fn max_result_bytes() -> usize {
+    std::env::var("MAX_RESULT_BYTES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_MAX_RESULT_BYTES)
+}
+
+/// Byte limit for a run: its own limit, but never above the runner's
+fn run_max_bytes(run_max_bytes: Option<i64>, runner_max_bytes: usize) -> usize {
+    run_max_bytes
+        .and_then(|bytes| usize::try_from(bytes).ok())
+        .map_or(runner_max_bytes, |bytes| bytes.min(runner_max_bytes))
+}
+
 async fn execute_run(
     db: &Database,
     metrics: &Arc<Metrics>,
     limiter: &Arc<QueryLimiter>,
+    connectors: &Arc<ConnectorCache>,
+    results: &ResultStore,
+    runner_id: &str,
+    mut cancellation: RunCancellation,
     run: &loupe::models::Run,
 ) -> anyhow::Result<()> {
     // Try to acquire a query execution slot
-    let _guard = match limiter.try_acquire(run.org_id) {
+    let _guard = match limiter.try_acquire(run.org_id).await {
         Ok(guard) => {
             tracing::debug!("Acquired query slot for org {}", run.org_id);
             guard
         }
         Err(e) => {
-            // Query limit reached - fail the run
-            let error_msg = format!("Query limit reached: {}", e);
-            db.fail_run(run.id, &error_msg).await?;
+            // Query limit reached - back to the queue until a slot frees up
+            metrics.runs_throttled_total.inc();
+            let max_wait = throttle_max_wait();
+            if db
+                .requeue_throttled_run(run.id, runner_id, THROTTLE_BACKOFF, max_wait)
+                .await?
+                .is_some()
+            {
+                tracing::debug!("Run {} throttled, requeued: {}", run.id, e);
+                return Ok(());
+            }
+
+            // Waited too long for a slot
+            let error_msg = format!(
+                "Query limit reached and no slot freed up within {}s: {}",
+                max_wait.as_secs(),
+                e
+            );
+            if db.fail_run(run.id, runner_id, &error_msg).await?.is_none() {
+                record_lost(run);
+                return Ok(());
+            }
             tracing::warn!("Run {} rejected: {}", run.id, error_msg);
             metrics
                 .query_executions_total
@@ -208,17 +338,42 @@ async fn execute_run(
         }
     };
 
-    // Increment in-flight queries
+    // From here on every outcome is recorded as completed, failed or retried
+    // rather than returned as an error. If recording it fails too, the run is
+    // no longer heartbeated once this returns, so its lease lapses and it is
+    // reclaimed.
     metrics.queries_in_flight.inc();
     let start = std::time::Instant::now();
 
-    // Get the datasource
-    let datasource = db.get_datasource(run.datasource_id, run.org_id).await?;
-
-    // Create connector based on type
-    let connector: Box<dyn Connector> = match datasource.ds_type {
-        DatasourceType::Postgres => {
-            Box::new(PostgresConnector::new(&datasource.connection_string_encrypted).await?)
+    // Look up what the run needs; a retry wouldn't fix problems here
+    let setup = async {
+        let datasource = db.get_datasource(run.datasource_id, run.org_id).await?;
+        let params = parse_bound_params(&run.parameters)?;
+        Ok::<_, Error>((datasource, params))
+    };
+    let (datasource, params) = match setup.await {
+        Ok(setup) => setup,
+        Err(e) => {
+            metrics.queries_in_flight.dec();
+            metrics
+                .query_executions_total
+                .with_label_values(&["failed"])
+                .inc();
+            if db
+                .fail_run(run.id, runner_id, &e.to_string())
+                .await?
+                .is_none()
+            {
+                record_lost(run);
+                return Ok(());
+            }
+            tracing::error!(
+                run_id = %run.id,
+                query_id = %run.query_id,
+                error = %e,
+                "Run failed before its query could start"
+            );
+            return Ok(());
         }
     };
 
@@ -226,21 +381,46 @@ async fn execute_run(
     let timeout = Duration::from_secs(run.timeout_seconds as u64);
     let max_rows = run.max_rows as usize;
 
-    // Parse bound parameters from run.parameters
-    let params = parse_bound_params(&run.parameters)?;
+    // Stream the result, stopping early once it outgrows the byte limit.
+    // The run's own limit (already capped by the org) can only lower the
+    // runner-wide one.
+    let max_bytes = run_max_bytes(run.max_bytes, max_result_bytes());
+    let read = async {
+        // Reuse the cached connector for this datasource config, connecting
+        // on a miss. Failing to connect is classified and retried like any
+        // other execution error.
+        let connector = connectors.get(&datasource).await?;
+        // One row past the limit tells a truncated result from one that
+        // fits exactly
+        let stream = connector
+            .execute_stream(
+                &run.executed_sql,
+                &params,
+                timeout,
+                max_rows + 1,
+                DEFAULT_BATCH_SIZE,
+            )
+            .await?;
+        collect_rows(stream, max_rows, max_bytes).await
+    };
 
-    // Execute with or without parameters
-    let result = if params.is_empty() {
-        connector
-            .execute(&run.executed_sql, timeout, max_rows)
-            .await
-    } else {
-        connector
-            .execute_with_params(&run.executed_sql, &params, timeout, max_rows)
-            .await
+    // Dropping the read on cancellation drops the stream, which cancels the
+    // query on the datasource
+    let result = tokio::select! {
+        result = read => result,
+        _ = cancellation.cancelled() => {
+            record_cancelled(metrics, run);
+            return Ok(());
+        }
     };
 
-    let execution_result = match result {
+    // A cancel that arrived as the query finished still wins
+    if cancellation.is_cancelled() {
+        record_cancelled(metrics, run);
+        return Ok(());
+    }
+
+    match result {
         Ok(output) => {
             let execution_time_ms = start.elapsed().as_millis() as i64;
             let execution_time_secs = execution_time_ms as f64 / 1000.0;
@@ -272,32 +452,89 @@ async fn execute_run(
                 );
             }
 
-            // Serialize results
-            let columns = serde_json::to_value(&output.columns)?;
-            let rows = serde_json::to_value(&output.rows)?;
-            let byte_count = serde_json::to_string(&rows)?.len() as i64;
-
-            // Store result
-            let result = db
-                .create_run_result(
-                    run.id,
-                    &columns,
-                    &rows,
-                    output.row_count as i64,
-                    byte_count,
-                    execution_time_ms,
-                )
-                .await?;
-
-            // Mark run as completed
-            db.complete_run(run.id, result.id).await?;
-
-            tracing::info!(
-                "Run {} completed: {} rows in {}ms",
-                run.id,
-                output.row_count,
-                execution_time_ms
-            );
+            if let Some(reason) = output.truncated {
+                tracing::warn!(
+                    run_id = %run.id,
+                    query_id = %run.query_id,
+                    rows = output.row_count,
+                    max_rows,
+                    max_bytes,
+                    ?reason,
+                    "Result truncated"
+                );
+            }
+
+            let stored = async {
+                // Rows arrive already serialized
+                let columns = serde_json::to_value(&output.columns)
+                    .map_err(|e| Error::Internal(format!("Failed to serialize columns: {}", e)))?;
+
+                // Store result, inline or in object storage depending on size
+                let result = results
+                    .put(
+                        run.id,
+                        &columns,
+                        &output.rows,
+                        output.row_count as i64,
+                        execution_time_ms,
+                        output.truncated,
+                    )
+                    .await?;
+
+                // Mark run as completed, unless it was cancelled or
+                // reclaimed in the meantime
+                db.complete_run(run.id, runner_id, result.id).await
+            };
+
+            match stored.await {
+                Ok(Some(_)) => {
+                    tracing::info!(
+                        "Run {} completed: {} rows in {}ms",
+                        run.id,
+                        output.row_count,
+                        execution_time_ms
+                    );
+                }
+                Ok(None) => record_lost(run),
+                Err(e) => {
+                    // Storage being unreachable is retried like a lost
+                    // datasource connection, if the run's policy allows it
+                    let error_msg = format!("Failed to store result: {}", e);
+                    let policy = &run.retry_policy;
+                    let retry = if policy.retries(RetryClass::Connection) {
+                        db.schedule_retry(
+                            run.id,
+                            runner_id,
+                            &error_msg,
+                            policy.backoff(run.retry_count),
+                            policy.jitter,
+                        )
+                        .await?
+                    } else {
+                        None
+                    };
+
+                    if let Some(retry_run) = retry {
+                        tracing::warn!(
+                            run_id = %run.id,
+                            query_id = %run.query_id,
+                            retry_count = retry_run.retry_count,
+                            next_retry_at = ?retry_run.next_retry_at,
+                            error = %e,
+                            "Run failed storing its result, scheduled for retry"
+                        );
+                    } else if db.fail_run(run.id, runner_id, &error_msg).await?.is_none() {
+                        record_lost(run);
+                    } else {
+                        tracing::error!(
+                            run_id = %run.id,
+                            query_id = %run.query_id,
+                            error = %e,
+                            "Run failed storing its result"
+                        );
+                    }
+                }
+            }
 
             Ok(())
         }
@@ -307,11 +544,20 @@ async fn execute_run(
             // Decrement in-flight counter
             metrics.queries_in_flight.dec();
 
-            // Determine if error is retryable (connection errors, database unavailable, etc.)
-            let is_retryable = is_retryable_error(&error_msg);
+            // Don't keep handing out a connector whose datasource went away
+            if matches!(e, Error::Connection(..)) {
+                connectors.invalidate(run.datasource_id);
+            }
+
+            // Retry transient failures (lost connections, deadlocks, overload),
+            // judged from the datasource's error code, if the run's policy
+            // covers them
+            let policy = &run.retry_policy;
+            let is_retryable = retry_class(&e).is_some_and(|class| policy.retries(class));
+            let backoff = policy.backoff(run.retry_count);
 
             // Check if it was a timeout
-            if error_msg.contains("timed out") {
+            if matches!(e, Error::Timeout(_)) {
                 metrics
                     .query_executions_total
                     .with_label_values(&["timeout"])
@@ -319,33 +565,38 @@ async fn execute_run(
                 metrics.query_timeouts_total.inc();
 
                 // Try to schedule retry for timeouts
-                if is_retryable {
-                    match db
-                        .schedule_retry(run.id, &format!("Query timed out: {}", error_msg))
-                        .await?
-                    {
-                        Some(retry_run) => {
-                            tracing::warn!(
-                                run_id = %run.id,
-                                query_id = %run.query_id,
-                                retry_count = retry_run.retry_count,
-                                next_retry_at = ?retry_run.next_retry_at,
-                                "Run timed out, scheduled for retry"
-                            );
-                        }
-                        None => {
-                            // Max retries exceeded, move to dead letter queue
-                            db.timeout_run(run.id).await?;
-                            db.move_to_dead_letter_queue(run.id).await?;
-                            tracing::error!(
-                                run_id = %run.id,
-                                query_id = %run.query_id,
-                                "Run timed out, max retries exceeded, moved to dead letter queue"
-                            );
-                        }
-                    }
+                let retry = if is_retryable {
+                    db.schedule_retry(
+                        run.id,
+                        runner_id,
+                        &format!("Query timed out: {}", error_msg),
+                        backoff,
+                        policy.jitter,
+                    )
+                    .await?
+                } else {
+                    None
+                };
+
+                if let Some(retry_run) = retry {
+                    tracing::warn!(
+                        run_id = %run.id,
+                        query_id = %run.query_id,
+                        retry_count = retry_run.retry_count,
+                        next_retry_at = ?retry_run.next_retry_at,
+                        "Run timed out, scheduled for retry"
+                    );
+                } else if db.timeout_run(run.id, runner_id).await?.is_none() {
+                    record_lost(run);
+                } else if is_retryable {
+                    // Max retries exceeded, move to dead letter queue
+                    db.move_to_dead_letter_queue(run.id).await?;
+                    tracing::error!(
+                        run_id = %run.id,
+                        query_id = %run.query_id,
+                        "Run timed out, max retries exceeded, moved to dead letter queue"
+                    );
                 } else {
-                    db.timeout_run(run.id).await?;
                     tracing::warn!(
                         run_id = %run.id,
                         query_id = %run.query_id,
@@ -354,7 +605,10 @@ async fn execute_run(
                 }
             } else if is_retryable {
                 // Try to schedule retry for retryable errors
-                match db.schedule_retry(run.id, &error_msg).await? {
+                match db
+                    .schedule_retry(run.id, runner_id, &error_msg, backoff, policy.jitter)
+                    .await?
+                {
                     Some(retry_run) => {
                         metrics
                             .query_executions_total
@@ -367,16 +621,20 @@ async fn execute_run(
                             max_retries = retry_run.max_retries,
                             next_retry_at = ?retry_run.next_retry_at,
                             error = %error_msg,
+                            code = ?e.code(),
                             "Run failed, scheduled for retry"
                         );
                     }
                     None => {
                         // Max retries exceeded, move to dead letter queue
+                        if db.fail_run(run.id, runner_id, &error_msg).await?.is_none() {
+                            record_lost(run);
+                            return Ok(());
+                        }
                         metrics
                             .query_executions_total
                             .with_label_values(&["failed_permanent"])
                             .inc();
-                        db.fail_run(run.id, &error_msg).await?;
                         db.move_to_dead_letter_queue(run.id).await?;
                         tracing::error!(
                             run_id = %run.id,
@@ -392,69 +650,52 @@ async fn execute_run(
                     .query_executions_total
                     .with_label_values(&["failed"])
                     .inc();
-                db.fail_run(run.id, &error_msg).await?;
+                if db.fail_run(run.id, runner_id, &error_msg).await?.is_none() {
+                    record_lost(run);
+                    return Ok(());
+                }
                 tracing::error!(
                     run_id = %run.id,
                     query_id = %run.query_id,
                     error = %error_msg,
+                    code = ?e.code(),
                     "Run failed (non-retryable error)"
                 );
             }
 
             Ok(())
         }
-    };
+    }
+}
 
-    execution_result
+/// Log a run that stopped being this runner's before it could be finished:
+/// it was cancelled, or its lease lapsed and it was reclaimed
+fn record_lost(run: &loupe::models::Run) {
+    tracing::info!(
+        run_id = %run.id,
+        query_id = %run.query_id,
+        "Run was cancelled or reclaimed before it could finish; leaving it as it is"
+    );
 }
 
-/// Determine if an error is retryable
-///
-/// Retryable errors include:
-/// - Connection errors (network issues, database unavailable)
-/// - Transient errors (deadlocks, timeout on acquire)
-/// - Resource exhaustion (too many connections)
+/// Account for a run abandoned because it was cancelled
 ///
-/// Non-retryable errors include:
-/// - SQL syntax errors
-/// - Invalid credentials
-/// - Permission denied
-/// - Data type mismatches
-fn is_retryable_error(error_msg: &str) -> bool {
-    let lower = error_msg.to_lowercase();
-
-    // Retryable errors
-    if lower.contains("connection")
-        || lower.contains("network")
-        || lower.contains("timeout")
-        || lower.contains("unavailable")
-        || lower.contains("deadlock")
-        || lower.contains("too many connections")
-        || lower.contains("could not connect")
-        || lower.contains("connection refused")
-        || lower.contains("connection reset")
-    {
-        return true;
-    }
-
-    // Non-retryable errors
-    if lower.contains("syntax error")
-        || lower.contains("permission denied")
-        || lower.contains("authentication failed")
-        || lower.contains("invalid credentials")
-        || lower.contains("does not exist")
-        || lower.contains("type mismatch")
-        || lower.contains("constraint violation")
-    {
-        return false;
-    }
-
-    // Default to non-retryable for unknown errors
-    false
+/// The API already marked the run cancelled, so there is nothing to store.
+fn record_cancelled(metrics: &Metrics, run: &loupe::models::Run) {
+    metrics
+        .query_executions_total
+        .with_label_values(&["cancelled"])
+        .inc();
+    metrics.queries_in_flight.dec();
+    tracing::info!(
+        run_id = %run.id,
+        query_id = %run.query_id,
+        "Run cancelled, query aborted"
+    );
 }
 
 /// Parse bound parameters from JSON array stored in run.parameters
-fn parse_bound_params(params_json: &serde_json::Value) -> anyhow::Result<Vec<TypedValue>> {
+fn parse_bound_params(params_json: &serde_json::Value) -> loupe::Result<Vec<TypedValue>> {
     let arr = match params_json.as_array() {
         Some(a) => a,
         None => return Ok(vec![]), // Empty or object = no bound params
@@ -465,7 +706,7 @@ fn parse_bound_params(params_json: &serde_json::Value) -> anyhow::Result<Vec<Typ
         let type_str = item
             .get("type")
             .and_then(|t| t.as_str())
-            .ok_or_else(|| anyhow::anyhow!("Parameter missing 'type' field"))?;
+            .ok_or_else(|| Error::BadRequest("Parameter missing 'type' field".to_string()))?;
 
         let value = item.get("value");
 
@@ -479,17 +720,22 @@ fn parse_bound_params(params_json: &serde_json::Value) -> anyhow::Result<Vec<Typ
             "date" => {
                 let s = value.and_then(|v| v.as_str()).unwrap_or("");
                 let date = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
-                    .map_err(|e| anyhow::anyhow!("Invalid date '{}': {}", s, e))?;
+                    .map_err(|e| Error::BadRequest(format!("Invalid date '{}': {}", s, e)))?;
                 TypedValue::Date(date)
             }
             "datetime" => {
                 let s = value.and_then(|v| v.as_str()).unwrap_or("");
                 let dt = chrono::DateTime::parse_from_rfc3339(s)
-                    .map_err(|e| anyhow::anyhow!("Invalid datetime '{}': {}", s, e))?;
+                    .map_err(|e| Error::BadRequest(format!("Invalid datetime '{}': {}", s, e)))?;
                 TypedValue::DateTime(dt.with_timezone(&chrono::Utc))
             }
             "null" => TypedValue::Null,
-            other => return Err(anyhow::anyhow!("Unknown parameter type: {}", other)),
+            other => {
+                return Err(Error::BadRequest(format!(
+                    "Unknown parameter type: {}",
+                    other
+                )));
+            }
         };
 
         params.push(typed);
@@ -0,0 +1,76 @@
+//! Postgres notifications for the runner
+//!
+//! One LISTEN connection per runner receives both newly queued runs
+//! (`RUNS_CHANNEL`, sent by `create_run`) and cancellations
+//! (`RUN_CANCELLED_CHANNEL`). Queued runs wake the claim loop so they start
+//! within milliseconds; the loop otherwise only polls every
+//! `IDLE_POLL_INTERVAL`, which also covers retries and any notification lost
+//! while the listener was disconnected.
+
+use crate::cancellation::CancellationWatcher;
+use loupe::{Database, RUN_CANCELLED_CHANNEL, RUNS_CHANNEL};
+use sqlx::postgres::PgListener;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Delay before retrying a listener that couldn't connect
+const RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Listen for run notifications, waking `work` and signalling cancellations
+pub fn spawn_listener(
+    db: Database,
+    work: Arc<Notify>,
+    cancellations: Arc<CancellationWatcher>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let mut listener = match connect(&db).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to LISTEN for run notifications, polling only for {:?}: {}",
+                        RECONNECT_DELAY,
+                        e
+                    );
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                    continue;
+                }
+            };
+            tracing::info!("Listening for run notifications");
+
+            // Runs may have been queued while we weren't listening
+            work.notify_one();
+
+            loop {
+                match listener.recv().await {
+                    Ok(notification) if notification.channel() == RUNS_CHANNEL => {
+                        work.notify_one();
+                    }
+                    Ok(notification) => {
+                        if let Ok(run_id) = notification.payload().parse()
+                            && cancellations.cancel(run_id)
+                        {
+                            tracing::info!(run_id = %run_id, "Run cancellation received");
+                        }
+                    }
+                    Err(e) => {
+                        // Reconnect; anything sent in between is found by the
+                        // wake-up below or the next poll
+                        tracing::warn!("Run notification listener error: {}", e);
+                        work.notify_one();
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+async fn connect(db: &Database) -> sqlx::Result<PgListener> {
+    let mut listener = PgListener::connect_with(&db.pool).await?;
+    listener
+        .listen_all([RUNS_CHANNEL, RUN_CANCELLED_CHANNEL])
+        .await?;
+    Ok(listener)
+}
@@ -1,4 +1,9 @@
-use loupe::{ObservabilityConfig, init_tracing, load_env, Database};
+mod retention;
+
+use actix_web::{App, HttpResponse, HttpServer, web};
+use loupe::{ObservabilityConfig, init_tracing, load_env, Database, Metrics, ResultStore};
+use loupe::models::{RetryPolicy, RunPriority};
+use std::sync::Arc;
 use std::time::Duration;
 
 const DEFAULT_POLL_INTERVAL_SECS: u64 = 10;
@@ -24,6 +29,26 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Connecting to database...");
 
     let db = Database::connect(&database_url).await?;
+    let metrics = Arc::new(Metrics::new().expect("Failed to create metrics registry"));
+
+    // Retention cleanup (0 disables it)
+    let cleanup_interval = std::env::var("RETENTION_CLEANUP_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .map_or(retention::DEFAULT_CLEANUP_INTERVAL, Duration::from_secs);
+    if cleanup_interval.is_zero() {
+        tracing::info!("Retention cleanup disabled");
+    } else {
+        // Offloaded results are deleted from the same store the runner writes to
+        let results = ResultStore::from_env(db.clone())?;
+        retention::spawn(db.clone(), results, metrics.clone(), cleanup_interval);
+        tracing::info!("Retention cleanup every {}s", cleanup_interval.as_secs());
+    }
+
+    if let Ok(addr) = std::env::var("SCHEDULER_METRICS_ADDR") {
+        serve_metrics(&addr, metrics.clone())?;
+        tracing::info!("Serving scheduler metrics on {}/metrics", addr);
+    }
 
     tracing::info!("Scheduler ready, polling every {}s", poll_interval);
 
@@ -35,6 +60,33 @@ async fn main() -> anyhow::Result<()> {
     }
 }
 
+/// Expose the scheduler's metrics for Prometheus at `addr`
+fn serve_metrics(addr: &str, metrics: Arc<Metrics>) -> std::io::Result<()> {
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(web::Data::new(metrics.clone()))
+            .route("/metrics", web::get().to(render_metrics))
+    })
+    .workers(1)
+    .disable_signals()
+    .bind(addr)?
+    .run();
+    tokio::spawn(server);
+    Ok(())
+}
+
+async fn render_metrics(metrics: web::Data<Arc<Metrics>>) -> HttpResponse {
+    match metrics.render() {
+        Ok(output) => HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4; charset=utf-8")
+            .body(output),
+        Err(e) => {
+            tracing::error!("Failed to render metrics: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
 async fn poll_and_enqueue(db: &Database) -> anyhow::Result<()> {
     let schedules = db.get_due_schedules().await?;
 
@@ -61,6 +113,15 @@ async fn poll_and_enqueue(db: &Database) -> anyhow::Result<()> {
             }
         };
 
+        // The org's result size ceiling applies to scheduled runs too
+        let max_bytes = match db.get_organization(org_id).await {
+            Ok(org) => org.cap_result_bytes(query.max_bytes),
+            Err(e) => {
+                tracing::error!("Failed to load org {} for schedule {}: {}", org_id, schedule_id, e);
+                continue;
+            }
+        };
+
         let run = match db
             .create_run(
                 org_id,
@@ -70,7 +131,10 @@ async fn poll_and_enqueue(db: &Database) -> anyhow::Result<()> {
                 &schedule.parameters,
                 query.timeout_seconds,
                 query.max_rows,
+                max_bytes,
                 schedule.created_by,
+                RunPriority::Scheduled,
+                &RetryPolicy::resolve(&query, Some(&schedule)),
             )
             .await
         {
@@ -0,0 +1,116 @@
+//! Retention cleanup of run history
+//!
+//! Every `RETENTION_CLEANUP_INTERVAL_SECS` the scheduler deletes results that
+//! have expired or fall outside their query's `keep_last` (with any rows
+//! offloaded to object storage), then finished runs and dead-lettered
+//! failures past their retention period. Deletes go in batches so a large
+//! backlog doesn't hold long locks on the tables runners are writing to.
+
+use loupe::{Database, Metrics, ResultStore};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often cleanup runs by default
+pub const DEFAULT_CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Rows deleted per statement
+const BATCH_SIZE: i64 = 1000;
+
+/// What one cleanup pass deleted
+#[derive(Debug, Default)]
+struct Deleted {
+    run_results: u64,
+    result_objects: u64,
+    runs: u64,
+    run_failures: u64,
+}
+
+/// Clean up every `interval` until the scheduler shuts down
+pub fn spawn(
+    db: Database,
+    results: ResultStore,
+    metrics: Arc<Metrics>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            let (deleted, result) = cleanup(&db, &results).await;
+            record(&metrics, &deleted);
+            match result {
+                Ok(()) => {
+                    metrics
+                        .retention_last_cleanup_timestamp_seconds
+                        .set(chrono::Utc::now().timestamp());
+                    tracing::info!(
+                        run_results = deleted.run_results,
+                        result_objects = deleted.result_objects,
+                        runs = deleted.runs,
+                        run_failures = deleted.run_failures,
+                        "Retention cleanup finished"
+                    );
+                }
+                Err(e) => tracing::error!("Retention cleanup failed: {}", e),
+            }
+        }
+    })
+}
+
+/// Run one cleanup pass; what was deleted before an error is still reported
+async fn cleanup(db: &Database, results: &ResultStore) -> (Deleted, loupe::Result<()>) {
+    let mut deleted = Deleted::default();
+    let result = async {
+        // Results first: runs are only deleted once their result is gone
+        loop {
+            let keys = db.delete_expired_run_results(BATCH_SIZE).await?;
+            deleted.run_results += keys.len() as u64;
+            for key in keys.iter().flatten() {
+                // An object left behind only costs storage; keep going
+                match results.delete_object(key).await {
+                    Ok(()) => deleted.result_objects += 1,
+                    Err(e) => tracing::warn!(key = %key, "Failed to delete expired result object: {}", e),
+                }
+            }
+            if (keys.len() as i64) < BATCH_SIZE {
+                break;
+            }
+        }
+
+        loop {
+            let count = db.delete_expired_runs(BATCH_SIZE).await?;
+            deleted.runs += count;
+            if (count as i64) < BATCH_SIZE {
+                break;
+            }
+        }
+
+        loop {
+            let count = db.delete_expired_run_failures(BATCH_SIZE).await?;
+            deleted.run_failures += count;
+            if (count as i64) < BATCH_SIZE {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+    .await;
+
+    (deleted, result)
+}
+
+fn record(metrics: &Metrics, deleted: &Deleted) {
+    for (kind, count) in [
+        ("run_results", deleted.run_results),
+        ("result_objects", deleted.result_objects),
+        ("runs", deleted.runs),
+        ("run_failures", deleted.run_failures),
+    ] {
+        metrics
+            .retention_deleted_total
+            .with_label_values(&[kind])
+            .inc_by(count);
+    }
+}
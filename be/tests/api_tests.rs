@@ -169,6 +169,7 @@ mod routes {
                 req.ds_type.clone(),
                 &req.connection_string, // Not encrypting for tests
                 req.user_id,
+                None,
             )
             .await?;
         Ok(HttpResponse::Created().json(DatasourceResponse::from(ds)))
@@ -251,7 +252,9 @@ mod routes {
                 &serde_json::json!([]),
                 req.timeout_seconds,
                 req.max_rows,
+                None,
                 req.user_id,
+                None,
             )
             .await?;
         Ok(HttpResponse::Created().json(QueryResponse::from(query)))
@@ -529,12 +532,12 @@ mod datasource_tests {
         // Create some datasources
         test_app
             .db
-            .create_datasource(org_id, "DS 1", DatasourceType::Postgres, "conn1", user_id)
+            .create_datasource(org_id, "DS 1", DatasourceType::Postgres, "conn1", user_id, None)
             .await
             .unwrap();
         test_app
             .db
-            .create_datasource(org_id, "DS 2", DatasourceType::Postgres, "conn2", user_id)
+            .create_datasource(org_id, "DS 2", DatasourceType::Postgres, "conn2", user_id, None)
             .await
             .unwrap();
 
@@ -581,6 +584,7 @@ mod query_tests {
                 DatasourceType::Postgres,
                 "postgres://localhost/test",
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -645,7 +649,9 @@ mod query_tests {
                 &json!([]),
                 30,
                 1000,
+                None,
                 user_id,
+                None,
             )
             .await
             .unwrap();
@@ -661,7 +667,9 @@ mod query_tests {
                 &json!([]),
                 30,
                 1000,
+                None,
                 user_id,
+                None,
             )
             .await
             .unwrap();
@@ -74,6 +74,7 @@ impl DatasourceFixture {
             DatasourceType::Postgres,
             conn_string,
             created_by,
+            None,
         )
         .await
         .unwrap()
@@ -96,6 +97,7 @@ impl DatasourceFixture {
             DatasourceType::Postgres,
             connection_string,
             created_by,
+            None,
         )
         .await
         .unwrap()
@@ -129,7 +131,9 @@ impl QueryFixture {
             &serde_json::json!([]),
             30,
             10000,
+            None,
             created_by,
+            None,
         )
         .await
         .unwrap()
@@ -158,7 +162,9 @@ impl QueryFixture {
             &serde_json::json!([]),
             30,
             10000,
+            None,
             created_by,
+            None,
         )
         .await
         .unwrap()
@@ -184,7 +190,10 @@ impl RunFixture {
             &serde_json::json!({}),
             30,
             10000,
+            None,
             created_by,
+            RunPriority::Interactive,
+            &RetryPolicy::default(),
         )
         .await
         .unwrap()
@@ -266,6 +275,7 @@ impl ScheduleFixture {
             &serde_json::json!([]),
             true,
             created_by,
+            None,
         )
         .await
         .unwrap()
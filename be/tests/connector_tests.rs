@@ -41,6 +41,12 @@ impl TestConnector {
     fn connector(&self) -> &PostgresConnector {
         &self.connector
     }
+
+    /// Run a statement outside the connector, e.g. to set up tables
+    async fn admin_execute(&self, sql: &str) {
+        let pool = sqlx::PgPool::connect(&self.connection_string).await.unwrap();
+        sqlx::raw_sql(sql).execute(&pool).await.unwrap();
+    }
 }
 
 mod test_connection {
@@ -195,6 +201,70 @@ mod execute_tests {
         assert!(err.contains("timeout") || err.contains("Timeout"));
     }
 
+    #[tokio::test]
+    async fn test_server_side_timeout() {
+        let test = TestConnector::new().await;
+
+        // statement_timeout cancels the statement on the server, reported as
+        // a timeout rather than a query error
+        let result = test.connector()
+            .execute("SELECT pg_sleep(5)", Duration::from_millis(200), 100)
+            .await;
+        assert!(matches!(result, Err(loupe::Error::Timeout(_))), "{:?}", result);
+
+        // Nothing is left running on the datasource
+        let pool = sqlx::PgPool::connect(&test.connection_string).await.unwrap();
+        let running: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pg_stat_activity WHERE query LIKE '%pg_sleep(5)%' AND state = 'active' AND pid <> pg_backend_pid()",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(running, 0);
+    }
+
+    #[tokio::test]
+    async fn test_runs_are_read_only() {
+        let test = TestConnector::new().await;
+        test.admin_execute(
+            "CREATE TABLE items (id INT); \
+             CREATE FUNCTION add_item() RETURNS INT VOLATILE LANGUAGE sql AS 'INSERT INTO items VALUES (1) RETURNING id'",
+        )
+        .await;
+
+        // Writes hidden from the wrapping SELECT, e.g. in a function, are refused
+        let result = test.connector()
+            .execute("SELECT add_item() AS id", Duration::from_secs(10), 100)
+            .await;
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("read-only transaction"), "{}", err);
+
+        let result = test.connector()
+            .execute("SELECT COUNT(*) AS n FROM items", Duration::from_secs(10), 100)
+            .await
+            .unwrap();
+        assert_eq!(result.rows[0][0], serde_json::json!(0));
+    }
+
+    #[tokio::test]
+    async fn test_limits_are_local_to_the_run() {
+        let test = TestConnector::new().await;
+
+        let result = test.connector()
+            .execute("SELECT current_setting('statement_timeout') AS t, current_setting('transaction_read_only') AS ro", Duration::from_secs(7), 100)
+            .await
+            .unwrap();
+        assert_eq!(result.rows[0][0], serde_json::json!("7s"));
+        assert_eq!(result.rows[0][1], serde_json::json!("on"));
+
+        // Each run sets its own limits on the pooled connection
+        let result = test.connector()
+            .execute("SELECT current_setting('lock_timeout') AS t", Duration::from_secs(3), 100)
+            .await
+            .unwrap();
+        assert_eq!(result.rows[0][0], serde_json::json!("3s"));
+    }
+
     #[tokio::test]
     async fn test_execution_time_tracked() {
         let test = TestConnector::new().await;
@@ -227,15 +297,9 @@ mod schema_tests {
     async fn test_get_schema_with_tables() {
         let test = TestConnector::new().await;
         
-        // Create a test table
-        test.connector()
-            .execute(
-                "CREATE TABLE test_users (id SERIAL PRIMARY KEY, name TEXT NOT NULL, age INT)",
-                Duration::from_secs(10),
-                1,
-            )
-            .await
-            .unwrap();
+        // Create a test table (runs are read-only, so not through the connector)
+        test.admin_execute("CREATE TABLE test_users (id SERIAL PRIMARY KEY, name TEXT NOT NULL, age INT)")
+            .await;
 
         let schema = test.connector().get_schema().await.unwrap();
         
@@ -257,6 +321,85 @@ mod schema_tests {
     }
 }
 
+mod stream_tests {
+    use super::*;
+    use futures_util::TryStreamExt;
+    use loupe::connectors::{RowBatch, collect_rows};
+    use loupe::models::TruncationReason;
+
+    #[tokio::test]
+    async fn test_execute_stream_batches() {
+        let test = TestConnector::new().await;
+
+        let stream = test.connector()
+            .execute_stream("SELECT generate_series(1, 25) AS n", &[], Duration::from_secs(10), 1000, 10)
+            .await
+            .unwrap();
+        let batches: Vec<RowBatch> = stream.try_collect().await.unwrap();
+
+        let sizes: Vec<usize> = batches.iter().map(|b| b.rows.len()).collect();
+        assert_eq!(sizes, vec![10, 10, 5]);
+        assert_eq!(batches[0].columns[0].name, "n");
+    }
+
+    #[tokio::test]
+    async fn test_collect_stops_at_byte_limit() {
+        let test = TestConnector::new().await;
+
+        let stream = test.connector()
+            .execute_stream("SELECT generate_series(1, 1000000) AS n", &[], Duration::from_secs(30), 1_000_000, 1000)
+            .await
+            .unwrap();
+        let collected = collect_rows(stream, 1_000_000, 1024).await.unwrap();
+
+        assert_eq!(collected.truncated, Some(TruncationReason::MaxBytes));
+        assert!(collected.byte_count() <= 1024);
+        assert!(collected.row_count < 1000);
+    }
+
+    #[tokio::test]
+    async fn test_byte_limit_cancels_the_query() {
+        let test = TestConnector::new().await;
+
+        // Draining the whole result would take minutes
+        let stream = test.connector()
+            .execute_stream("SELECT n, pg_sleep(0.002) FROM generate_series(1, 100000) AS n", &[], Duration::from_secs(300), 1_000_000, 10)
+            .await
+            .unwrap();
+        let collected = collect_rows(stream, 1_000_000, 256).await.unwrap();
+        assert_eq!(collected.truncated, Some(TruncationReason::MaxBytes));
+
+        let pool = sqlx::PgPool::connect(&test.connection_string).await.unwrap();
+        let mut running = 1;
+        for _ in 0..50 {
+            running = sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM pg_stat_activity WHERE query LIKE '%pg_sleep(0.002)%' AND state = 'active' AND pid <> pg_backend_pid()",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            if running == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(running, 0);
+    }
+
+    #[tokio::test]
+    async fn test_stream_timeout() {
+        let test = TestConnector::new().await;
+
+        let stream = test.connector()
+            .execute_stream("SELECT pg_sleep(2)", &[], Duration::from_millis(100), 100, 100)
+            .await
+            .unwrap();
+        let result: Result<Vec<RowBatch>, _> = stream.try_collect().await;
+
+        assert!(result.unwrap_err().to_string().contains("timed out"));
+    }
+}
+
 mod concurrent_tests {
     use super::*;
     use tokio::task::JoinSet;
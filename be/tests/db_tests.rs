@@ -74,6 +74,22 @@ mod organization_tests {
         let result = db.get_organization(Uuid::new_v4()).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_set_organization_max_result_bytes() {
+        let test_db = TestDb::new().await;
+        let db = test_db.database();
+
+        let org = db.create_organization("My Company").await.unwrap();
+        assert_eq!(org.max_result_bytes, None);
+
+        let org = db.set_organization_max_result_bytes(org.id, Some(1024)).await.unwrap();
+        assert_eq!(org.max_result_bytes, Some(1024));
+        assert_eq!(org.cap_result_bytes(Some(4096)), Some(1024));
+
+        let org = db.set_organization_max_result_bytes(org.id, None).await.unwrap();
+        assert_eq!(org.max_result_bytes, None);
+    }
 }
 
 mod user_tests {
@@ -214,6 +230,7 @@ mod datasource_tests {
                 DatasourceType::Postgres,
                 "encrypted_conn_string",
                 user.id,
+                Some(4),
             )
             .await
             .unwrap();
@@ -222,6 +239,7 @@ mod datasource_tests {
         assert_eq!(ds.ds_type, DatasourceType::Postgres);
         assert_eq!(ds.org_id, org.id);
         assert_eq!(ds.created_by, user.id);
+        assert_eq!(ds.max_concurrent_runs, Some(4));
     }
 
     #[tokio::test]
@@ -229,10 +247,10 @@ mod datasource_tests {
         let (test_db, org, user) = setup().await;
         let db = test_db.database();
 
-        db.create_datasource(org.id, "DS 1", DatasourceType::Postgres, "conn1", user.id)
+        db.create_datasource(org.id, "DS 1", DatasourceType::Postgres, "conn1", user.id, None)
             .await
             .unwrap();
-        db.create_datasource(org.id, "DS 2", DatasourceType::Postgres, "conn2", user.id)
+        db.create_datasource(org.id, "DS 2", DatasourceType::Postgres, "conn2", user.id, None)
             .await
             .unwrap();
 
@@ -252,17 +270,38 @@ mod datasource_tests {
                 DatasourceType::Postgres,
                 "conn",
                 user.id,
+                None,
             )
             .await
             .unwrap();
 
         let updated = db
-            .update_datasource(ds.id, org.id, Some("Renamed"), None)
+            .update_datasource(ds.id, org.id, Some("Renamed"), None, None)
             .await
             .unwrap();
 
         assert_eq!(updated.name, "Renamed");
         assert_eq!(updated.connection_string_encrypted, "conn"); // unchanged
+        assert_eq!(updated.config_version, ds.config_version); // rename keeps pooled connectors
+
+        let updated = db
+            .update_datasource(ds.id, org.id, None, Some("conn2"), None)
+            .await
+            .unwrap();
+        assert_eq!(updated.config_version, ds.config_version + 1);
+        assert_eq!(updated.max_concurrent_runs, None);
+
+        let limited = db
+            .update_datasource(ds.id, org.id, None, None, Some(Some(2)))
+            .await
+            .unwrap();
+        assert_eq!(limited.max_concurrent_runs, Some(2));
+
+        let cleared = db
+            .update_datasource(ds.id, org.id, None, None, Some(None))
+            .await
+            .unwrap();
+        assert_eq!(cleared.max_concurrent_runs, None);
     }
 
     #[tokio::test]
@@ -277,6 +316,7 @@ mod datasource_tests {
                 DatasourceType::Postgres,
                 "conn",
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -300,6 +340,7 @@ mod datasource_tests {
                 DatasourceType::Postgres,
                 "conn",
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -326,7 +367,7 @@ mod query_tests {
             .await
             .unwrap();
         let ds = db
-            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id)
+            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id, None)
             .await
             .unwrap();
         (test_db, org, user, ds)
@@ -348,7 +389,9 @@ mod query_tests {
                 &serde_json::json!({}),
                 10000,
                 30,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -380,7 +423,9 @@ mod query_tests {
                 &serde_json::json!([]),
                 60,
                 5000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -404,7 +449,9 @@ mod query_tests {
                 &serde_json::json!([]),
                 30,
                 10000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -420,6 +467,8 @@ mod query_tests {
                 None,
                 Some(60),
                 None,
+                None,
+                None,
             )
             .await
             .unwrap();
@@ -447,7 +496,9 @@ mod query_tests {
                 &serde_json::json!([]),
                 30,
                 10000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -471,7 +522,7 @@ mod run_tests {
             .await
             .unwrap();
         let ds = db
-            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id)
+            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id, None)
             .await
             .unwrap();
         let query = db
@@ -485,7 +536,9 @@ mod run_tests {
                 &serde_json::json!([]),
                 30,
                 10000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -506,7 +559,10 @@ mod run_tests {
                 &serde_json::json!({}),
                 30,
                 10000,
+                None,
                 user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
             )
             .await
             .unwrap();
@@ -516,6 +572,40 @@ mod run_tests {
         assert_eq!(run.created_by, user.id);
     }
 
+    #[tokio::test]
+    async fn test_create_run_notifies_runners() {
+        let (test_db, org, user, ds, query) = setup().await;
+        let db = test_db.database();
+
+        let mut listener = sqlx::postgres::PgListener::connect_with(&db.pool)
+            .await
+            .unwrap();
+        listener.listen(loupe::RUNS_CHANNEL).await.unwrap();
+
+        let run = db
+            .create_run(
+                org.id,
+                query.id,
+                ds.id,
+                "SELECT 1",
+                &serde_json::json!({}),
+                30,
+                10000,
+                None,
+                user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap();
+
+        let notification = tokio::time::timeout(std::time::Duration::from_secs(5), listener.recv())
+            .await
+            .expect("no notification received")
+            .unwrap();
+        assert_eq!(notification.payload(), run.id.to_string());
+    }
+
     #[tokio::test]
     async fn test_claim_run() {
         let (test_db, org, user, ds, query) = setup().await;
@@ -530,7 +620,10 @@ mod run_tests {
                 &serde_json::json!({}),
                 30,
                 10000,
+                None,
                 user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
             )
             .await
             .unwrap();
@@ -545,6 +638,300 @@ mod run_tests {
         assert_eq!(claimed.runner_id.as_deref(), Some(runner_id));
     }
 
+    #[tokio::test]
+    async fn test_claim_respects_datasource_limit() {
+        let (test_db, org, user, ds, query) = setup().await;
+        let db = test_db.database();
+
+        db.update_datasource(ds.id, org.id, None, None, Some(Some(1)))
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            db.create_run(
+                org.id,
+                query.id,
+                ds.id,
+                "SELECT 1",
+                &serde_json::json!({}),
+                30,
+                10000,
+                None,
+                user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let first = db.claim_run("runner-1").await.unwrap().unwrap();
+        assert!(db.claim_run("runner-2").await.unwrap().is_none());
+
+        // The slot frees up once the first run finishes
+        db.fail_run(first.id, "runner-1", "boom").await.unwrap().unwrap();
+        assert!(db.claim_run("runner-2").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_claim_round_robins_between_orgs() {
+        let (test_db, org, user, ds, query) = setup().await;
+        let db = test_db.database();
+
+        let other_org = db.create_organization("Other Org").await.unwrap();
+        let other_user = db
+            .create_user(other_org.id, "other@example.com", "hash", "Other", OrgRole::Admin)
+            .await
+            .unwrap();
+        let other_ds = db
+            .create_datasource(other_org.id, "Other DS", DatasourceType::Postgres, "conn", other_user.id, None)
+            .await
+            .unwrap();
+        let other_query = db
+            .create_query(
+                other_org.id,
+                other_ds.id,
+                "Other Query",
+                None,
+                "SELECT 1",
+                &serde_json::json!([]),
+                &serde_json::json!([]),
+                30,
+                10000,
+                None,
+                other_user.id,
+                None,
+            )
+            .await
+            .unwrap();
+
+        // The first org queues a backlog before the second org's single run
+        for _ in 0..3 {
+            db.create_run(
+                org.id,
+                query.id,
+                ds.id,
+                "SELECT 1",
+                &serde_json::json!({}),
+                30,
+                10000,
+                None,
+                user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap();
+        }
+        db.create_run(
+            other_org.id,
+            other_query.id,
+            other_ds.id,
+            "SELECT 1",
+            &serde_json::json!({}),
+            30,
+            10000,
+            None,
+            other_user.id,
+            RunPriority::Interactive,
+            &RetryPolicy::default(),
+        )
+        .await
+        .unwrap();
+
+        let first = db.claim_run("runner-1").await.unwrap().unwrap();
+        let second = db.claim_run("runner-1").await.unwrap().unwrap();
+        assert_ne!(first.org_id, second.org_id);
+    }
+
+    #[tokio::test]
+    async fn test_claim_prefers_higher_priority() {
+        let (test_db, org, user, ds, query) = setup().await;
+        let db = test_db.database();
+
+        for priority in [RunPriority::Backfill, RunPriority::Scheduled, RunPriority::Interactive] {
+            db.create_run(
+                org.id,
+                query.id,
+                ds.id,
+                "SELECT 1",
+                &serde_json::json!({}),
+                30,
+                10000,
+                None,
+                user.id,
+                priority,
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let claimed = db.claim_run("runner-1").await.unwrap().unwrap();
+        assert_eq!(claimed.priority, RunPriority::Interactive);
+        let claimed = db.claim_run("runner-1").await.unwrap().unwrap();
+        assert_eq!(claimed.priority, RunPriority::Scheduled);
+
+        let depths = db.get_queue_depth_by_priority().await.unwrap();
+        assert_eq!(depths, vec![(RunPriority::Backfill, 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_claim_ages_low_priority_runs() {
+        let (test_db, org, user, ds, query) = setup().await;
+        let db = test_db.database();
+
+        let backfill = db
+            .create_run(
+                org.id,
+                query.id,
+                ds.id,
+                "SELECT 1",
+                &serde_json::json!({}),
+                30,
+                10000,
+                None,
+                user.id,
+                RunPriority::Backfill,
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap();
+        db.create_run(
+            org.id,
+            query.id,
+            ds.id,
+            "SELECT 1",
+            &serde_json::json!({}),
+            30,
+            10000,
+            None,
+            user.id,
+            RunPriority::Interactive,
+            &RetryPolicy::default(),
+        )
+        .await
+        .unwrap();
+
+        // Waiting long enough promotes the backfill run to the top lane,
+        // where it's older than the interactive run
+        sqlx::query("UPDATE runs SET created_at = NOW() - INTERVAL '30 minutes' WHERE id = $1")
+            .bind(backfill.id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let claimed = db.claim_run("runner-1").await.unwrap().unwrap();
+        assert_eq!(claimed.id, backfill.id);
+    }
+
+    #[tokio::test]
+    async fn test_run_takes_max_bytes() {
+        let (test_db, org, user, ds, query) = setup().await;
+        let db = test_db.database();
+
+        let query = db
+            .update_query(query.id, org.id, None, None, None, None, None, None, None, Some(Some(2048)), None)
+            .await
+            .unwrap();
+        assert_eq!(query.max_bytes, Some(2048));
+
+        let run = db
+            .create_run(
+                org.id,
+                query.id,
+                ds.id,
+                "SELECT 1",
+                &serde_json::json!({}),
+                30,
+                10000,
+                query.max_bytes,
+                user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(run.max_bytes, Some(2048));
+
+        let query = db
+            .update_query(query.id, org.id, None, None, None, None, None, None, None, Some(None), None)
+            .await
+            .unwrap();
+        assert_eq!(query.max_bytes, None);
+    }
+
+    #[tokio::test]
+    async fn test_run_takes_retry_policy() {
+        let (test_db, org, user, ds, query) = setup().await;
+        let db = test_db.database();
+
+        let policy = RetryPolicy {
+            max_retries: 1,
+            base_delay_seconds: 5,
+            ..RetryPolicy::default()
+        };
+        let query = db
+            .update_query(query.id, org.id, None, None, None, None, None, None, None, None, Some(Some(&policy)))
+            .await
+            .unwrap();
+        let run = db
+            .create_run(
+                org.id,
+                query.id,
+                ds.id,
+                "SELECT 1",
+                &serde_json::json!({}),
+                30,
+                10000,
+                None,
+                user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::resolve(&query, None),
+            )
+            .await
+            .unwrap();
+        assert_eq!(run.max_retries, 1);
+        assert_eq!(run.retry_policy.0, policy);
+
+        db.claim_run("runner-1").await.unwrap().unwrap();
+        let backoff = policy.backoff(run.retry_count);
+        let retry = db
+            .schedule_retry(run.id, "runner-1", "deadlock", backoff, policy.jitter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(retry.retry_count, 1);
+        let delay = retry.next_retry_at.unwrap() - chrono::Utc::now();
+        assert!(delay <= chrono::Duration::seconds(5));
+
+        // Out of retries, the run is left for the runner to fail
+        sqlx::query("UPDATE runs SET next_retry_at = NOW() WHERE id = $1")
+            .bind(run.id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        db.claim_retry_run("runner-1").await.unwrap().unwrap();
+        let retry = db
+            .schedule_retry(run.id, "runner-1", "deadlock", backoff, policy.jitter)
+            .await
+            .unwrap();
+        assert!(retry.is_none());
+        let failed = db.fail_run(run.id, "runner-1", "deadlock").await.unwrap().unwrap();
+        assert_eq!(failed.retry_count, 1);
+
+        // Left alone unless given, cleared with Some(None)
+        let query = db
+            .update_query(query.id, org.id, Some("Renamed"), None, None, None, None, None, None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(query.retry_policy.as_deref(), Some(&policy));
+        let query = db
+            .update_query(query.id, org.id, None, None, None, None, None, None, None, None, Some(None))
+            .await
+            .unwrap();
+        assert!(query.retry_policy.is_none());
+    }
+
     #[tokio::test]
     async fn test_claim_empty_queue() {
         let test_db = TestDb::new().await;
@@ -568,7 +955,10 @@ mod run_tests {
                 &serde_json::json!({}),
                 30,
                 10000,
+                None,
                 user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
             )
             .await
             .unwrap();
@@ -585,42 +975,478 @@ mod run_tests {
                 1,
                 4,
                 5,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let completed = db.complete_run(run.id, "runner-1", result.id).await.unwrap().unwrap();
+
+        assert_eq!(completed.status, RunStatus::Completed);
+        assert!(completed.completed_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_fail_run() {
+        let (test_db, org, user, ds, query) = setup().await;
+        let db = test_db.database();
+
+        let run = db
+            .create_run(
+                org.id,
+                query.id,
+                ds.id,
+                "SELECT 1",
+                &serde_json::json!({}),
+                30,
+                10000,
+                None,
+                user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap();
+
+        // Claim it first
+        db.claim_run("runner-1").await.unwrap();
+
+        let failed = db
+            .fail_run(run.id, "runner-1", "Connection refused")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(failed.status, RunStatus::Failed);
+        assert_eq!(failed.error_message.as_deref(), Some("Connection refused"));
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_renews_leases() {
+        let (test_db, org, user, ds, query) = setup().await;
+        let db = test_db.database();
+
+        db.create_run(
+            org.id,
+            query.id,
+            ds.id,
+            "SELECT 1",
+            &serde_json::json!({}),
+            30,
+            10000,
+            None,
+            user.id,
+            RunPriority::Interactive,
+            &RetryPolicy::default(),
+        )
+        .await
+        .unwrap();
+        let claimed = db.claim_run("runner-1").await.unwrap().unwrap();
+        assert!(claimed.lease_expires_at.is_some());
+
+        assert_eq!(
+            db.runner_heartbeat("runner-1", Some("host-a"), &[claimed.id]).await.unwrap(),
+            1
+        );
+        // Runs the runner isn't executing anymore, or never held, lapse
+        assert_eq!(db.runner_heartbeat("runner-1", Some("host-a"), &[]).await.unwrap(), 0);
+        assert_eq!(db.runner_heartbeat("runner-2", None, &[claimed.id]).await.unwrap(), 0);
+        assert_eq!(db.count_active_runners().await.unwrap(), 2);
+
+        db.deregister_runner("runner-2").await.unwrap();
+        assert_eq!(db.count_active_runners().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reclaim_expired_runs() {
+        let (test_db, org, user, ds, query) = setup().await;
+        let db = test_db.database();
+
+        let run = db
+            .create_run(
+                org.id,
+                query.id,
+                ds.id,
+                "SELECT 1",
+                &serde_json::json!({}),
+                30,
+                10000,
+                None,
+                user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
             )
             .await
             .unwrap();
+        db.claim_run("runner-1").await.unwrap();
+
+        // A live lease is left alone
+        assert!(db.reclaim_expired_runs().await.unwrap().is_empty());
+
+        let expire = || {
+            sqlx::query("UPDATE runs SET lease_expires_at = NOW() - INTERVAL '1 second' WHERE id = $1")
+                .bind(run.id)
+                .execute(&db.pool)
+        };
+
+        // With retries left the run goes back to the queue
+        expire().await.unwrap();
+        let reclaimed = db.reclaim_expired_runs().await.unwrap();
+        assert_eq!(reclaimed.len(), 1);
+        assert_eq!(reclaimed[0].status, RunStatus::Queued);
+        assert_eq!(reclaimed[0].retry_count, 1);
+        assert!(reclaimed[0].runner_id.is_none());
+
+        // Out of retries it fails
+        sqlx::query("UPDATE runs SET retry_count = max_retries WHERE id = $1")
+            .bind(run.id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        db.claim_run("runner-2").await.unwrap().unwrap();
+        expire().await.unwrap();
+        let reclaimed = db.reclaim_expired_runs().await.unwrap();
+        assert_eq!(reclaimed[0].status, RunStatus::Failed);
+        assert!(reclaimed[0].completed_at.is_some());
+        assert!(reclaimed[0].error_message.as_deref().unwrap().contains("runner-2"));
+    }
+
+    #[tokio::test]
+    async fn test_reclaimed_run_is_fenced_from_old_runner() {
+        let (test_db, org, user, ds, query) = setup().await;
+        let db = test_db.database();
+
+        let run = db
+            .create_run(
+                org.id,
+                query.id,
+                ds.id,
+                "SELECT 1",
+                &serde_json::json!({}),
+                30,
+                10000,
+                None,
+                user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap();
+        db.claim_run("runner-1").await.unwrap().unwrap();
+        sqlx::query("UPDATE runs SET lease_expires_at = NOW() - INTERVAL '1 second' WHERE id = $1")
+            .bind(run.id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        db.reclaim_expired_runs().await.unwrap();
+        db.claim_run("runner-2").await.unwrap().unwrap();
+
+        // The runner that lost the lease can't finish the run
+        assert!(db.complete_run(run.id, "runner-1", Uuid::new_v4()).await.unwrap().is_none());
+        assert!(db.fail_run(run.id, "runner-1", "boom").await.unwrap().is_none());
+        assert!(db.timeout_run(run.id, "runner-1").await.unwrap().is_none());
+        assert!(db
+            .schedule_retry(run.id, "runner-1", "boom", std::time::Duration::from_secs(1), 0.0)
+            .await
+            .unwrap()
+            .is_none());
+        let current = db.get_run(run.id, org.id).await.unwrap();
+        assert_eq!(current.status, RunStatus::Running);
+        assert_eq!(current.runner_id.as_deref(), Some("runner-2"));
+
+        let completed = db.complete_run(run.id, "runner-2", Uuid::new_v4()).await.unwrap();
+        assert_eq!(completed.unwrap().status, RunStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_requeue_throttled_run() {
+        let (test_db, org, user, ds, query) = setup().await;
+        let db = test_db.database();
+
+        let run = db
+            .create_run(
+                org.id,
+                query.id,
+                ds.id,
+                "SELECT 1",
+                &serde_json::json!({}),
+                30,
+                10000,
+                None,
+                user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap();
+        db.claim_run("runner-1").await.unwrap();
+
+        let wait = std::time::Duration::from_secs(300);
+        let requeued = db
+            .requeue_throttled_run(run.id, "runner-1", std::time::Duration::from_secs(60), wait)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(requeued.status, RunStatus::Queued);
+        assert_eq!(requeued.retry_count, 0);
+        assert!(requeued.first_throttled_at.is_some());
+
+        // Not claimable until the backoff passes
+        assert!(db.claim_run("runner-1").await.unwrap().is_none());
+        sqlx::query("UPDATE runs SET throttled_until = NOW() WHERE id = $1")
+            .bind(run.id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        db.claim_run("runner-1").await.unwrap().unwrap();
+
+        // Past the max wait it is left for the runner to fail
+        sqlx::query("UPDATE runs SET first_throttled_at = NOW() - INTERVAL '1 hour' WHERE id = $1")
+            .bind(run.id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert!(db
+            .requeue_throttled_run(run.id, "runner-1", std::time::Duration::from_secs(2), wait)
+            .await
+            .unwrap()
+            .is_none());
+
+        // A retry starts the wait over
+        let retry = db
+            .schedule_retry(run.id, "runner-1", "deadlock", std::time::Duration::from_secs(1), 0.0)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(retry.first_throttled_at.is_none());
+        assert!(retry.throttled_until.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_run_is_not_resurrected() {
+        let (test_db, org, user, ds, query) = setup().await;
+        let db = test_db.database();
+
+        let run = db
+            .create_run(
+                org.id,
+                query.id,
+                ds.id,
+                "SELECT 1",
+                &serde_json::json!({}),
+                30,
+                10000,
+                None,
+                user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap();
+        db.claim_run("runner-1").await.unwrap();
+
+        let cancelled = db.cancel_run(run.id).await.unwrap().unwrap();
+        assert_eq!(cancelled.status, RunStatus::Cancelled);
+        assert_eq!(db.cancelled_run_ids(&[run.id]).await.unwrap(), vec![run.id]);
+
+        // The runner finishing afterwards doesn't overwrite the cancellation
+        let result = db
+            .create_run_result(run.id, &serde_json::json!([]), &serde_json::json!([]), 0, 2, 5, None)
+            .await
+            .unwrap();
+        assert!(db.complete_run(run.id, "runner-1", result.id).await.unwrap().is_none());
+        assert!(db.fail_run(run.id, "runner-1", "Connection refused").await.unwrap().is_none());
+        assert!(db.timeout_run(run.id, "runner-1").await.unwrap().is_none());
+        let retry = db
+            .schedule_retry(
+                run.id,
+                "runner-1",
+                "Connection reset",
+                std::time::Duration::from_secs(30),
+                0.0,
+            )
+            .await
+            .unwrap();
+        assert!(retry.is_none());
+        assert_eq!(db.get_run(run.id, org.id).await.unwrap().status, RunStatus::Cancelled);
+
+        // Finished runs can't be cancelled again
+        assert!(db.cancel_run(run.id).await.unwrap().is_none());
+    }
+}
+
+mod run_failure_tests {
+    use super::*;
+
+    /// Dead-letter one run of a fresh query; returns the failure's run id
+    async fn setup() -> (TestDb, Organization, User, Query, Uuid) {
+        let test_db = TestDb::new().await;
+        let db = test_db.database();
+        let org = db.create_organization("Test Org").await.unwrap();
+        let user = db
+            .create_user(org.id, "admin@example.com", "hash", "Admin", OrgRole::Admin)
+            .await
+            .unwrap();
+        let ds = db
+            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id, None)
+            .await
+            .unwrap();
+        let query = db
+            .create_query(
+                org.id,
+                ds.id,
+                "Test Query",
+                None,
+                "SELECT 1",
+                &serde_json::json!([]),
+                &serde_json::json!([]),
+                30,
+                10000,
+                None,
+                user.id,
+                None,
+            )
+            .await
+            .unwrap();
+        let run = db
+            .create_run(
+                org.id,
+                query.id,
+                ds.id,
+                "SELECT $1",
+                &serde_json::json!([{"type": "number", "value": 1}]),
+                45,
+                500,
+                None,
+                user.id,
+                RunPriority::Scheduled,
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap();
+        db.claim_run("runner-1").await.unwrap().unwrap();
+        db.fail_run(run.id, "runner-1", "Connection refused").await.unwrap().unwrap();
+        db.move_to_dead_letter_queue(run.id).await.unwrap();
+        (test_db, org, user, query, run.id)
+    }
+
+    #[tokio::test]
+    async fn test_dead_letter_keeps_run_limits() {
+        let (test_db, org, _user, query, run_id) = setup().await;
+        let db = test_db.database();
+
+        let (failures, total) = db
+            .list_run_failures_paginated(org.id, None, None, None, None, "created_at", "DESC", 20, 0)
+            .await
+            .unwrap();
+        assert_eq!(total, 1);
+        let failure = db.get_run_failure(failures[0].id, org.id).await.unwrap();
+        assert_eq!(failure.run_id, run_id);
+        assert_eq!(failure.query_id, query.id);
+        assert_eq!(failure.executed_sql, "SELECT $1");
+        assert_eq!(failure.error_message, "Connection refused");
+        assert_eq!(failure.timeout_seconds, Some(45));
+        assert_eq!(failure.max_rows, Some(500));
+        assert!(failure.replayed_run_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_run_failures_filters() {
+        let (test_db, org, _user, query, _) = setup().await;
+        let db = test_db.database();
+
+        let (_, total) = db
+            .list_run_failures_paginated(org.id, Some(query.id), None, None, None, "created_at", "DESC", 20, 0)
+            .await
+            .unwrap();
+        assert_eq!(total, 1);
+
+        let (_, total) = db
+            .list_run_failures_paginated(org.id, None, Some(Uuid::new_v4()), None, None, "created_at", "DESC", 20, 0)
+            .await
+            .unwrap();
+        assert_eq!(total, 0);
 
-        let completed = db.complete_run(run.id, result.id).await.unwrap();
+        let future = chrono::Utc::now() + chrono::Duration::hours(1);
+        let (_, total) = db
+            .list_run_failures_paginated(org.id, None, None, Some(future), None, "created_at", "DESC", 20, 0)
+            .await
+            .unwrap();
+        assert_eq!(total, 0);
 
-        assert_eq!(completed.status, RunStatus::Completed);
-        assert!(completed.completed_at.is_some());
+        // Other orgs see nothing
+        let other = db.create_organization("Other Org").await.unwrap();
+        let (_, total) = db
+            .list_run_failures_paginated(other.id, None, None, None, None, "created_at", "DESC", 20, 0)
+            .await
+            .unwrap();
+        assert_eq!(total, 0);
     }
 
     #[tokio::test]
-    async fn test_fail_run() {
-        let (test_db, org, user, ds, query) = setup().await;
+    async fn test_mark_run_failure_replayed() {
+        let (test_db, org, user, query, _) = setup().await;
         let db = test_db.database();
 
+        let (failures, _) = db
+            .list_run_failures_paginated(org.id, None, None, None, None, "created_at", "DESC", 20, 0)
+            .await
+            .unwrap();
         let run = db
             .create_run(
                 org.id,
                 query.id,
-                ds.id,
-                "SELECT 1",
-                &serde_json::json!({}),
+                query.datasource_id,
+                &failures[0].executed_sql,
+                &failures[0].parameters,
                 30,
                 10000,
+                None,
                 user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
             )
             .await
             .unwrap();
 
-        // Claim it first
-        db.claim_run("runner-1").await.unwrap();
+        let failure = db
+            .mark_run_failure_replayed(failures[0].id, org.id, run.id)
+            .await
+            .unwrap();
+        assert_eq!(failure.replayed_run_id, Some(run.id));
+        assert!(failure.replayed_at.is_some());
+    }
 
-        let failed = db.fail_run(run.id, "Connection refused").await.unwrap();
+    #[tokio::test]
+    async fn test_purge_run_failures() {
+        let (test_db, org, _user, query, _) = setup().await;
+        let db = test_db.database();
 
-        assert_eq!(failed.status, RunStatus::Failed);
-        assert_eq!(failed.error_message.as_deref(), Some("Connection refused"));
+        // Criteria are combined
+        let purged = db
+            .purge_run_failures(org.id, None, Some(query.id), Some(Uuid::new_v4()), None)
+            .await
+            .unwrap();
+        assert_eq!(purged, 0);
+
+        let past = chrono::Utc::now() - chrono::Duration::hours(1);
+        let purged = db
+            .purge_run_failures(org.id, None, None, None, Some(past))
+            .await
+            .unwrap();
+        assert_eq!(purged, 0);
+
+        let purged = db
+            .purge_run_failures(org.id, None, Some(query.id), None, None)
+            .await
+            .unwrap();
+        assert_eq!(purged, 1);
+
+        let (_, total) = db
+            .list_run_failures_paginated(org.id, None, None, None, None, "created_at", "DESC", 20, 0)
+            .await
+            .unwrap();
+        assert_eq!(total, 0);
     }
 }
 
@@ -636,7 +1462,7 @@ mod run_result_tests {
             .await
             .unwrap();
         let ds = db
-            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id)
+            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id, None)
             .await
             .unwrap();
         let query = db
@@ -650,7 +1476,9 @@ mod run_result_tests {
                 &serde_json::json!([]),
                 30,
                 10000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -663,7 +1491,10 @@ mod run_result_tests {
                 &serde_json::json!({}),
                 30,
                 10000,
+                None,
                 user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
             )
             .await
             .unwrap();
@@ -679,7 +1510,7 @@ mod run_result_tests {
         let rows = serde_json::json!([[1]]);
 
         let result = db
-            .create_run_result(run.id, &columns, &rows, 1, 8, 5)
+            .create_run_result(run.id, &columns, &rows, 1, 8, 5, None)
             .await
             .unwrap();
 
@@ -687,6 +1518,25 @@ mod run_result_tests {
         assert_eq!(result.row_count, 1);
         assert_eq!(result.byte_count, 8);
         assert_eq!(result.execution_time_ms, 5);
+        assert!(!result.truncated);
+        assert_eq!(result.truncation_reason, None);
+    }
+
+    #[tokio::test]
+    async fn test_store_truncated_run_result() {
+        let (test_db, _org, run) = setup_with_run().await;
+        let db = test_db.database();
+
+        let columns = serde_json::json!([{"name": "num", "data_type": "INT4"}]);
+        let rows = serde_json::json!([[1]]);
+
+        db.create_run_result(run.id, &columns, &rows, 1, 5, 5, Some(TruncationReason::MaxBytes))
+            .await
+            .unwrap();
+
+        let fetched = db.get_run_result(run.id).await.unwrap();
+        assert!(fetched.truncated);
+        assert_eq!(fetched.truncation_reason, Some(TruncationReason::MaxBytes));
     }
 
     #[tokio::test]
@@ -697,7 +1547,7 @@ mod run_result_tests {
         let columns = serde_json::json!([{"name": "id", "data_type": "INT8"}]);
         let rows = serde_json::json!([[1], [2], [3]]);
 
-        db.create_run_result(run.id, &columns, &rows, 3, 24, 10)
+        db.create_run_result(run.id, &columns, &rows, 3, 24, 10, None)
             .await
             .unwrap();
 
@@ -705,6 +1555,407 @@ mod run_result_tests {
         assert_eq!(fetched.row_count, 3);
         assert_eq!(fetched.columns, columns);
     }
+
+    #[tokio::test]
+    async fn test_store_offloaded_run_result() {
+        let (test_db, _org, run) = setup_with_run().await;
+        let db = test_db.database();
+
+        let columns = serde_json::json!([{"name": "id", "data_type": "INT8"}]);
+        let key = format!("results/{}.json", run.id);
+
+        db.create_offloaded_run_result(run.id, &columns, &key, ResultEncoding::Json, 3, 24, 10, None)
+            .await
+            .unwrap();
+
+        let fetched = db.get_run_result(run.id).await.unwrap();
+        assert_eq!(fetched.rows, None);
+        assert_eq!(fetched.storage_key.as_deref(), Some(key.as_str()));
+        assert_eq!(fetched.byte_count, 24);
+    }
+
+    #[tokio::test]
+    async fn test_delete_query_returns_offloaded_result_keys() {
+        let (test_db, org, run) = setup_with_run().await;
+        let db = test_db.database();
+
+        let columns = serde_json::json!([{"name": "id", "data_type": "INT8"}]);
+        let key = format!("results/{}.json", run.id);
+
+        db.create_offloaded_run_result(run.id, &columns, &key, ResultEncoding::Json, 3, 24, 10, None)
+            .await
+            .unwrap();
+
+        // Another org can't delete the query, and learns no keys
+        assert!(db.delete_query(run.query_id, Uuid::new_v4()).await.unwrap().is_empty());
+
+        let keys = db.delete_query(run.query_id, org.id).await.unwrap();
+        assert_eq!(keys, vec![key]);
+        assert!(db.get_run_result(run.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_result_store_round_trips_offloaded_rows() {
+        use loupe::ResultStore;
+        use object_store::memory::InMemory;
+        use std::sync::Arc;
+
+        let (test_db, _org, run) = setup_with_run().await;
+        let objects = Arc::new(InMemory::new());
+        let store = ResultStore::with_object_store(test_db.database().clone(), objects, 0);
+
+        let columns = serde_json::json!([{"name": "id", "data_type": "INT8"}]);
+        let rows = serde_json::value::to_raw_value(&serde_json::json!([[1], [2]])).unwrap();
+
+        let stored = store.put(run.id, &columns, &rows, 2, 10, None).await.unwrap();
+        assert!(stored.storage_key.is_some());
+
+        let fetched = store.get_run_result(run.id).await.unwrap();
+        assert_eq!(fetched.rows, Some(serde_json::json!([[1], [2]])));
+
+        store.delete_object(fetched.storage_key.as_deref().unwrap()).await.unwrap();
+        assert!(store.get_run_result(run.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_result_store_columnar_encoding() {
+        use loupe::ResultStore;
+
+        let (test_db, _org, run) = setup_with_run().await;
+        let store = ResultStore::inline(test_db.database().clone())
+            .with_encoding(ResultEncoding::ColumnarZstd);
+
+        let columns = serde_json::json!([
+            {"name": "ts", "data_type": "TIMESTAMPTZ"},
+            {"name": "value", "data_type": "FLOAT8"}
+        ]);
+        let rows = serde_json::json!([["2026-01-01T00:00:00Z", 1.5], ["2026-01-01T00:01:00Z", 2.5]]);
+        let raw = serde_json::value::to_raw_value(&rows).unwrap();
+
+        let stored = store.put(run.id, &columns, &raw, 2, 10, None).await.unwrap();
+        assert_eq!(stored.encoding, ResultEncoding::ColumnarZstd);
+        assert_eq!(stored.rows, None);
+        assert_eq!(stored.byte_count, stored.data.as_ref().unwrap().len() as i64);
+
+        let fetched = store.get_run_result(run.id).await.unwrap();
+        assert_eq!(fetched.rows, Some(rows));
+
+        let (_, data) = store.get_columnar_result(run.id).await.unwrap();
+        assert_eq!(data[1], vec![serde_json::json!(1.5), serde_json::json!(2.5)]);
+    }
+
+    #[tokio::test]
+    async fn test_result_store_offloads_columnar_rows() {
+        use loupe::ResultStore;
+        use object_store::memory::InMemory;
+        use std::sync::Arc;
+
+        let (test_db, _org, run) = setup_with_run().await;
+        let objects = Arc::new(InMemory::new());
+        let store = ResultStore::with_object_store(test_db.database().clone(), objects, 0)
+            .with_encoding(ResultEncoding::ColumnarZstd);
+
+        let columns = serde_json::json!([{"name": "id", "data_type": "INT8"}]);
+        let rows = serde_json::value::to_raw_value(&serde_json::json!([[1], [2]])).unwrap();
+
+        let stored = store.put(run.id, &columns, &rows, 2, 10, None).await.unwrap();
+        assert!(stored.storage_key.unwrap().ends_with(".columns.json.zst"));
+        assert_eq!(stored.data, None);
+
+        let fetched = store.get_run_result(run.id).await.unwrap();
+        assert_eq!(fetched.rows, Some(serde_json::json!([[1], [2]])));
+    }
+
+    #[tokio::test]
+    async fn test_json_results_read_by_columnar_store() {
+        use loupe::ResultStore;
+
+        let (test_db, _org, run) = setup_with_run().await;
+        let db = test_db.database();
+        let columns = serde_json::json!([{"name": "id", "data_type": "INT8"}, {"name": "n", "data_type": "TEXT"}]);
+        db.create_run_result(run.id, &columns, &serde_json::json!([[1, "a"]]), 1, 9, 5, None)
+            .await
+            .unwrap();
+
+        let store = ResultStore::inline(db.clone()).with_encoding(ResultEncoding::ColumnarZstd);
+        let (_, data) = store.get_columnar_result(run.id).await.unwrap();
+        assert_eq!(data, vec![vec![serde_json::json!(1)], vec![serde_json::json!("a")]]);
+    }
+
+    #[tokio::test]
+    async fn test_result_store_keeps_small_rows_inline() {
+        use loupe::ResultStore;
+        use object_store::memory::InMemory;
+        use std::sync::Arc;
+
+        let (test_db, _org, run) = setup_with_run().await;
+        let objects = Arc::new(InMemory::new());
+        let store = ResultStore::with_object_store(test_db.database().clone(), objects, 1024);
+
+        let columns = serde_json::json!([{"name": "id", "data_type": "INT8"}]);
+        let rows = serde_json::value::to_raw_value(&serde_json::json!([[1]])).unwrap();
+
+        let stored = store.put(run.id, &columns, &rows, 1, 10, None).await.unwrap();
+        assert_eq!(stored.storage_key, None);
+        assert_eq!(stored.rows, Some(serde_json::json!([[1]])));
+    }
+}
+
+mod retention_tests {
+    use super::*;
+
+    struct Setup {
+        test_db: TestDb,
+        org: Organization,
+        user: User,
+        query: Query,
+    }
+
+    async fn setup() -> Setup {
+        let test_db = TestDb::new().await;
+        let db = test_db.database();
+        let org = db.create_organization("Test Org").await.unwrap();
+        let user = db
+            .create_user(org.id, "admin@example.com", "hash", "Admin", OrgRole::Admin)
+            .await
+            .unwrap();
+        let ds = db
+            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id, None)
+            .await
+            .unwrap();
+        let query = db
+            .create_query(
+                org.id,
+                ds.id,
+                "Test Query",
+                None,
+                "SELECT 1",
+                &serde_json::json!([]),
+                &serde_json::json!([]),
+                30,
+                10000,
+                None,
+                user.id,
+                None,
+            )
+            .await
+            .unwrap();
+        Setup { test_db, org, user, query }
+    }
+
+    async fn create_run(s: &Setup) -> Run {
+        s.test_db
+            .database()
+            .create_run(
+                s.org.id,
+                s.query.id,
+                s.query.datasource_id,
+                "SELECT 1",
+                &serde_json::json!({}),
+                30,
+                10000,
+                None,
+                s.user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
+            )
+            .await
+            .unwrap()
+    }
+
+    /// Hand a run to "runner-1" as if it had claimed it
+    async fn start_run(db: &Database, run_id: Uuid) {
+        sqlx::query("UPDATE runs SET status = 'running', runner_id = 'runner-1' WHERE id = $1")
+            .bind(run_id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+    }
+
+    async fn create_result(s: &Setup) -> RunResult {
+        let db = s.test_db.database();
+        let run = create_run(s).await;
+        start_run(db, run.id).await;
+        db.complete_run(run.id, "runner-1", Uuid::new_v4()).await.unwrap().unwrap();
+        db.create_run_result(run.id, &serde_json::json!([]), &serde_json::json!([]), 0, 2, 5, None)
+            .await
+            .unwrap()
+    }
+
+    /// Move a result's creation and expiry `days` into the past
+    async fn age_result(db: &Database, result: &RunResult, days: i32) {
+        sqlx::query(
+            "UPDATE run_results SET created_at = created_at - make_interval(days => $2), expires_at = expires_at - make_interval(days => $2) WHERE id = $1",
+        )
+        .bind(result.id)
+        .bind(days)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+    }
+
+    async fn age_run(db: &Database, run_id: Uuid, days: i32) {
+        sqlx::query("UPDATE runs SET created_at = created_at - make_interval(days => $2) WHERE id = $1")
+            .bind(run_id)
+            .bind(days)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+    }
+
+    async fn put_on_dashboard(s: &Setup) {
+        let db = s.test_db.database();
+        let viz = db
+            .create_visualization(
+                s.org.id,
+                s.query.id,
+                "Chart",
+                ChartType::Line,
+                &serde_json::json!({}),
+                &serde_json::json!([]),
+                s.user.id,
+            )
+            .await
+            .unwrap();
+        let dashboard = db
+            .create_dashboard(s.org.id, "Dash", None, &serde_json::json!([]), &serde_json::json!([]), s.user.id)
+            .await
+            .unwrap();
+        db.create_tile(dashboard.id, viz.id, None, 0, 0, 4, 4, &serde_json::json!({}))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_result_expiry_follows_policy() {
+        let s = setup().await;
+        let db = s.test_db.database();
+
+        let result = create_result(&s).await;
+        let days = (result.expires_at.unwrap() - result.created_at).num_days();
+        assert_eq!(days, i64::from(DEFAULT_RETENTION_DAYS));
+
+        let org_policy = RetentionPolicy { keep_days: 30, ..Default::default() };
+        db.set_organization_retention_policy(s.org.id, Some(&org_policy))
+            .await
+            .unwrap();
+        let result = create_result(&s).await;
+        assert_eq!((result.expires_at.unwrap() - result.created_at).num_days(), 30);
+
+        // The query's policy wins over the org's
+        let query_policy = RetentionPolicy { keep_days: 2, ..Default::default() };
+        db.set_query_retention_policy(s.query.id, s.org.id, Some(&query_policy))
+            .await
+            .unwrap();
+        let result = create_result(&s).await;
+        assert_eq!((result.expires_at.unwrap() - result.created_at).num_days(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_delete_expired_run_results() {
+        let s = setup().await;
+        let db = s.test_db.database();
+
+        let expired = create_result(&s).await;
+        let fresh = create_result(&s).await;
+        age_result(db, &expired, 8).await;
+
+        let keys = db.delete_expired_run_results(100).await.unwrap();
+        assert_eq!(keys, vec![None]);
+        assert!(db.get_run_result(expired.run_id).await.is_err());
+        assert!(db.get_run_result(fresh.run_id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_delete_results_past_keep_last() {
+        let s = setup().await;
+        let db = s.test_db.database();
+        let policy = RetentionPolicy { keep_last: Some(2), ..Default::default() };
+        db.set_query_retention_policy(s.query.id, s.org.id, Some(&policy))
+            .await
+            .unwrap();
+
+        let mut results = Vec::new();
+        for days in [3, 2, 1] {
+            let result = create_result(&s).await;
+            age_result(db, &result, days).await;
+            results.push(result);
+        }
+
+        assert_eq!(db.delete_expired_run_results(100).await.unwrap().len(), 1);
+        assert!(db.get_run_result(results[0].run_id).await.is_err());
+        assert!(db.get_run_result(results[1].run_id).await.is_ok());
+        assert!(db.get_run_result(results[2].run_id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_dashboard_results_are_kept() {
+        let s = setup().await;
+        let db = s.test_db.database();
+        put_on_dashboard(&s).await;
+
+        let older = create_result(&s).await;
+        let latest = create_result(&s).await;
+        age_result(db, &older, 10).await;
+        age_result(db, &latest, 9).await;
+
+        assert_eq!(db.delete_expired_run_results(100).await.unwrap().len(), 1);
+        assert!(db.get_run_result(older.run_id).await.is_err());
+        assert!(db.get_run_result(latest.run_id).await.is_ok());
+
+        // Unless the policy says otherwise
+        let policy = RetentionPolicy { keep_dashboard_results: false, ..Default::default() };
+        db.set_organization_retention_policy(s.org.id, Some(&policy))
+            .await
+            .unwrap();
+        assert_eq!(db.delete_expired_run_results(100).await.unwrap().len(), 1);
+        assert!(db.get_run_result(latest.run_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_delete_expired_runs() {
+        let s = setup().await;
+        let db = s.test_db.database();
+
+        let finished = create_run(&s).await;
+        start_run(db, finished.id).await;
+        db.fail_run(finished.id, "runner-1", "boom").await.unwrap().unwrap();
+        age_run(db, finished.id, 8).await;
+
+        let queued = create_run(&s).await;
+        age_run(db, queued.id, 8).await;
+
+        let with_result = create_result(&s).await;
+        age_run(db, with_result.run_id, 8).await;
+
+        let recent = create_run(&s).await;
+        start_run(db, recent.id).await;
+        db.fail_run(recent.id, "runner-1", "boom").await.unwrap().unwrap();
+
+        assert_eq!(db.delete_expired_runs(100).await.unwrap(), 1);
+        assert!(db.get_run(finished.id, s.org.id).await.is_err());
+        assert!(db.get_run(queued.id, s.org.id).await.is_ok());
+        assert!(db.get_run(with_result.run_id, s.org.id).await.is_ok());
+        assert!(db.get_run(recent.id, s.org.id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_delete_expired_run_failures() {
+        let s = setup().await;
+        let db = s.test_db.database();
+
+        let run = create_run(&s).await;
+        start_run(db, run.id).await;
+        db.fail_run(run.id, "runner-1", "boom").await.unwrap().unwrap();
+        db.move_to_dead_letter_queue(run.id).await.unwrap();
+        assert_eq!(db.delete_expired_run_failures(100).await.unwrap(), 0);
+
+        sqlx::query("UPDATE run_failures SET last_failed_at = NOW() - INTERVAL '8 days'")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(db.delete_expired_run_failures(100).await.unwrap(), 1);
+    }
 }
 
 mod visualization_tests {
@@ -719,7 +1970,7 @@ mod visualization_tests {
             .await
             .unwrap();
         let ds = db
-            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id)
+            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id, None)
             .await
             .unwrap();
         let query = db
@@ -733,7 +1984,9 @@ mod visualization_tests {
                 &serde_json::json!([]),
                 30,
                 10000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -862,7 +2115,7 @@ mod dashboard_tests {
             .await
             .unwrap();
         let ds = db
-            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id)
+            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id, None)
             .await
             .unwrap();
         let query = db
@@ -876,7 +2129,9 @@ mod dashboard_tests {
                 &serde_json::json!([]),
                 30,
                 10000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -1063,7 +2318,7 @@ mod schedule_tests {
             .await
             .unwrap();
         let ds = db
-            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id)
+            .create_datasource(org.id, "Test DS", DatasourceType::Postgres, "conn", user.id, None)
             .await
             .unwrap();
         let query = db
@@ -1077,7 +2332,9 @@ mod schedule_tests {
                 &serde_json::json!([]),
                 30,
                 10000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -1099,6 +2356,7 @@ mod schedule_tests {
                 &serde_json::json!([]),
                 true,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -1122,6 +2380,7 @@ mod schedule_tests {
             &serde_json::json!([]),
             true,
             user.id,
+            None,
         )
         .await
         .unwrap();
@@ -1134,6 +2393,7 @@ mod schedule_tests {
             &serde_json::json!([]),
             false,
             user.id,
+            None,
         )
         .await
         .unwrap();
@@ -1157,6 +2417,7 @@ mod schedule_tests {
                 &serde_json::json!([]),
                 true,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -1187,6 +2448,7 @@ mod schedule_tests {
             &serde_json::json!([]),
             true,
             user.id,
+            None,
         )
         .await
         .unwrap();
@@ -1201,6 +2463,7 @@ mod schedule_tests {
             &serde_json::json!([]),
             false,
             user.id,
+            None,
         )
         .await
         .unwrap();
@@ -1211,3 +2474,56 @@ mod schedule_tests {
         assert!(due.iter().all(|s| s.enabled));
     }
 }
+
+mod query_slot_tests {
+    use super::*;
+    use loupe::{LimitError, QueryLimiter, QueryLimits};
+
+    #[tokio::test]
+    async fn test_slots_are_shared_between_limiters() {
+        let test_db = TestDb::new().await;
+        let db = test_db.database();
+        let org = db.create_organization("Test Org").await.unwrap();
+
+        let limits = QueryLimits {
+            max_concurrent_per_org: 2,
+            max_concurrent_global: 10,
+        };
+        // Two runners, each with its own limiter
+        let runner_a = QueryLimiter::postgres(limits.clone(), db.clone(), "runner-a");
+        let runner_b = QueryLimiter::postgres(limits, db.clone(), "runner-b");
+
+        let _a = runner_a.try_acquire(org.id).await.unwrap();
+        let b = runner_b.try_acquire(org.id).await.unwrap();
+        assert!(matches!(
+            runner_a.try_acquire(org.id).await,
+            Err(LimitError::OrgLimitReached { current: 2, .. })
+        ));
+
+        // Releasing happens in the background
+        drop(b);
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert!(runner_a.try_acquire(org.id).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_expired_slots_are_freed() {
+        let test_db = TestDb::new().await;
+        let db = test_db.database();
+        let org = db.create_organization("Test Org").await.unwrap();
+
+        let attempt = db.acquire_query_slot(org.id, "crashed", 1, 10).await.unwrap();
+        assert!(attempt.slot_id.is_some());
+        assert!(db.acquire_query_slot(org.id, "runner-a", 1, 10).await.unwrap().slot_id.is_none());
+
+        // The crashed runner never renews its lease
+        sqlx::query("UPDATE query_slots SET expires_at = NOW() - INTERVAL '1 second' WHERE holder = 'crashed'")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let attempt = db.acquire_query_slot(org.id, "runner-a", 1, 10).await.unwrap();
+        assert!(attempt.slot_id.is_some());
+        assert_eq!(attempt.org_count, 0);
+    }
+}
@@ -0,0 +1,143 @@
+//! HTTP/JSON connector integration tests
+//!
+//! These tests verify the HttpConnector against a local wiremock server.
+
+use loupe::connectors::{Connector, HttpConnector};
+use loupe::params::TypedValue;
+use serde_json::json;
+use std::time::Duration;
+use wiremock::matchers::{body_json, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn connector_for(server: &MockServer) -> HttpConnector {
+    let config = json!({
+        "base_url": format!("{}/api", server.uri()),
+        "headers": {"X-Team": "data"},
+        "auth": {"type": "bearer", "token": "s3cret"},
+        "health_path": "/health"
+    });
+    HttpConnector::new(&config.to_string()).expect("Failed to create connector")
+}
+
+#[tokio::test]
+async fn test_connection_uses_health_path() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/health"))
+        .and(header("authorization", "Bearer s3cret"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+
+    assert!(connector_for(&server).test_connection().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_connection_failure_status() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&server)
+        .await;
+
+    assert!(connector_for(&server).test_connection().await.is_err());
+}
+
+#[tokio::test]
+async fn test_get_with_params_and_selector() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/teams/core%20infra/incidents"))
+        .and(query_param("status", "open"))
+        .and(header("x-team", "data"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": {"items": [
+                {"id": 1, "sev": 2, "owner": {"name": "ana"}},
+                {"id": 2, "sev": 1, "owner": {"name": "bo"}}
+            ]}
+        })))
+        .mount(&server)
+        .await;
+
+    let spec = r#"{"path": "/teams/$1/incidents", "params": {"status": "$2"}, "select": "$.data.items"}"#;
+    let params = vec![TypedValue::String("core infra".into()), TypedValue::String("open".into())];
+
+    let result = connector_for(&server)
+        .execute_with_params(spec, &params, Duration::from_secs(5), 100)
+        .await
+        .unwrap();
+
+    assert_eq!(result.row_count, 2);
+    let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["id", "owner.name", "sev"]);
+    assert_eq!(result.rows[1], vec![json!(2), json!("bo"), json!(1)]);
+}
+
+#[tokio::test]
+async fn test_post_body_substitution() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/search"))
+        .and(body_json(json!({"limit": 10, "active": true})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([{"n": 1}])))
+        .mount(&server)
+        .await;
+
+    let spec = r#"{"method": "POST", "path": "/search", "body": {"limit": "$1", "active": "$2"}}"#;
+    let params = vec![TypedValue::Integer(10), TypedValue::Boolean(true)];
+
+    let result = connector_for(&server)
+        .execute_with_params(spec, &params, Duration::from_secs(5), 100)
+        .await
+        .unwrap();
+
+    assert_eq!(result.rows, vec![vec![json!(1)]]);
+}
+
+#[tokio::test]
+async fn test_error_status_is_query_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("boom"))
+        .mount(&server)
+        .await;
+
+    let err = connector_for(&server)
+        .execute(r#"{"path": "/broken"}"#, Duration::from_secs(5), 100)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("500"));
+}
+
+#[tokio::test]
+async fn test_timeout() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(2)))
+        .mount(&server)
+        .await;
+
+    let err = connector_for(&server)
+        .execute(r#"{"path": "/slow"}"#, Duration::from_millis(100), 100)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().to_lowercase().contains("timeout"));
+}
+
+#[tokio::test]
+async fn test_oversized_response_is_rejected() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![b' '; 50 * 1024 * 1024 + 1]))
+        .mount(&server)
+        .await;
+
+    let err = connector_for(&server)
+        .execute(r#"{"path": "/huge"}"#, Duration::from_secs(30), 100)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("exceeds"));
+}
@@ -0,0 +1,205 @@
+//! MySQL connector integration tests
+//!
+//! These tests verify the MySqlConnector against a real MySQL instance
+//! using testcontainers.
+
+use loupe::connectors::{Connector, MySqlConnector};
+use loupe::params::TypedValue;
+use std::time::Duration;
+use testcontainers::{runners::AsyncRunner, ContainerAsync};
+use testcontainers_modules::mysql::Mysql;
+
+/// Test helper that provides a MySQL container and connector
+struct TestConnector {
+    connector: MySqlConnector,
+    container: ContainerAsync<Mysql>,
+}
+
+impl TestConnector {
+    async fn new() -> Self {
+        let container = Mysql::default()
+            .start()
+            .await
+            .expect("Failed to start mysql container");
+
+        let host = container.get_host().await.expect("get host");
+        let port = container.get_host_port_ipv4(3306).await.expect("get port");
+        let connection_string = format!("mysql://root@{}:{}/test", host, port);
+
+        let connector = MySqlConnector::new(&connection_string)
+            .await
+            .expect("Failed to create connector");
+
+        Self {
+            connector,
+            container,
+        }
+    }
+
+    fn connector(&self) -> &MySqlConnector {
+        &self.connector
+    }
+}
+
+mod test_connection {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_connection_success() {
+        let test = TestConnector::new().await;
+
+        let duration = test.connector().test_connection().await.unwrap();
+        assert!(duration.as_millis() < 5000);
+    }
+
+    #[tokio::test]
+    async fn test_connection_failure() {
+        let result = MySqlConnector::new("mysql://localhost:9999/nonexistent").await;
+        assert!(result.is_err());
+    }
+}
+
+mod execute_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_simple_select() {
+        let test = TestConnector::new().await;
+
+        let result = test.connector()
+            .execute("SELECT 1 AS num, 'hello' AS greeting", Duration::from_secs(10), 100)
+            .await
+            .unwrap();
+
+        assert_eq!(result.row_count, 1);
+        assert_eq!(result.columns[0].name, "num");
+        assert_eq!(result.columns[1].name, "greeting");
+        assert_eq!(result.rows[0][0], serde_json::json!(1));
+        assert_eq!(result.rows[0][1], serde_json::json!("hello"));
+    }
+
+    #[tokio::test]
+    async fn test_max_rows_limit() {
+        let test = TestConnector::new().await;
+
+        let result = test.connector()
+            .execute(
+                "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 100) SELECT n FROM seq",
+                Duration::from_secs(10),
+                10,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.row_count, 10);
+    }
+
+    #[tokio::test]
+    async fn test_various_data_types() {
+        let test = TestConnector::new().await;
+
+        let result = test.connector()
+            .execute(
+                r#"
+                SELECT
+                    CAST(42 AS SIGNED) AS int_val,
+                    CAST(2.50 AS DECIMAL(10, 2)) AS dec_val,
+                    CAST('2024-01-15' AS DATE) AS date_val,
+                    JSON_OBJECT('key', 'value') AS json_val,
+                    NULL AS null_val
+                "#,
+                Duration::from_secs(10),
+                100,
+            )
+            .await
+            .unwrap();
+
+        let row = &result.rows[0];
+        assert_eq!(row[0], serde_json::json!(42));
+        assert_eq!(row[1], serde_json::json!(2.5));
+        assert_eq!(row[2], serde_json::json!("2024-01-15"));
+        assert_eq!(row[3], serde_json::json!({"key": "value"}));
+        assert_eq!(row[4], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_params() {
+        let test = TestConnector::new().await;
+
+        let params = vec![TypedValue::Integer(5), TypedValue::String("x".into())];
+        let result = test.connector()
+            .execute_with_params(
+                "SELECT $2 AS label, $1 AS n, $1 * 2 AS doubled",
+                &params,
+                Duration::from_secs(10),
+                100,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.rows[0][0], serde_json::json!("x"));
+        assert_eq!(result.rows[0][1], serde_json::json!(5));
+        assert_eq!(result.rows[0][2], serde_json::json!(10));
+    }
+
+    #[tokio::test]
+    async fn test_syntax_error() {
+        let test = TestConnector::new().await;
+
+        let result = test.connector()
+            .execute("SELEKT 1", Duration::from_secs(10), 100)
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let test = TestConnector::new().await;
+
+        let result = test.connector()
+            .execute("SELECT SLEEP(2)", Duration::from_millis(100), 100)
+            .await;
+
+        assert!(result.is_err());
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("timeout") || err.contains("Timeout"));
+    }
+}
+
+mod schema_tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_schema_with_tables() {
+        let test = TestConnector::new().await;
+
+        // The connector only runs wrapped SELECTs, so create the table over a raw pool
+        let pool = sqlx::MySqlPool::connect(&format!(
+            "mysql://root@{}:{}/test",
+            test.container.get_host().await.unwrap(),
+            test.container.get_host_port_ipv4(3306).await.unwrap()
+        ))
+        .await
+        .unwrap();
+        sqlx::query("CREATE TABLE test_users (id INT PRIMARY KEY, name TEXT NOT NULL, age INT)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let schema = test.connector().get_schema().await.unwrap();
+
+        let table = schema
+            .iter()
+            .find(|t| t.name == "test_users")
+            .expect("Should find test_users table");
+        assert_eq!(table.schema, "test");
+        assert_eq!(table.columns.len(), 3);
+
+        let name_col = table.columns.iter().find(|c| c.name == "name").unwrap();
+        assert!(!name_col.is_nullable);
+
+        let age_col = table.columns.iter().find(|c| c.name == "age").unwrap();
+        assert!(age_col.is_nullable);
+    }
+}
@@ -0,0 +1,123 @@
+//! Connector plugin integration tests
+//!
+//! These tests drive the reference plugin in `examples/echo_plugin.rs`,
+//! which `cargo test` builds alongside the test binaries.
+
+use loupe::connectors::{Connector, PluginConnector, PluginSpec};
+use loupe::params::TypedValue;
+use loupe::Error;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn echo_spec() -> PluginSpec {
+    // target/<profile>/deps/<test binary> -> target/<profile>/examples/echo_plugin
+    let mut command = std::env::current_exe().unwrap();
+    command.pop();
+    command.pop();
+    command.push("examples");
+    command.push(format!("echo_plugin{}", std::env::consts::EXE_SUFFIX));
+
+    PluginSpec {
+        command,
+        args: vec![],
+        env: HashMap::new(),
+    }
+}
+
+async fn echo_connector() -> PluginConnector {
+    PluginConnector::spawn("echo", &echo_spec(), "echo://local")
+        .await
+        .expect("Failed to start echo plugin")
+}
+
+#[tokio::test]
+async fn test_connection() {
+    let connector = echo_connector().await;
+    assert!(connector.test_connection().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_execute_with_params_and_truncation() {
+    let connector = echo_connector().await;
+    let params = vec![TypedValue::String("a".into()), TypedValue::Integer(7)];
+
+    let result = connector
+        .execute_with_params("SELECT $1, $2", &params, Duration::from_secs(5), 2)
+        .await
+        .unwrap();
+
+    let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["query", "params", "connection", "n"]);
+    assert_eq!(result.row_count, 2);
+    assert_eq!(result.rows[0][0], json!("SELECT $1, $2"));
+    assert_eq!(
+        result.rows[0][1],
+        json!([{"type": "string", "value": "a"}, {"type": "integer", "value": 7}])
+    );
+    assert_eq!(result.rows[0][2], json!("echo://local"));
+}
+
+#[tokio::test]
+async fn test_plugin_error_maps_to_query_error() {
+    let connector = echo_connector().await;
+    let err = connector
+        .execute("please fail", Duration::from_secs(5), 10)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, Error::QueryExecution(..)));
+    assert!(err.to_string().contains("echo refused query"));
+
+    // The plugin stays usable after an error
+    assert!(connector.execute("ok", Duration::from_secs(5), 10).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_slow_query_does_not_block_other_calls() {
+    let connector = echo_connector().await;
+
+    let slow = connector.execute("sleep", Duration::from_secs(20), 10);
+    tokio::pin!(slow);
+
+    let fast = async {
+        // Let the slow request reach the plugin first
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            connector.test_connection().await?;
+            connector.execute("ok", Duration::from_secs(5), 10).await
+        })
+        .await
+    };
+
+    tokio::select! {
+        result = &mut slow => panic!("slow query finished first: {:?}", result.map(|r| r.row_count)),
+        result = fast => {
+            let output = result.expect("calls queued behind the slow query").unwrap();
+            assert_eq!(output.rows[0][0], json!("ok"));
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_get_schema() {
+    let connector = echo_connector().await;
+    let schema = connector.get_schema().await.unwrap();
+
+    assert_eq!(schema.len(), 1);
+    assert_eq!(schema[0].name, "echo");
+    assert_eq!(schema[0].columns.len(), 2);
+}
+
+#[tokio::test]
+async fn test_missing_executable() {
+    let spec = PluginSpec {
+        command: PathBuf::from("/nonexistent/loupe-plugin"),
+        args: vec![],
+        env: HashMap::new(),
+    };
+
+    let err = PluginConnector::spawn("missing", &spec, "").await.err().unwrap();
+    assert!(matches!(err, Error::Connection(..)));
+}
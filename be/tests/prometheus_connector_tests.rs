@@ -0,0 +1,136 @@
+//! Prometheus connector integration tests
+//!
+//! These tests verify the PrometheusConnector against a wiremock stand-in
+//! for the Prometheus HTTP API.
+
+use loupe::connectors::{Connector, PrometheusConnector};
+use loupe::params::TypedValue;
+use serde_json::json;
+use std::time::Duration;
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn success(data: serde_json::Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({"status": "success", "data": data}))
+}
+
+#[tokio::test]
+async fn test_connection() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/status/buildinfo"))
+        .respond_with(success(json!({"version": "2.50.0"})))
+        .mount(&server)
+        .await;
+
+    let connector = PrometheusConnector::new(&server.uri()).unwrap();
+    assert!(connector.test_connection().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_instant_query() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/query"))
+        .and(query_param("query", r#"up{job="api"}"#))
+        .respond_with(success(json!({
+            "resultType": "vector",
+            "result": [{"metric": {"__name__": "up", "job": "api"}, "value": [1700000000, "1"]}]
+        })))
+        .mount(&server)
+        .await;
+
+    let connector = PrometheusConnector::new(&server.uri()).unwrap();
+    let params = vec![TypedValue::String("api".into())];
+    let result = connector
+        .execute_with_params(r#"up{job="$1"}"#, &params, Duration::from_secs(5), 100)
+        .await
+        .unwrap();
+
+    let names: Vec<&str> = result.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["timestamp", "__name__", "job", "value"]);
+    assert_eq!(result.rows[0][3], json!(1.0));
+}
+
+#[tokio::test]
+async fn test_range_query_uses_start_end_step() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/query_range"))
+        .and(query_param("start", "2024-01-01T00:00:00.000Z"))
+        .and(query_param("end", "2024-01-01T01:00:00.000Z"))
+        .and(query_param("step", "15m"))
+        .respond_with(success(json!({
+            "resultType": "matrix",
+            "result": [{"metric": {"job": "api"}, "values": [
+                [1704067200, "3"], [1704068100, "4"], [1704069000, "5"]
+            ]}]
+        })))
+        .mount(&server)
+        .await;
+
+    let connector = PrometheusConnector::new(&server.uri()).unwrap();
+    let spec = r#"{"query": "sum(rate(requests_total[5m]))", "start": "$1", "end": "$2", "step": "$3"}"#;
+    let params = vec![
+        TypedValue::Date(chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+        TypedValue::String("2024-01-01T01:00:00Z".into()),
+        TypedValue::String("15m".into()),
+    ];
+
+    let result = connector
+        .execute_with_params(spec, &params, Duration::from_secs(5), 2)
+        .await
+        .unwrap();
+
+    assert_eq!(result.row_count, 2);
+    assert_eq!(result.rows[1], vec![json!("2024-01-01T00:15:00.000Z"), json!("api"), json!(4.0)]);
+}
+
+#[tokio::test]
+async fn test_prometheus_error_is_reported() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/query"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "status": "error", "errorType": "bad_data", "error": "parse error"
+        })))
+        .mount(&server)
+        .await;
+
+    let connector = PrometheusConnector::new(&server.uri()).unwrap();
+    let err = connector
+        .execute("sum(", Duration::from_secs(5), 100)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("parse error"));
+}
+
+#[tokio::test]
+async fn test_get_schema_lists_metrics_and_labels() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/label/__name__/values"))
+        .respond_with(success(json!(["http_requests_total", "up"])))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/api/v1/labels"))
+        .respond_with(success(json!(["__name__", "instance", "job"])))
+        .mount(&server)
+        .await;
+
+    let connector = PrometheusConnector::new(&server.uri()).unwrap();
+    let schema = connector.get_schema().await.unwrap();
+
+    let metrics: Vec<&str> = schema
+        .iter()
+        .filter(|t| t.schema == "metrics")
+        .map(|t| t.name.as_str())
+        .collect();
+    assert_eq!(metrics, vec!["http_requests_total", "up"]);
+
+    let labels = schema.iter().find(|t| t.schema == "labels").unwrap();
+    let keys: Vec<&str> = labels.columns.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(keys, vec!["instance", "job"]);
+}
@@ -23,7 +23,14 @@ fn arb_org_role() -> impl Strategy<Value = OrgRole> {
 
 /// Generate arbitrary DatasourceType values
 fn arb_datasource_type() -> impl Strategy<Value = DatasourceType> {
-    Just(DatasourceType::Postgres)
+    prop_oneof![
+        Just(DatasourceType::Postgres),
+        Just(DatasourceType::Mysql),
+        Just(DatasourceType::Sqlite),
+        Just(DatasourceType::Http),
+        Just(DatasourceType::Prometheus),
+        Just(DatasourceType::Plugin),
+    ]
 }
 
 /// Generate arbitrary ParamType values
@@ -171,6 +178,9 @@ proptest! {
             tags: vec![],
             timeout_seconds: timeout,
             max_rows,
+            retry_policy: None,
+            max_bytes: None,
+            retention_policy: None,
         };
 
         // Invariants
@@ -200,6 +210,7 @@ proptest! {
             parameters: serde_json::json!({}),
             timeout_seconds: timeout,
             max_rows,
+            max_bytes: None,
         };
 
         prop_assert!(req.timeout_seconds > 0);
@@ -218,6 +229,7 @@ proptest! {
             name: name.clone(),
             ds_type,
             connection_string: "postgres://localhost:5432/test".to_string(),
+            max_concurrent_runs: None,
         };
 
         prop_assert!(!req.name.is_empty());
@@ -296,6 +308,8 @@ proptest! {
             name: name.clone(),
             ds_type,
             connection_string_encrypted: "ENCRYPTED_SECRET_CONNECTION_STRING".to_string(),
+            config_version: 1,
+            max_concurrent_runs: None,
             created_by: Uuid::new_v4(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -331,12 +345,17 @@ proptest! {
             id: Uuid::new_v4(),
             run_id: Uuid::new_v4(),
             columns: serde_json::json!([{"name": "id", "data_type": "INT8"}]),
-            rows: serde_json::to_value(&rows).unwrap(),
+            rows: Some(serde_json::to_value(&rows).unwrap()),
             row_count: row_count as i64,
             byte_count: 100,
             execution_time_ms: 50,
             created_at: Utc::now(),
             expires_at: None,
+            truncated: false,
+            truncation_reason: None,
+            storage_key: None,
+            encoding: ResultEncoding::Json,
+            data: None,
         };
 
         let response = RunResultResponse::from(result);
@@ -398,6 +417,7 @@ proptest! {
                 parameters: serde_json::json!({}),
                 tags: vec![],
                 enabled: true,
+                retry_policy: None,
             };
 
             // Cron expression should not be empty
@@ -430,6 +450,9 @@ proptest! {
                 tags: vec![],
                 timeout_seconds: 30,
                 max_rows: 1000,
+                retry_policy: None,
+                max_bytes: None,
+                retention_policy: None,
             };
 
             // Verify the request was created with the correct name
@@ -450,6 +473,9 @@ proptest! {
             tags: vec![],
             timeout_seconds: timeout,
             max_rows: 1000,
+            retry_policy: None,
+            max_bytes: None,
+            retention_policy: None,
         };
 
         // Should accept any timeout in valid range
@@ -469,6 +495,9 @@ proptest! {
             tags: vec![],
             timeout_seconds: 30,
             max_rows,
+            retry_policy: None,
+            max_bytes: None,
+            retention_policy: None,
         };
 
         // Should accept any max_rows in valid range
@@ -0,0 +1,192 @@
+//! SQLite connector integration tests
+//!
+//! These tests verify the SqliteConnector against a database file created
+//! in a temporary directory.
+
+use futures_util::TryStreamExt;
+use loupe::connectors::{Connector, RowBatch, SqliteConnector, collect_rows};
+use loupe::models::TruncationReason;
+use loupe::params::TypedValue;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Test helper that provides a seeded SQLite file and connector
+struct TestConnector {
+    connector: SqliteConnector,
+    dir: PathBuf,
+}
+
+impl TestConnector {
+    async fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("loupe-sqlite-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        let path = dir.join("test.db");
+
+        // Seed through a writable pool; the connector itself is read-only
+        let pool = sqlx::SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .expect("create sqlite file");
+        for stmt in [
+            "CREATE TABLE test_users (id INTEGER PRIMARY KEY, name TEXT NOT NULL, score REAL)",
+            "INSERT INTO test_users (id, name, score) VALUES (1, 'Alice', 9.5), (2, 'Bob', NULL)",
+            "CREATE VIEW high_scores AS SELECT name, score FROM test_users WHERE score > 5",
+        ] {
+            sqlx::query(stmt).execute(&pool).await.expect("seed");
+        }
+        pool.close().await;
+
+        let connector = SqliteConnector::with_allowed_dirs(
+            &format!("sqlite://{}", path.display()),
+            std::slice::from_ref(&dir),
+        )
+        .await
+        .expect("Failed to create connector");
+
+        Self { connector, dir }
+    }
+
+    fn connector(&self) -> &SqliteConnector {
+        &self.connector
+    }
+}
+
+impl Drop for TestConnector {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+#[tokio::test]
+async fn test_connection_success() {
+    let test = TestConnector::new().await;
+    assert!(test.connector().test_connection().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_path_outside_allow_list_rejected() {
+    let test = TestConnector::new().await;
+    let conn = format!("sqlite://{}", test.dir.join("test.db").display());
+
+    let result = SqliteConnector::with_allowed_dirs(&conn, &[]).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_simple_select() {
+    let test = TestConnector::new().await;
+
+    let result = test.connector()
+        .execute("SELECT id, name, score FROM test_users ORDER BY id", Duration::from_secs(10), 100)
+        .await
+        .unwrap();
+
+    assert_eq!(result.row_count, 2);
+    assert_eq!(result.columns[1].name, "name");
+    assert_eq!(result.rows[0], vec![serde_json::json!(1), serde_json::json!("Alice"), serde_json::json!(9.5)]);
+    assert_eq!(result.rows[1][2], serde_json::Value::Null);
+}
+
+#[tokio::test]
+async fn test_max_rows_limit() {
+    let test = TestConnector::new().await;
+
+    let result = test.connector()
+        .execute(
+            "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 100) SELECT n FROM seq",
+            Duration::from_secs(10),
+            10,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.row_count, 10);
+}
+
+#[tokio::test]
+async fn test_execute_with_params() {
+    let test = TestConnector::new().await;
+
+    let params = vec![TypedValue::String("Bob".into()), TypedValue::Integer(2)];
+    let result = test.connector()
+        .execute_with_params(
+            "SELECT id FROM test_users WHERE name = $1 AND id = $2",
+            &params,
+            Duration::from_secs(10),
+            100,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.rows, vec![vec![serde_json::json!(2)]]);
+}
+
+#[tokio::test]
+async fn test_get_schema_lists_tables_and_views() {
+    let test = TestConnector::new().await;
+
+    let schema = test.connector().get_schema().await.unwrap();
+
+    let table = schema.iter().find(|t| t.name == "test_users").expect("table");
+    assert_eq!(table.schema, "main");
+    assert_eq!(table.columns.len(), 3);
+    assert!(!table.columns.iter().find(|c| c.name == "name").unwrap().is_nullable);
+    assert!(table.columns.iter().find(|c| c.name == "score").unwrap().is_nullable);
+
+    let view = schema.iter().find(|t| t.name == "high_scores").expect("view");
+    assert_eq!(view.columns.len(), 2);
+}
+
+#[tokio::test]
+async fn test_execute_stream_batches() {
+    let test = TestConnector::new().await;
+    let sql = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 25) SELECT i FROM n";
+
+    let stream = test
+        .connector()
+        .execute_stream(sql, &[], Duration::from_secs(5), 1000, 10)
+        .await
+        .unwrap();
+    let batches: Vec<RowBatch> = stream.try_collect().await.unwrap();
+
+    let sizes: Vec<usize> = batches.iter().map(|b| b.rows.len()).collect();
+    assert_eq!(sizes, vec![10, 10, 5]);
+    assert_eq!(batches[0].columns[0].name, "i");
+}
+
+#[tokio::test]
+async fn test_execute_stream_with_params_and_byte_limit() {
+    let test = TestConnector::new().await;
+    let params = vec![TypedValue::Number(0.0)];
+
+    let stream = test
+        .connector()
+        .execute_stream(
+            "SELECT id, name FROM test_users WHERE COALESCE(score, 0) >= $1 ORDER BY id",
+            &params,
+            Duration::from_secs(5),
+            1000,
+            1,
+        )
+        .await
+        .unwrap();
+    // Room for the first row only: [[1,"Alice"]]
+    let collected = collect_rows(stream, 1000, 13).await.unwrap();
+
+    assert_eq!(collected.rows.get(), r#"[[1,"Alice"]]"#);
+    assert_eq!(collected.row_count, 1);
+    assert_eq!(collected.truncated, Some(TruncationReason::MaxBytes));
+}
+
+#[tokio::test]
+async fn test_execute_stream_query_error() {
+    let test = TestConnector::new().await;
+
+    let stream = test
+        .connector()
+        .execute_stream("SELECT * FROM missing_table", &[], Duration::from_secs(5), 10, 10)
+        .await
+        .unwrap();
+    let result: Result<Vec<RowBatch>, _> = stream.try_collect().await;
+
+    assert!(result.is_err());
+}
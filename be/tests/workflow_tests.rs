@@ -89,6 +89,7 @@ mod complete_analytics_workflow {
                 DatasourceType::Postgres,
                 "postgres://localhost:5432/analytics",
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -105,7 +106,9 @@ mod complete_analytics_workflow {
                 &json!([]),
                 30,
                 10000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -121,7 +124,9 @@ mod complete_analytics_workflow {
                 &json!([]),
                 30,
                 10000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -137,7 +142,9 @@ mod complete_analytics_workflow {
                 &json!([]),
                 60,
                 10,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -273,7 +280,10 @@ mod complete_analytics_workflow {
                 &json!({}),
                 30,
                 10000,
+                None,
                 user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
             )
             .await
             .unwrap();
@@ -303,12 +313,17 @@ mod complete_analytics_workflow {
                 3,
                 256,
                 125,
+                None,
             )
             .await
             .unwrap();
 
         // Complete the run
-        let completed = db.complete_run(run.id, result.id).await.unwrap();
+        let completed = db
+            .complete_run(run.id, "test-runner-1", result.id)
+            .await
+            .unwrap()
+            .unwrap();
         assert_eq!(completed.status, RunStatus::Completed);
         assert!(completed.completed_at.is_some());
 
@@ -360,6 +375,7 @@ mod scheduled_query_workflow {
                 DatasourceType::Postgres,
                 "conn",
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -376,7 +392,9 @@ mod scheduled_query_workflow {
                 &json!([]),
                 30,
                 1000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -392,6 +410,7 @@ mod scheduled_query_workflow {
                 &json!(["automated", "metrics"]),
                 true,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -413,7 +432,10 @@ mod scheduled_query_workflow {
                 &json!({}),
                 query.timeout_seconds,
                 query.max_rows,
+                None,
                 user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
             )
             .await
             .unwrap();
@@ -431,11 +453,15 @@ mod scheduled_query_workflow {
                 1,
                 8,
                 50,
+                None,
             )
             .await
             .unwrap();
 
-        db.complete_run(run.id, result.id).await.unwrap();
+        db.complete_run(run.id, "scheduler-runner", result.id)
+            .await
+            .unwrap()
+            .unwrap();
 
         // Update the schedule's last run time
         db.update_schedule_last_run(schedule.id, &schedule.cron_expression, true)
@@ -462,7 +488,7 @@ mod scheduled_query_workflow {
             .await
             .unwrap();
         let datasource = db
-            .create_datasource(org.id, "DB", DatasourceType::Postgres, "conn", user.id)
+            .create_datasource(org.id, "DB", DatasourceType::Postgres, "conn", user.id, None)
             .await
             .unwrap();
         let query = db
@@ -476,7 +502,9 @@ mod scheduled_query_workflow {
                 &json!([]),
                 30,
                 1000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -492,6 +520,7 @@ mod scheduled_query_workflow {
                 &json!([]),
                 true,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -500,7 +529,7 @@ mod scheduled_query_workflow {
 
         // Disable schedule
         let disabled = db
-            .update_schedule(schedule.id, org.id, None, None, None, None, Some(false))
+            .update_schedule(schedule.id, org.id, None, None, None, None, Some(false), None)
             .await
             .unwrap();
 
@@ -512,7 +541,7 @@ mod scheduled_query_workflow {
 
         // Re-enable
         let enabled = db
-            .update_schedule(schedule.id, org.id, None, None, None, None, Some(true))
+            .update_schedule(schedule.id, org.id, None, None, None, None, Some(true), None)
             .await
             .unwrap();
 
@@ -537,7 +566,7 @@ mod concurrent_operations {
             .await
             .unwrap();
         let datasource = db
-            .create_datasource(org.id, "DB", DatasourceType::Postgres, "conn", user.id)
+            .create_datasource(org.id, "DB", DatasourceType::Postgres, "conn", user.id, None)
             .await
             .unwrap();
         let query = db
@@ -551,7 +580,9 @@ mod concurrent_operations {
                 &json!([]),
                 30,
                 1000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -568,7 +599,10 @@ mod concurrent_operations {
                     &json!({}),
                     30,
                     1000,
+                    None,
                     user.id,
+                    RunPriority::Interactive,
+                    &RetryPolicy::default(),
                 )
                 .await
                 .unwrap();
@@ -614,7 +648,7 @@ mod concurrent_operations {
             .await
             .unwrap();
         let datasource = db
-            .create_datasource(org.id, "DB", DatasourceType::Postgres, "conn", user.id)
+            .create_datasource(org.id, "DB", DatasourceType::Postgres, "conn", user.id, None)
             .await
             .unwrap();
         let query = db
@@ -628,7 +662,9 @@ mod concurrent_operations {
                 &json!([]),
                 30,
                 1000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -702,7 +738,7 @@ mod error_scenarios {
             .await
             .unwrap();
         let datasource = db
-            .create_datasource(org.id, "DB", DatasourceType::Postgres, "conn", user.id)
+            .create_datasource(org.id, "DB", DatasourceType::Postgres, "conn", user.id, None)
             .await
             .unwrap();
         let query = db
@@ -716,7 +752,9 @@ mod error_scenarios {
                 &json!([]),
                 30,
                 1000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -731,7 +769,10 @@ mod error_scenarios {
                 &json!({}),
                 30,
                 1000,
+                None,
                 user.id,
+                RunPriority::Interactive,
+                &RetryPolicy::default(),
             )
             .await
             .unwrap();
@@ -739,8 +780,9 @@ mod error_scenarios {
         // Claim and fail the run
         db.claim_run("runner-1").await.unwrap();
         let failed = db
-            .fail_run(run.id, "relation \"nonexistent_table\" does not exist")
+            .fail_run(run.id, "runner-1", "relation \"nonexistent_table\" does not exist")
             .await
+            .unwrap()
             .unwrap();
 
         assert_eq!(failed.status, RunStatus::Failed);
@@ -770,7 +812,7 @@ mod error_scenarios {
 
         // Create datasource in org1
         let ds1 = db
-            .create_datasource(org1.id, "Org1 DS", DatasourceType::Postgres, "conn1", user1.id)
+            .create_datasource(org1.id, "Org1 DS", DatasourceType::Postgres, "conn1", user1.id, None)
             .await
             .unwrap();
 
@@ -813,7 +855,7 @@ mod error_scenarios {
             .await
             .unwrap();
         let datasource = db
-            .create_datasource(org.id, "DB", DatasourceType::Postgres, "conn", user.id)
+            .create_datasource(org.id, "DB", DatasourceType::Postgres, "conn", user.id, None)
             .await
             .unwrap();
         let query = db
@@ -827,7 +869,9 @@ mod error_scenarios {
                 &json!([]),
                 30,
                 1000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -856,6 +900,7 @@ mod error_scenarios {
                 &json!([]),
                 true,
                 user.id,
+                None,
             )
             .await
             .unwrap();
@@ -890,7 +935,7 @@ mod pagination_workflow {
             .await
             .unwrap();
         let datasource = db
-            .create_datasource(org.id, "DB", DatasourceType::Postgres, "conn", user.id)
+            .create_datasource(org.id, "DB", DatasourceType::Postgres, "conn", user.id, None)
             .await
             .unwrap();
 
@@ -906,7 +951,9 @@ mod pagination_workflow {
                 &json!([]),
                 30,
                 1000,
+                None,
                 user.id,
+                None,
             )
             .await
             .unwrap();